@@ -0,0 +1,204 @@
+//! Where the shipped `data/` folder and the Node export engine live. Release
+//! builds carry them as Tauri resources under `engine/` (see `bundle.resources`
+//! in `tauri.conf.json`): `data/` plus the engine `npm run build:engine` writes
+//! to `build/engine/`, with the scripts bundled into plain `.mjs`. Debug builds
+//! fall back to the repo checkout they were built from and run the `.ts`
+//! scripts through tsx.
+
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::storage_paths::StorageError;
+
+/// Folder below the Tauri resource dir that mirrors the repo root.
+pub const ENGINE_RESOURCE_DIR: &str = "engine";
+
+/// Folders every engine root must contain.
+const REQUIRED_DATA: [&str; 2] = ["data/bands", "data/musicians"];
+
+/// Scripts the app runs, without extension.
+const ENGINE_SCRIPTS: [&str; 2] = ["desktop_export", "desktop_preview"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResourceOrigin {
+    Bundled,
+    DevCheckout,
+}
+
+impl ResourceOrigin {
+    /// Bundled engines ship prebuilt scripts; the checkout runs the sources.
+    fn script_extension(self) -> &'static str {
+        match self {
+            ResourceOrigin::Bundled => "mjs",
+            ResourceOrigin::DevCheckout => "ts",
+        }
+    }
+}
+
+/// The folder the export scripts run in. It holds `data/`, `scripts/` and the
+/// packages the scripts import.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineRoot {
+    pub root: PathBuf,
+    pub origin: ResourceOrigin,
+}
+
+impl EngineRoot {
+    pub fn data_dir(&self) -> PathBuf {
+        self.root.join("data")
+    }
+
+    /// `scripts/<name>.mjs` or `scripts/<name>.ts`, depending on the origin.
+    pub fn script(&self, name: &str) -> PathBuf {
+        script_path(&self.root, self.origin, name)
+    }
+
+    /// Arguments for `node` that run the script; only the checkout needs tsx.
+    pub fn node_args(&self, name: &str) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        if self.origin == ResourceOrigin::DevCheckout {
+            args.push("--import".into());
+            args.push("tsx".into());
+        }
+        args.push(self.script(name).into_os_string());
+        args
+    }
+}
+
+fn script_path(root: &Path, origin: ResourceOrigin, name: &str) -> PathBuf {
+    root.join("scripts")
+        .join(format!("{}.{}", name, origin.script_extension()))
+}
+
+/// Places to look, in order: the bundled resources, then (debug builds only)
+/// the repo checkout.
+pub fn engine_candidates(resource_dir: Option<PathBuf>) -> Vec<(PathBuf, ResourceOrigin)> {
+    let mut candidates = Vec::new();
+    if let Some(dir) = resource_dir {
+        candidates.push((dir.join(ENGINE_RESOURCE_DIR), ResourceOrigin::Bundled));
+    }
+    if cfg!(debug_assertions) {
+        let checkout = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../..");
+        candidates.push((
+            checkout.canonicalize().unwrap_or(checkout),
+            ResourceOrigin::DevCheckout,
+        ));
+    }
+    candidates
+}
+
+fn missing_parts(root: &Path, origin: ResourceOrigin) -> Vec<String> {
+    let data = REQUIRED_DATA.into_iter().map(PathBuf::from);
+    let scripts = ENGINE_SCRIPTS
+        .into_iter()
+        .map(|name| script_path(Path::new(""), origin, name));
+    data.chain(scripts)
+        .filter(|part| !root.join(part).exists())
+        .map(|part| part.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+/// The first complete candidate. The error names what each candidate lacks.
+pub fn locate_engine(candidates: &[(PathBuf, ResourceOrigin)]) -> Result<EngineRoot, StorageError> {
+    let mut problems = Vec::new();
+    for (root, origin) in candidates {
+        let missing = missing_parts(root, *origin);
+        if missing.is_empty() {
+            return Ok(EngineRoot {
+                root: root.clone(),
+                origin: *origin,
+            });
+        }
+        problems.push(format!("{} lacks {}", root.display(), missing.join(", ")));
+    }
+    if problems.is_empty() {
+        problems.push("no resource folder is available".into());
+    }
+    Err(StorageError::Resolve(format!(
+        "Bundled data and export engine not found; reinstall StagePilot ({})",
+        problems.join("; ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{locate_engine, ResourceOrigin, ENGINE_SCRIPTS, REQUIRED_DATA};
+    use crate::test_support::TempDir;
+    use std::ffi::OsString;
+    use std::fs;
+
+    /// `engine/` lacks the scripts; `repo/` is a complete checkout.
+    fn candidates() -> TempDir {
+        let base = TempDir::new("res");
+        fs::create_dir_all(base.join("engine/data/bands")).expect("partial bundle");
+        for part in REQUIRED_DATA {
+            fs::create_dir_all(base.join("repo").join(part)).expect("data");
+        }
+        for name in ENGINE_SCRIPTS {
+            base.write(format!("repo/scripts/{}.ts", name), "");
+        }
+        base
+    }
+
+    #[test]
+    fn prefers_first_complete_candidate() {
+        let base = candidates();
+        let found = locate_engine(&[
+            (base.join("engine"), ResourceOrigin::Bundled),
+            (base.join("repo"), ResourceOrigin::DevCheckout),
+        ])
+        .expect("checkout");
+        assert_eq!(found.origin, ResourceOrigin::DevCheckout);
+        assert_eq!(found.data_dir(), base.join("repo/data"));
+    }
+
+    #[test]
+    fn error_names_missing_parts() {
+        let base = candidates();
+        let err = locate_engine(&[(base.join("engine"), ResourceOrigin::Bundled)])
+            .expect_err("incomplete");
+        assert!(format!("{:?}", err).contains("scripts/desktop_export.mjs"));
+    }
+
+    #[test]
+    fn bundled_engine_needs_prebuilt_scripts() {
+        let base = candidates();
+        for part in REQUIRED_DATA {
+            fs::create_dir_all(base.join("engine").join(part)).expect("data");
+        }
+        // Sources alone do not make a bundled engine.
+        base.write("engine/scripts/desktop_export.ts", "");
+        base.write("engine/scripts/desktop_preview.ts", "");
+        assert!(locate_engine(&[(base.join("engine"), ResourceOrigin::Bundled)]).is_err());
+
+        for name in ENGINE_SCRIPTS {
+            base.write(format!("engine/scripts/{}.mjs", name), "");
+        }
+        let found =
+            locate_engine(&[(base.join("engine"), ResourceOrigin::Bundled)]).expect("bundle");
+        assert_eq!(
+            found.node_args("desktop_export"),
+            vec![OsString::from(
+                base.join("engine/scripts/desktop_export.mjs")
+            )]
+        );
+    }
+
+    #[test]
+    fn checkout_runs_sources_through_tsx() {
+        let base = candidates();
+        let found =
+            locate_engine(&[(base.join("repo"), ResourceOrigin::DevCheckout)]).expect("checkout");
+        assert_eq!(
+            found.node_args("desktop_preview"),
+            vec![
+                OsString::from("--import"),
+                OsString::from("tsx"),
+                OsString::from(base.join("repo/scripts/desktop_preview.ts")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Bands, musicians, contacts and presets resolved across two layers: the
+//! shipped `data/` folder and the user library (`library/bands.json`,
+//! `musicians.json`, `contacts.json`, `presets.json`). A library record with
+//! the id of a shipped one overrides it field by field, so fields the library
+//! UI does not edit (presets, `bandLeader`, ...) keep their shipped values.
+//! The export engine (`src/infra/fs/repo.ts`) layers the same files.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::export_status::collect_files;
+use crate::storage_paths::StorageError;
+
+/// Snake_case keys written by older library versions, with their current name.
+const LEGACY_KEYS: [(&str, &str); 5] = [
+    ("role_constraints", "roleConstraints"),
+    ("default_lineup", "defaultLineup"),
+    ("band_leader", "bandLeader"),
+    ("default_contact_id", "defaultContactId"),
+    ("default_roles", "defaultRoles"),
+];
+const LEGACY_MEMBER_KEYS: [(&str, &str); 2] =
+    [("musician_id", "musicianId"), ("is_default", "isDefault")];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordOrigin {
+    Shipped,
+    User,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayeredRecord {
+    pub id: String,
+    pub origin: RecordOrigin,
+    /// A user record that replaces fields of a shipped record with the same id.
+    pub overrides_shipped: bool,
+    /// Merged JSON with camelCase keys.
+    pub value: Value,
+}
+
+impl LayeredRecord {
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Library `name`, else `lastName firstName` as shipped musicians are listed.
+    pub fn display_name(&self) -> String {
+        if let Some(name) = self.text("name") {
+            return name.to_string();
+        }
+        let first = self.text("firstName").unwrap_or("");
+        let last = self.text("lastName").unwrap_or("");
+        format!("{} {}", last, first).trim().to_string()
+    }
+
+    /// Roles a musician plays: library `defaultRoles`, else the shipped folder.
+    pub fn roles(&self) -> Vec<String> {
+        let roles: Vec<String> = self
+            .value
+            .get("defaultRoles")
+            .and_then(|v| v.as_array())
+            .map(|roles| {
+                roles
+                    .iter()
+                    .filter_map(|role| role.as_str())
+                    .map(|role| role.trim().to_lowercase())
+                    .filter(|role| !role.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !roles.is_empty() {
+            return roles;
+        }
+        self.text("group")
+            .map(|group| vec![group.to_lowercase()])
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DataRepository {
+    bands: Vec<LayeredRecord>,
+    musicians: Vec<LayeredRecord>,
+    contacts: Vec<LayeredRecord>,
+    presets: Vec<LayeredRecord>,
+}
+
+impl DataRepository {
+    /// Reads both layers. Invalid JSON in either layer is an error; a missing
+    /// folder or library file is an empty layer.
+    pub fn load(data_root: &Path, library_dir: &Path) -> Result<Self, StorageError> {
+        let presets_dir = data_root.join("assets").join("presets");
+        let mut shipped_presets = read_shipped(&presets_dir.join("groups"), false)?;
+        shipped_presets.extend(read_shipped(&presets_dir.join("monitors"), false)?);
+        Ok(Self {
+            bands: layer(
+                read_shipped(&data_root.join("bands"), false)?,
+                read_library(&library_dir.join("bands.json"))?,
+            ),
+            musicians: layer(
+                read_shipped(&data_root.join("musicians"), true)?,
+                read_library(&library_dir.join("musicians.json"))?,
+            ),
+            contacts: layer(
+                read_shipped(&data_root.join("contacts"), false)?,
+                read_library(&library_dir.join("contacts.json"))?,
+            ),
+            presets: layer(
+                shipped_presets,
+                read_library(&library_dir.join("presets.json"))?,
+            ),
+        })
+    }
+
+    pub fn bands(&self) -> &[LayeredRecord] {
+        &self.bands
+    }
+
+    pub fn musicians(&self) -> &[LayeredRecord] {
+        &self.musicians
+    }
+
+    pub fn presets(&self) -> &[LayeredRecord] {
+        &self.presets
+    }
+
+    /// A band by id, or by code ignoring case, as project `bandRef`s use both.
+    pub fn band(&self, reference: &str) -> Option<&LayeredRecord> {
+        let reference = reference.trim();
+        if let Some(band) = self.bands.iter().find(|band| band.id == reference) {
+            return Some(band);
+        }
+        self.bands.iter().find(|band| {
+            band.id.eq_ignore_ascii_case(reference)
+                || band
+                    .text("code")
+                    .is_some_and(|code| code.eq_ignore_ascii_case(reference))
+        })
+    }
+
+    pub fn musician(&self, id: &str) -> Option<&LayeredRecord> {
+        self.musicians.iter().find(|musician| musician.id == id)
+    }
+
+    pub fn contact(&self, id: &str) -> Option<&LayeredRecord> {
+        self.contacts.iter().find(|contact| contact.id == id)
+    }
+}
+
+fn read_json(path: &Path) -> Result<Value, StorageError> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| StorageError::Resolve(format!("Invalid JSON in {}: {e}", path.display())))
+}
+
+/// Every `*.json` below `dir`. Musicians live in `<role>/<id>.json`; with
+/// `group_folders` the role folder becomes `group` when the file names none.
+fn read_shipped(dir: &Path, group_folders: bool) -> Result<Vec<Value>, StorageError> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    collect_files(dir, &mut files);
+    files.retain(|path| path.extension().and_then(|s| s.to_str()) == Some("json"));
+    files.sort();
+    let mut records = Vec::new();
+    for path in files {
+        let mut value = read_json(&path)?;
+        let folder = path
+            .parent()
+            .filter(|parent| group_folders && *parent != dir)
+            .and_then(|parent| parent.file_name())
+            .and_then(|name| name.to_str());
+        if let (Some(fields), Some(folder)) = (value.as_object_mut(), folder) {
+            fields
+                .entry("group")
+                .or_insert_with(|| Value::String(folder.to_string()));
+        }
+        records.push(value);
+    }
+    Ok(records)
+}
+
+fn read_library(path: &Path) -> Result<Vec<Value>, StorageError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    match read_json(path)? {
+        Value::Array(items) => Ok(items.into_iter().map(normalize_keys).collect()),
+        _ => Err(StorageError::Resolve(format!(
+            "{} is not a JSON list",
+            path.display()
+        ))),
+    }
+}
+
+fn rename_keys(fields: &mut Map<String, Value>, keys: &[(&str, &str)]) {
+    for (legacy, current) in keys {
+        if let Some(value) = fields.remove(*legacy) {
+            fields.entry(*current).or_insert(value);
+        }
+    }
+}
+
+fn normalize_keys(mut value: Value) -> Value {
+    if let Some(fields) = value.as_object_mut() {
+        rename_keys(fields, &LEGACY_KEYS);
+        if let Some(Value::Array(members)) = fields.get_mut("members") {
+            for member in members.iter_mut().filter_map(|m| m.as_object_mut()) {
+                rename_keys(member, &LEGACY_MEMBER_KEYS);
+            }
+        }
+    }
+    value
+}
+
+/// `null` in the library means "not set", not "clear the shipped value".
+fn override_fields(base: &mut Value, user: Value) {
+    if let (Some(base), Value::Object(fields)) = (base.as_object_mut(), user) {
+        base.extend(fields.into_iter().filter(|(_, v)| !v.is_null()));
+    }
+}
+
+/// One record as `load` resolves it: the library entry, with legacy keys
+/// renamed, layered over the shipped record.
+pub(crate) fn resolve_record(shipped: Option<Value>, library: Option<Value>) -> Option<Value> {
+    match (shipped, library.map(normalize_keys)) {
+        (Some(mut base), Some(user)) => {
+            override_fields(&mut base, user);
+            Some(base)
+        }
+        (base, user) => base.or(user),
+    }
+}
+
+fn record_id(value: &Value) -> Option<String> {
+    value
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+fn layer(shipped: Vec<Value>, user: Vec<Value>) -> Vec<LayeredRecord> {
+    let mut records: Vec<LayeredRecord> = Vec::new();
+    for value in shipped {
+        let Some(id) = record_id(&value) else {
+            continue;
+        };
+        if records.iter().any(|record| record.id == id) {
+            continue;
+        }
+        records.push(LayeredRecord {
+            id,
+            origin: RecordOrigin::Shipped,
+            overrides_shipped: false,
+            value,
+        });
+    }
+    for value in user {
+        let Some(id) = record_id(&value) else {
+            continue;
+        };
+        match records.iter_mut().find(|record| record.id == id) {
+            Some(record) => {
+                override_fields(&mut record.value, value);
+                record.overrides_shipped |= record.origin == RecordOrigin::Shipped;
+                record.origin = RecordOrigin::User;
+            }
+            None => records.push(LayeredRecord {
+                id,
+                origin: RecordOrigin::User,
+                overrides_shipped: false,
+                value,
+            }),
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataRepository, RecordOrigin};
+    use crate::test_support::TempDir;
+
+    /// Shipped band `band` and bassist `b1`; the library overrides `band`
+    /// (snake_case, as older builds wrote it) and adds `mine` and `u1`.
+    fn layers() -> TempDir {
+        let base = TempDir::new("repo");
+        base.write(
+            "data/bands/band.json",
+            r#"{"id":"band","code":"BND","name":"Band","bandLeader":"b1"}"#,
+        );
+        base.write(
+            "data/musicians/bass/b1.json",
+            r#"{"id":"b1","firstName":"Jan","lastName":"Novak"}"#,
+        );
+        base.write(
+            "library/bands.json",
+            r#"[{"id":"band","name":"Renamed","description":null,"members":[{"musician_id":"u1","roles":["keys"],"is_default":true}]},
+               {"id":"mine","code":"MINE","name":"Mine"}]"#,
+        );
+        base.write(
+            "library/musicians.json",
+            r#"[{"id":"u1","name":"Eva","default_roles":["Keys"]}]"#,
+        );
+        base.write(
+            "data/assets/presets/groups/bass/di.json",
+            r#"{"id":"di","type":"preset","label":"DI"}"#,
+        );
+        base.write(
+            "data/assets/presets/monitors/wedge.json",
+            r#"{"id":"wedge","type":"monitor"}"#,
+        );
+        base.write(
+            "library/presets.json",
+            r#"[{"id":"di","label":"DI box"},{"id":"iem","type":"monitor"}]"#,
+        );
+        base.write("data/contacts/b1.json", r#"{"id":"b1","phone":"1"}"#);
+        base.write("library/contacts.json", r#"[{"id":"u1","phone":"2"}]"#);
+        base
+    }
+
+    fn load(base: &TempDir) -> DataRepository {
+        DataRepository::load(&base.join("data"), &base.join("library")).expect("load")
+    }
+
+    #[test]
+    fn user_record_overrides_shipped_fields() {
+        let base = layers();
+        let repo = load(&base);
+        let band = repo.band("bnd").expect("shipped band by code");
+        assert_eq!(band.origin, RecordOrigin::User);
+        assert!(band.overrides_shipped);
+        assert_eq!(band.value["name"], "Renamed");
+        assert_eq!(band.value["bandLeader"], "b1");
+        assert_eq!(band.value["code"], "BND");
+    }
+
+    #[test]
+    fn legacy_snake_case_keys_are_normalized() {
+        let base = layers();
+        let repo = load(&base);
+        let band = repo.band("band").expect("band");
+        assert_eq!(band.value["members"][0]["musicianId"], "u1");
+        assert_eq!(band.value["members"][0]["isDefault"], true);
+    }
+
+    #[test]
+    fn user_only_record_is_not_an_override() {
+        let base = layers();
+        let repo = load(&base);
+        let mine = repo.band("mine").expect("user band");
+        assert_eq!(mine.origin, RecordOrigin::User);
+        assert!(!mine.overrides_shipped);
+    }
+
+    #[test]
+    fn musician_name_and_roles_come_from_either_layer() {
+        let base = layers();
+        let repo = load(&base);
+        let shipped = repo.musician("b1").expect("shipped musician");
+        assert_eq!(shipped.origin, RecordOrigin::Shipped);
+        assert_eq!(shipped.display_name(), "Novak Jan");
+        assert_eq!(shipped.roles(), vec!["bass"]);
+        let user = repo.musician("u1").expect("user musician");
+        assert_eq!(user.display_name(), "Eva");
+        assert_eq!(user.roles(), vec!["keys"]);
+    }
+
+    #[test]
+    fn presets_and_contacts_are_layered_like_bands() {
+        let base = layers();
+        let repo = load(&base);
+        let ids: Vec<&str> = repo.presets().iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["di", "wedge", "iem"]);
+        let di = &repo.presets()[0];
+        assert!(di.overrides_shipped);
+        assert_eq!(di.value["label"], "DI box");
+        assert_eq!(di.value["type"], "preset");
+        // Preset folders are not musician roles.
+        assert!(di.value.get("group").is_none());
+        assert_eq!(repo.contact("b1").expect("shipped").value["phone"], "1");
+        assert_eq!(
+            repo.contact("u1").expect("library").origin,
+            RecordOrigin::User
+        );
+    }
+
+    #[test]
+    fn invalid_library_json_is_an_error() {
+        let base = layers();
+        base.write("library/musicians.json", "{");
+        assert!(DataRepository::load(&base.join("data"), &base.join("library")).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! Whether a project's latest export still matches what it was rendered
+//! from: the project itself, and the band, musician, contact and preset
+//! records of the shipped `data/` folder and the user library.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data_repository::{DataRepository, LayeredRecord};
+use crate::project_diff::lineup_musician_ids;
+use crate::project_migrations::{migrate_project_json, PROJECT_SCHEMA_VERSION_KEY};
+use crate::project_versions::{
+    list_versions, read_version, update_version_meta, version_dir, ProjectSnapshotMeta,
+};
+use crate::storage_paths::{safe_join, sha256_hex, StorageError};
+
+/// Stamps that change on every save without changing the document.
+const VOLATILE_PROJECT_KEYS: [&str; 3] = ["createdAt", "updatedAt", PROJECT_SCHEMA_VERSION_KEY];
+
+/// Folders under `data/assets/` that feed the PDF for every band. Presets are
+/// hashed as resolved records instead, as the library can override them.
+const SHARED_ASSET_DIRS: [&str; 2] = ["catalog", "templates"];
+
+/// Input hashes recorded in a snapshot's `meta.json` at export time.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceHashes {
+    pub project: String,
+    pub library: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportState {
+    NeverExported,
+    UpToDate,
+    ProjectChanged,
+    LibraryChanged,
+    /// Inputs match, but `exports/<slug>.pdf` is missing or differs from the
+    /// latest snapshot's PDF (e.g. the export was locked by a PDF viewer).
+    ExportFileOutdated,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportStatus {
+    pub state: ExportState,
+    pub version_id: Option<String>,
+    pub generated_at: Option<String>,
+    pub project_changed: bool,
+    /// `None` when the snapshot predates recorded library hashes.
+    pub library_changed: Option<bool>,
+    pub export_file_outdated: bool,
+}
+
+/// Hash of the project content, ignoring timestamps and key order. Legacy
+/// shapes are upgraded first so a migrated file hashes like its original.
+pub fn project_content_hash(project: &Value) -> String {
+    let mut project = project.clone();
+    migrate_project_json(&mut project);
+    if let Some(fields) = project.as_object_mut() {
+        for key in VOLATILE_PROJECT_KEYS {
+            fields.remove(key);
+        }
+    }
+    sha256_hex(project.to_string().as_bytes())
+}
+
+pub(crate) fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+/// Hash of the library records a project's PDF depends on, as
+/// `DataRepository` resolves them (shipped `data/` with the user library on
+/// top): its band, the musicians and contacts it or its band refers to, every
+/// preset, and the shared catalog and templates under `data/assets/`.
+pub fn library_hash(data_root: &Path, repository: &DataRepository, project: &Value) -> String {
+    let mut digests: BTreeMap<String, String> = BTreeMap::new();
+    let mut record = |kind: &str, record: &LayeredRecord| {
+        digests.insert(
+            format!("{}/{}", kind, record.id),
+            sha256_hex(record.value.to_string().as_bytes()),
+        );
+    };
+    let mut people: BTreeSet<String> = BTreeSet::new();
+    let text = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+
+    if let Some(band) = project
+        .get("bandRef")
+        .and_then(|v| v.as_str())
+        .and_then(|band_ref| repository.band(band_ref))
+    {
+        record("bands", band);
+        people.extend(
+            band.value
+                .get("defaultLineup")
+                .map(lineup_musician_ids)
+                .unwrap_or_default(),
+        );
+        people.extend(text(&band.value, "bandLeader"));
+        people.extend(text(&band.value, "defaultContactId"));
+    }
+    people.extend(
+        project
+            .get("lineup")
+            .map(lineup_musician_ids)
+            .unwrap_or_default(),
+    );
+    people.extend(text(project, "bandLeaderId"));
+    people.extend(text(project, "talkbackOwnerId"));
+
+    for id in &people {
+        if let Some(musician) = repository.musician(id) {
+            record("musicians", musician);
+        }
+        if let Some(contact) = repository.contact(id) {
+            record("contacts", contact);
+        }
+    }
+    for preset in repository.presets() {
+        record("presets", preset);
+    }
+
+    let mut files = Vec::new();
+    for dir in SHARED_ASSET_DIRS {
+        collect_files(&data_root.join("assets").join(dir), &mut files);
+    }
+    digests.extend(files.iter().filter_map(|path| {
+        let relative = path.strip_prefix(data_root).ok()?;
+        let bytes = fs::read(path).ok()?;
+        Some((
+            relative.to_string_lossy().replace('\\', "/"),
+            sha256_hex(&bytes),
+        ))
+    }));
+    let listing: String = digests
+        .iter()
+        .map(|(path, digest)| format!("{}\n{}\n", path, digest))
+        .collect();
+    sha256_hex(listing.as_bytes())
+}
+
+/// Records the input hashes of a freshly exported snapshot.
+pub fn record_source_hashes(
+    versions_root: &Path,
+    data_root: &Path,
+    repository: &DataRepository,
+    project_id: &str,
+    version_id: &str,
+) -> Result<ProjectSnapshotMeta, StorageError> {
+    let (_, project_json) = read_version(versions_root, project_id, version_id)?;
+    let project: Value = serde_json::from_str(&project_json)
+        .map_err(|e| StorageError::Resolve(format!("Invalid project JSON in version: {e}")))?;
+    let hashes = SourceHashes {
+        project: project_content_hash(&project),
+        library: library_hash(data_root, repository, &project),
+    };
+    update_version_meta(versions_root, project_id, version_id, |meta| {
+        meta.source_hashes = Some(hashes);
+    })
+}
+
+fn file_hash(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|bytes| sha256_hex(&bytes))
+}
+
+/// Compares `project` (the current file) with the newest export snapshot.
+pub fn export_status(
+    versions_root: &Path,
+    exports_dir: &Path,
+    data_root: &Path,
+    repository: &DataRepository,
+    project_id: &str,
+    project: &Value,
+) -> Result<ExportStatus, StorageError> {
+    let latest = list_versions(versions_root, project_id)?
+        .into_iter()
+        .find(|meta| meta.kind.is_none());
+    let Some(latest) = latest else {
+        return Ok(ExportStatus {
+            state: ExportState::NeverExported,
+            version_id: None,
+            generated_at: None,
+            project_changed: true,
+            library_changed: None,
+            export_file_outdated: true,
+        });
+    };
+
+    let recorded_project = match &latest.source_hashes {
+        Some(hashes) => hashes.project.clone(),
+        None => {
+            let (_, snapshot) = read_version(versions_root, project_id, &latest.version_id)?;
+            serde_json::from_str::<Value>(&snapshot)
+                .map(|snapshot| project_content_hash(&snapshot))
+                .unwrap_or_default()
+        }
+    };
+    let project_changed = recorded_project != project_content_hash(project);
+    let library_changed = latest
+        .source_hashes
+        .as_ref()
+        .map(|hashes| hashes.library != library_hash(data_root, repository, project));
+
+    let dir = version_dir(versions_root, project_id, &latest.version_id)?;
+    let version_pdf = latest
+        .pdf_file_name
+        .as_deref()
+        .and_then(|name| safe_join(&dir, name).ok())
+        .and_then(|path| file_hash(&path));
+    let export_pdf = project
+        .get("slug")
+        .and_then(|v| v.as_str())
+        .and_then(|slug| safe_join(exports_dir, &format!("{}.pdf", slug)).ok())
+        .and_then(|path| file_hash(&path));
+    let export_file_outdated = export_pdf.is_none() || export_pdf != version_pdf;
+
+    let state = if project_changed {
+        ExportState::ProjectChanged
+    } else if library_changed == Some(true) {
+        ExportState::LibraryChanged
+    } else if export_file_outdated {
+        ExportState::ExportFileOutdated
+    } else {
+        ExportState::UpToDate
+    };
+    Ok(ExportStatus {
+        state,
+        version_id: Some(latest.version_id),
+        generated_at: Some(latest.generated_at),
+        project_changed,
+        library_changed,
+        export_file_outdated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_status, record_source_hashes, ExportState};
+    use crate::data_repository::DataRepository;
+    use crate::test_support::TempDir;
+    use serde_json::{json, Value};
+
+    fn project() -> Value {
+        json!({"id": "p1", "slug": "gig", "bandRef": "B", "updatedAt": "2025-01-01T00:00:00.000Z"})
+    }
+
+    /// Band `B` with drummer `d1`, an unrelated `d2`, and export `v1` of `p1`
+    /// with its hashes recorded and its PDF published.
+    fn exported() -> TempDir {
+        let root = TempDir::new("status");
+        root.write(
+            "data/bands/b.json",
+            r#"{"id":"b","code":"B","defaultLineup":{"drums":"d1"}}"#,
+        );
+        root.write("data/musicians/drums/d1.json", r#"{"id":"d1"}"#);
+        root.write("data/musicians/drums/d2.json", r#"{"id":"d2"}"#);
+        root.write(
+            "data/assets/presets/groups/drums/kick.json",
+            r#"{"id":"kick"}"#,
+        );
+        root.write("versions/p1/v1/project.json", project().to_string());
+        root.write("versions/p1/v1/gig.pdf", "%PDF v1");
+        root.write(
+            "versions/p1/v1/meta.json",
+            r#"{"projectId":"p1","versionId":"v1","generatedAt":"2025-01-01T00:00:00.000Z","pdfFileName":"gig.pdf"}"#,
+        );
+        root.write("exports/gig.pdf", "%PDF v1");
+        record_source_hashes(
+            &root.join("versions"),
+            &root.join("data"),
+            &repository(&root),
+            "p1",
+            "v1",
+        )
+        .expect("record");
+        root
+    }
+
+    fn repository(root: &TempDir) -> DataRepository {
+        DataRepository::load(&root.join("data"), &root.join("library")).expect("repository")
+    }
+
+    fn state(root: &TempDir, project: &Value) -> ExportState {
+        export_status(
+            &root.join("versions"),
+            &root.join("exports"),
+            &root.join("data"),
+            &repository(root),
+            "p1",
+            project,
+        )
+        .expect("status")
+        .state
+    }
+
+    fn resaved() -> Value {
+        let mut saved = project();
+        saved["updatedAt"] = json!("2025-02-01T00:00:00.000Z");
+        saved
+    }
+
+    #[test]
+    fn resave_without_changes_is_up_to_date() {
+        let root = exported();
+        assert_eq!(state(&root, &resaved()), ExportState::UpToDate);
+    }
+
+    #[test]
+    fn unrelated_musician_change_is_ignored() {
+        let root = exported();
+        root.write("data/musicians/drums/d2.json", r#"{"id":"d2","x":1}"#);
+        assert_eq!(state(&root, &resaved()), ExportState::UpToDate);
+    }
+
+    #[test]
+    fn referenced_musician_change_is_library_changed() {
+        let root = exported();
+        root.write("data/musicians/drums/d1.json", r#"{"id":"d1","x":1}"#);
+        assert_eq!(state(&root, &resaved()), ExportState::LibraryChanged);
+    }
+
+    #[test]
+    fn library_override_of_referenced_musician_is_library_changed() {
+        let root = exported();
+        root.write("library/musicians.json", r#"[{"id":"d1","name":"Dan"}]"#);
+        assert_eq!(state(&root, &resaved()), ExportState::LibraryChanged);
+    }
+
+    #[test]
+    fn library_preset_is_library_changed() {
+        let root = exported();
+        root.write("library/presets.json", r#"[{"id":"snare"}]"#);
+        assert_eq!(state(&root, &resaved()), ExportState::LibraryChanged);
+    }
+
+    #[test]
+    fn unrelated_library_musician_is_ignored() {
+        let root = exported();
+        root.write("library/musicians.json", r#"[{"id":"d2","name":"Dee"}]"#);
+        assert_eq!(state(&root, &resaved()), ExportState::UpToDate);
+    }
+
+    #[test]
+    fn project_edit_wins_over_library_change() {
+        let root = exported();
+        root.write("data/musicians/drums/d1.json", r#"{"id":"d1","x":1}"#);
+        let mut saved = resaved();
+        saved["eventVenue"] = json!("Roxy");
+        assert_eq!(state(&root, &saved), ExportState::ProjectChanged);
+    }
+
+    #[test]
+    fn replaced_export_file_is_outdated() {
+        let root = exported();
+        root.write("exports/gig.pdf", "%PDF other");
+        assert_eq!(state(&root, &project()), ExportState::ExportFileOutdated);
+    }
+}
@@ -1,21 +1,102 @@
+mod app_resources;
+mod data_repository;
+mod export_status;
+mod project_bundle;
+mod project_diff;
+mod project_fork;
+mod project_index;
+mod project_migrations;
+mod project_model;
+mod project_naming;
+mod project_query;
+mod project_rename;
+mod project_revision;
+mod project_trash;
+mod project_versions;
+mod storage_backup;
+mod storage_doctor;
+mod storage_journal;
+mod storage_lock;
+mod storage_migrations;
+mod storage_move;
 mod storage_paths;
-
+#[cfg(test)]
+mod test_support;
+mod timestamps;
+mod version_annotations;
+mod version_blobs;
+mod version_retention;
+
+use app_resources::{engine_candidates, locate_engine, EngineRoot};
+use data_repository::{DataRepository, LayeredRecord, RecordOrigin};
+use export_status::{export_status, record_source_hashes, ExportStatus};
+use project_bundle::{
+    create_bundle, merge_library_writes, plan_import, preview_bundle, read_bundle, BundleManifest,
+    BundlePreview, ImportedRecord, RecordResolution, BUNDLE_EXTENSION, LIBRARY_BANDS,
+};
+use project_diff::{diff_projects, ProjectDiff, CURRENT_PROJECT_STATE};
+use project_fork::{fork_project, ForkOrigin};
+use project_index::{refresh_project_index, update_project_index, ProjectIndex, ProjectIndexEntry};
+use project_migrations::{
+    migrate_project_json, normalize_lineup_keys, ProjectMigrationReport, LIFECYCLE_KEYS,
+    PROJECT_SCHEMA_VERSION, PROJECT_SCHEMA_VERSION_KEY,
+};
+use project_model::{validate_project, ProjectFieldError};
+use project_naming::{
+    format_project_display_name, format_project_slug, generate_uuid_v7, unique_project_name,
+    NamingBand,
+};
+use project_query::{query_entries, ProjectQuery};
+use project_rename::{
+    carry_project_artifacts, rename_project as rename_project_files, RenameOutcome,
+};
+use project_revision::{check_revision, project_revision, ProjectConflict};
+use project_trash::{
+    list_trash as list_trash_entries, move_project_to_trash, purge_expired_trash as purge_expired,
+    purge_trash as purge_trash_entries, restore_from_trash, TrashEntry, TRASH_RETENTION_DAYS,
+};
+use project_versions::{
+    create_restore_point, list_versions, read_version, set_version_sent as flag_version_sent,
+    version_dir, version_pdf_path, ProjectSnapshotMeta,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
     process::Command,
     sync::mpsc,
+    time::Duration,
+};
+use storage_backup::{create_backup, read_backup_manifest, restore_backup};
+use storage_doctor::{
+    quarantine_project_file as quarantine_file_at, repair_storage as repair_storage_at,
+    scan_storage as scan_storage_at, StorageRepairReport, StorageScanReport,
 };
+use storage_journal::FileJournal;
+use storage_lock::lock_storage_file;
+use storage_migrations::FailedMigration;
+use storage_move::{copy_storage_tree, remove_moved_entries, StorageMoveReport};
 use storage_paths::{
-    atomic_write_bytes, ensure_user_storage, exports_dir, library_dir as storage_library_dir,
-    maybe_wipe_storage_for_dev, project_json_path, projects_dir as storage_projects_dir,
-    sanitize_id_to_filename, temp_dir as storage_temp_dir, user_storage_root,
-    versions_dir as storage_versions_dir, StorageError,
+    atomic_write_bytes, ensure_storage_at, ensure_user_storage, ensure_writable_storage,
+    export_files_for_slug, exports_dir, is_read_only, library_dir as storage_library_dir,
+    maybe_wipe_storage_for_dev, now_iso, project_json_path, projects_dir as storage_projects_dir,
+    resolve_storage_root, safe_join, sanitize_id_to_filename, set_configured_storage_root,
+    temp_dir as storage_temp_dir, update_storage_meta, user_storage_root,
+    versions_dir as storage_versions_dir, StorageError, StorageRootSource, STORAGE_SCHEMA_VERSION,
 };
+use tauri::Manager;
 use tauri_plugin_dialog::DialogExt;
+use timestamps::{stamp_project, Clock, SystemClock};
+use version_annotations::{
+    annotate_version as annotate_version_meta, distribution_log, record_sent, DistributionEntry,
+};
+use version_blobs::{
+    collect_garbage, dedupe_versions, ingest_version, read_manifest, relink_versions,
+    VersionManifest,
+};
+use version_retention::{prune_versions as prune_version_dirs, PruneReport, VersionRetention};
 
 #[derive(Debug, Serialize)]
 struct ApiError {
@@ -23,6 +104,52 @@ struct ApiError {
     message: String,
     export_pdf_path: Option<String>,
     version_pdf_path: Option<String>,
+    /// Current on-disk project for `PROJECT_CONFLICT`.
+    conflict: Option<Box<ProjectConflict>>,
+    /// Rejected fields for `PROJECT_INVALID`.
+    field_errors: Option<Box<[ProjectFieldError]>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageInfo {
+    root: String,
+    root_source: StorageRootSource,
+    schema_version: u32,
+    supported_schema_version: u32,
+    read_only: bool,
+    created_at: String,
+    last_migrated_at: Option<String>,
+    failed_migration: Option<FailedMigration>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageMoveResult {
+    root: String,
+    report: StorageMoveReport,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupResult {
+    archive_path: String,
+    file_count: usize,
+    total_bytes: u64,
+    schema_version: u32,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreResult {
+    root: String,
+    file_count: usize,
+    schema_version: u32,
+    previous_root: Option<String>,
+    /// Folders kept from the replaced storage because the backup lacks them.
+    carried_over: Vec<String>,
+    warnings: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -44,38 +171,68 @@ struct BandOption {
     id: String,
     name: String,
     code: Option<String>,
+    origin: RecordOrigin,
 }
 
+/// Library bands are camelCase on disk; the aliases read files written before.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 struct LibraryBand {
     id: String,
     name: String,
+    #[serde(default)]
     code: String,
     description: Option<String>,
+    #[serde(default)]
+    band_leader: Option<String>,
+    #[serde(default)]
+    default_contact_id: Option<String>,
+    #[serde(default)]
     constraints: HashMap<String, RoleCountConstraint>,
+    #[serde(alias = "role_constraints")]
     role_constraints: Option<Value>,
+    #[serde(alias = "default_lineup")]
     default_lineup: Option<Value>,
+    #[serde(default)]
     members: Vec<LibraryBandMember>,
+    #[serde(default)]
     contacts: Vec<LibraryContact>,
+    #[serde(default)]
     messages: Vec<LibraryMessage>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 struct LibraryBandMember {
+    #[serde(alias = "musician_id")]
     musician_id: String,
+    #[serde(default)]
     roles: Vec<String>,
+    #[serde(default, alias = "is_default")]
     is_default: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 struct LibraryMusician {
     id: String,
     name: String,
     gender: Option<String>,
+    #[serde(default, alias = "default_roles")]
     default_roles: Vec<String>,
     notes: Option<String>,
 }
 
+/// A band or musician with the layer it resolved from.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Layered<T> {
+    #[serde(flatten)]
+    record: T,
+    origin: RecordOrigin,
+    overrides_shipped: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct LibraryInstrument {
     id: String,
@@ -140,6 +297,9 @@ struct ExportPdfResult {
     export_updated: bool,
     version_id: String,
     version_path: String,
+    /// Same project JSON and PDF bytes as the previous export.
+    #[serde(default)]
+    identical_to_previous: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -157,17 +317,7 @@ fn normalize_default_lineup_keys(default_lineup: Option<Value>) -> Option<Value>
     let Some(Value::Object(mut lineup)) = default_lineup else {
         return default_lineup;
     };
-
-    if let Some(value) = lineup.remove("lead_vocs") {
-        lineup.insert("vocs".to_string(), value);
-    } else if let Some(value) = lineup.remove("lead_voc") {
-        lineup.insert("vocs".to_string(), value);
-    }
-
-    if let Some(value) = lineup.remove("vocs") {
-        lineup.insert("vocs".to_string(), value);
-    }
-
+    normalize_lineup_keys(&mut lineup);
     Some(Value::Object(lineup))
 }
 
@@ -195,27 +345,56 @@ fn infer_monitoring_default_from_ref(monitor_ref: &str) -> Value {
     })
 }
 
-fn resolve_repo_root() -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("..")
-        .join("..")
-        .join("..")
+/// Shipped data and export scripts: bundled resources, or the repo checkout
+/// in debug builds.
+fn engine_root(app: &tauri::AppHandle) -> Result<EngineRoot, ApiError> {
+    locate_engine(&engine_candidates(app.path().resource_dir().ok())).map_err(|err| {
+        map_storage_error(err, "RESOURCES_MISSING", "Failed to locate app resources")
+    })
+}
+
+fn data_root(app: &tauri::AppHandle) -> Result<PathBuf, ApiError> {
+    Ok(engine_root(app)?.data_dir())
 }
 
 fn map_storage_error(err: StorageError, code: &str, context: &str) -> ApiError {
+    let mut code = code.to_string();
     let message = match err {
         StorageError::Io(e) => format!("{} ({})", context, e),
         StorageError::Resolve(msg) => format!("{} ({})", context, msg),
         StorageError::InvalidSchema(schema) => format!(
-            "{} (Unsupported user storage schemaVersion {}. Please update StagePilot.)",
+            "{} (No migration available for user storage schemaVersion {}.)",
             context, schema
         ),
+        StorageError::Locked(path) => {
+            code = "STORAGE_LOCKED".into();
+            format!(
+                "{} ({} is being modified by another StagePilot window or process. Retry in a moment.)",
+                context, path
+            )
+        }
+        StorageError::MigrationFailed(reason) => {
+            code = "STORAGE_READ_ONLY".into();
+            format!(
+                "{} (Upgrading user storage failed and it was rolled back; it stays read-only until a StagePilot update: {})",
+                context, reason
+            )
+        }
+        StorageError::ReadOnly(schema) => {
+            code = "STORAGE_READ_ONLY".into();
+            format!(
+                "{} (User storage schemaVersion {} was written by a newer StagePilot and is read-only. Please update StagePilot.)",
+                context, schema
+            )
+        }
     };
     ApiError {
-        code: code.into(),
+        code,
         message,
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     }
 }
 
@@ -225,9 +404,23 @@ fn map_io_error(err: std::io::Error, code: &str, message: &str) -> ApiError {
         message: format!("{} ({})", message, err),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     }
 }
 
+fn ensure_writable(app: &tauri::AppHandle, code: &str) -> Result<(), ApiError> {
+    ensure_writable_storage(app)
+        .map(|_| ())
+        .map_err(|err| map_storage_error(err, code, "Failed to prepare user storage for writing"))
+}
+
+fn ensured_storage_root(app: &tauri::AppHandle, code: &str) -> Result<PathBuf, ApiError> {
+    ensure_user_storage(app)
+        .and_then(|_| user_storage_root(app))
+        .map_err(|err| map_storage_error(err, code, "Failed to resolve user storage root"))
+}
+
 fn resolve_project_path_by_id(
     app: &tauri::AppHandle,
     project_id: &str,
@@ -257,6 +450,65 @@ fn library_file(app: &tauri::AppHandle, file_name: &str) -> Result<PathBuf, ApiE
     Ok(library_dir(app)?.join(file_name))
 }
 
+/// Shipped bands and musicians with the user library layered on top.
+fn data_repository(app: &tauri::AppHandle, code: &str) -> Result<DataRepository, ApiError> {
+    DataRepository::load(&data_root(app)?, &library_dir(app)?)
+        .map_err(|err| map_storage_error(err, code, "Failed to load bands and musicians"))
+}
+
+fn layered<T: for<'de> Deserialize<'de>>(
+    record: &LayeredRecord,
+    value: Value,
+    code: &str,
+) -> Result<Layered<T>, ApiError> {
+    let parsed = serde_json::from_value(value).map_err(|err| ApiError {
+        code: code.into(),
+        message: format!("Invalid record '{}' ({})", record.id, err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    Ok(Layered {
+        record: parsed,
+        origin: record.origin,
+        overrides_shipped: record.overrides_shipped,
+    })
+}
+
+fn library_band(record: &LayeredRecord, code: &str) -> Result<Layered<LibraryBand>, ApiError> {
+    layered(record, record.value.clone(), code)
+}
+
+/// Shipped musicians have no `name`/`defaultRoles`; they are derived.
+fn library_musician(
+    record: &LayeredRecord,
+    code: &str,
+) -> Result<Layered<LibraryMusician>, ApiError> {
+    let mut value = record.value.clone();
+    if let Some(fields) = value.as_object_mut() {
+        fields.insert("name".into(), Value::String(record.display_name()));
+        fields.insert("defaultRoles".into(), serde_json::json!(record.roles()));
+    }
+    layered(record, value, code)
+}
+
+/// Leaves out records that do not parse, so one malformed record does not
+/// fail the whole library listing. Each one is logged with its id.
+fn readable_records<T>(
+    records: impl Iterator<Item = Result<Layered<T>, ApiError>>,
+) -> Vec<Layered<T>> {
+    records
+        .filter_map(|record| match record {
+            Ok(record) => Some(record),
+            Err(err) => {
+                eprintln!("[library] skipped record: {}", err.message);
+                None
+            }
+        })
+        .collect()
+}
+
 fn load_library_list<T: for<'de> Deserialize<'de>>(
     app: &tauri::AppHandle,
     file_name: &str,
@@ -277,6 +529,8 @@ fn load_library_list<T: for<'de> Deserialize<'de>>(
         message: format!("Invalid {} JSON ({})", file_name, err),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })
 }
 
@@ -300,6 +554,8 @@ fn load_library_map<T: for<'de> Deserialize<'de>>(
         message: format!("Invalid {} JSON ({})", file_name, err),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })
 }
 
@@ -308,6 +564,7 @@ fn save_library_map<T: Serialize>(
     file_name: &str,
     items: &HashMap<String, T>,
 ) -> Result<(), ApiError> {
+    ensure_writable(app, "LIBRARY_WRITE_FAILED")?;
     let path = library_file(app, file_name)?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|err| {
@@ -323,6 +580,8 @@ fn save_library_map<T: Serialize>(
         message: format!("Failed to serialize {} ({})", file_name, err),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })?;
     atomic_write_bytes(&path, &json).map_err(|err| {
         map_storage_error(
@@ -338,6 +597,7 @@ fn save_library_list<T: Serialize>(
     file_name: &str,
     items: &Vec<T>,
 ) -> Result<(), ApiError> {
+    ensure_writable(app, "LIBRARY_WRITE_FAILED")?;
     let path = library_file(app, file_name)?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|err| {
@@ -353,6 +613,8 @@ fn save_library_list<T: Serialize>(
         message: format!("Failed to serialize {} ({})", file_name, err),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })?;
     atomic_write_bytes(&path, &json).map_err(|err| {
         map_storage_error(
@@ -363,6 +625,52 @@ fn save_library_list<T: Serialize>(
     })
 }
 
+/// Load-modify-save of a library list while holding its cross-process lock.
+fn update_library_list<T, R>(
+    app: &tauri::AppHandle,
+    file_name: &str,
+    update: impl FnOnce(&mut Vec<T>) -> Result<R, ApiError>,
+) -> Result<R, ApiError>
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    let path = library_file(app, file_name)?;
+    let _lock = lock_storage_file(&path).map_err(|err| {
+        map_storage_error(
+            err,
+            "LIBRARY_WRITE_FAILED",
+            &format!("Failed to lock {}", file_name),
+        )
+    })?;
+    let mut items = load_library_list::<T>(app, file_name)?;
+    let result = update(&mut items)?;
+    save_library_list(app, file_name, &items)?;
+    Ok(result)
+}
+
+/// Load-modify-save of a library map while holding its cross-process lock.
+fn update_library_map<T, R>(
+    app: &tauri::AppHandle,
+    file_name: &str,
+    update: impl FnOnce(&mut HashMap<String, T>) -> Result<R, ApiError>,
+) -> Result<R, ApiError>
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    let path = library_file(app, file_name)?;
+    let _lock = lock_storage_file(&path).map_err(|err| {
+        map_storage_error(
+            err,
+            "LIBRARY_WRITE_FAILED",
+            &format!("Failed to lock {}", file_name),
+        )
+    })?;
+    let mut items = load_library_map::<T>(app, file_name)?;
+    let result = update(&mut items)?;
+    save_library_map(app, file_name, &items)?;
+    Ok(result)
+}
+
 #[tauri::command]
 fn get_user_data_dir(app: tauri::AppHandle) -> Result<String, ApiError> {
     let dir = user_storage_root(&app).map_err(|err| {
@@ -383,306 +691,476 @@ fn get_user_data_dir(app: tauri::AppHandle) -> Result<String, ApiError> {
 }
 
 #[tauri::command]
-fn list_projects(app: tauri::AppHandle) -> Result<Vec<ProjectSummary>, ApiError> {
-    let projects_dir = storage_projects_dir(&app).map_err(|err| {
-        map_storage_error(err, "PROJECT_LIST_FAILED", "Failed to resolve projects dir")
+fn get_storage_info(app: tauri::AppHandle) -> Result<StorageInfo, ApiError> {
+    let resolved = resolve_storage_root(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "APP_DATA_DIR_FAILED",
+            "Failed to resolve user storage root",
+        )
     })?;
+    let meta = ensure_user_storage(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "APP_DATA_DIR_FAILED",
+            "Failed to initialize user storage",
+        )
+    })?;
+    Ok(StorageInfo {
+        root: resolved.path.to_string_lossy().to_string(),
+        root_source: resolved.source,
+        schema_version: meta.schema_version,
+        supported_schema_version: STORAGE_SCHEMA_VERSION,
+        read_only: is_read_only(&meta),
+        created_at: meta.created_at,
+        last_migrated_at: meta.last_migrated_at,
+        failed_migration: meta.failed_migration,
+    })
+}
 
-    let mut results = Vec::new();
-    let entries = fs::read_dir(&projects_dir)
-        .map_err(|err| map_io_error(err, "PROJECT_LIST_FAILED", "Failed to read projects"))?;
-
-    for entry in entries {
-        let entry = entry
-            .map_err(|err| map_io_error(err, "PROJECT_LIST_FAILED", "Failed to read projects"))?;
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) != Some("json") {
-            continue;
-        }
-
-        let contents = fs::read_to_string(&path).map_err(|err| {
-            map_io_error(err, "PROJECT_LIST_FAILED", "Failed to read project file")
-        })?;
-
-        let json: serde_json::Value = serde_json::from_str(&contents).map_err(|err| ApiError {
-            code: "PROJECT_LIST_FAILED".into(),
-            message: format!("Invalid project JSON: {}", err),
+#[tauri::command]
+fn move_storage_root(
+    app: tauri::AppHandle,
+    target_root: String,
+) -> Result<StorageMoveResult, ApiError> {
+    let current = resolve_storage_root(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "STORAGE_MOVE_FAILED",
+            "Failed to resolve user storage root",
+        )
+    })?;
+    let fixed_by = match current.source {
+        StorageRootSource::Env => Some("the STAGEPILOT_DATA_DIR override"),
+        StorageRootSource::Portable => Some("portable mode"),
+        StorageRootSource::Configured | StorageRootSource::Default => None,
+    };
+    if let Some(reason) = fixed_by {
+        return Err(ApiError {
+            code: "STORAGE_MOVE_FAILED".into(),
+            message: format!("Storage root is fixed by {} and cannot be moved.", reason),
             export_pdf_path: None,
             version_pdf_path: None,
-        })?;
-
-        let id = json
-            .get("id")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| {
-                path.file_stem()
-                    .and_then(|s| s.to_str())
-                    .map(|s| s.to_string())
-            })
-            .unwrap_or_else(|| "unknown".to_string());
-
-        let created_at = json
-            .get("createdAt")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        let updated_at = json
-            .get("updatedAt")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| {
-                json.get("eventDate")
-                    .and_then(|v| v.as_str())
-                    .map(|s| format!("{}T00:00:00Z", s))
-            });
-
-        let summary = ProjectSummary {
-            id,
-            slug: json
-                .get("slug")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            display_name: json
-                .get("displayName")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            band_ref: json
-                .get("bandRef")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            event_date: json
-                .get("eventDate")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            event_venue: json
-                .get("eventVenue")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            purpose: json
-                .get("purpose")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            created_at,
-            updated_at,
-        };
+            conflict: None,
+            field_errors: None,
+        });
+    }
+    ensure_writable(&app, "STORAGE_MOVE_FAILED")?;
 
-        results.push(summary);
+    let target = PathBuf::from(target_root.trim());
+    let mut report = copy_storage_tree(&current.path, &target).map_err(|err| {
+        map_storage_error(err, "STORAGE_MOVE_FAILED", "Failed to copy user storage")
+    })?;
+    ensure_storage_at(&target).map_err(|err| {
+        map_storage_error(
+            err,
+            "STORAGE_MOVE_FAILED",
+            "Failed to open moved user storage",
+        )
+    })?;
+    if let Err(err) = relink_versions(&target).and_then(|_| collect_garbage(&target)) {
+        report
+            .warnings
+            .push(format!("Failed to re-link version snapshots ({:?})", err));
     }
+    set_configured_storage_root(&app, &target).map_err(|err| {
+        map_storage_error(
+            err,
+            "STORAGE_MOVE_FAILED",
+            "Failed to save storage location",
+        )
+    })?;
+    eprintln!(
+        "[storage] moved root {} -> {} ({} files)",
+        current.path.display(),
+        target.display(),
+        report.files_copied
+    );
+    report.warnings.extend(remove_moved_entries(&current.path));
 
-    results.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-    Ok(results)
+    Ok(StorageMoveResult {
+        root: target.to_string_lossy().to_string(),
+        report,
+    })
 }
 
 #[tauri::command]
-fn list_bands() -> Result<Vec<BandOption>, ApiError> {
-    let bands_dir = resolve_repo_root().join("data").join("bands");
-    let entries = fs::read_dir(&bands_dir).map_err(|err| ApiError {
-        code: "BAND_LIST_FAILED".into(),
-        message: format!("Failed to read bands at {} ({})", bands_dir.display(), err),
-        export_pdf_path: None,
-        version_pdf_path: None,
+fn backup_storage(
+    app: tauri::AppHandle,
+    archive_path: String,
+    include_exports: bool,
+) -> Result<BackupResult, ApiError> {
+    let root = user_storage_root(&app).map_err(|err| {
+        map_storage_error(err, "BACKUP_FAILED", "Failed to resolve user storage root")
     })?;
+    let meta = ensure_user_storage(&app).map_err(|err| {
+        map_storage_error(err, "BACKUP_FAILED", "Failed to initialize user storage")
+    })?;
+    let archive = PathBuf::from(&archive_path);
+    if archive.starts_with(&root) {
+        return Err(ApiError {
+            code: "BACKUP_FAILED".into(),
+            message: "Backup archive must be saved outside the StagePilot storage folder.".into(),
+            export_pdf_path: None,
+            version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
+        });
+    }
+    let manifest = create_backup(&root, &archive, meta.schema_version, include_exports)
+        .map_err(|err| map_storage_error(err, "BACKUP_FAILED", "Failed to write backup"))?;
+    eprintln!(
+        "[storage] backup written path={} files={}",
+        archive.display(),
+        manifest.files.len()
+    );
+    Ok(BackupResult {
+        archive_path,
+        file_count: manifest.files.len(),
+        total_bytes: manifest.total_bytes(),
+        schema_version: manifest.schema_version,
+        created_at: manifest.created_at,
+    })
+}
 
-    let mut results = Vec::new();
-    for entry in entries {
-        let entry =
-            entry.map_err(|err| map_io_error(err, "BAND_LIST_FAILED", "Failed to read bands"))?;
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) != Some("json") {
-            continue;
-        }
-
-        let contents = fs::read_to_string(&path)
-            .map_err(|err| map_io_error(err, "BAND_LIST_FAILED", "Failed to read band file"))?;
-
-        let json: serde_json::Value = serde_json::from_str(&contents).map_err(|err| ApiError {
-            code: "BAND_LIST_FAILED".into(),
-            message: format!("Invalid band JSON: {}", err),
+#[tauri::command]
+fn restore_storage(
+    app: tauri::AppHandle,
+    archive_path: String,
+    target_root: Option<String>,
+) -> Result<RestoreResult, ApiError> {
+    let archive = PathBuf::from(&archive_path);
+    let manifest = read_backup_manifest(&archive)
+        .map_err(|err| map_storage_error(err, "RESTORE_FAILED", "Invalid backup archive"))?;
+    if manifest.schema_version > STORAGE_SCHEMA_VERSION {
+        return Err(ApiError {
+            code: "BACKUP_INCOMPATIBLE".into(),
+            message: format!(
+                "Backup uses storage schemaVersion {} but this StagePilot supports up to {}. Please update StagePilot.",
+                manifest.schema_version, STORAGE_SCHEMA_VERSION
+            ),
             export_pdf_path: None,
             version_pdf_path: None,
-        })?;
+            conflict: None,
+            field_errors: None,
+        });
+    }
 
-        let id = json
-            .get("id")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_default();
-        let name = json
-            .get("name")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_default();
+    let current = resolve_storage_root(&app).map_err(|err| {
+        map_storage_error(err, "RESTORE_FAILED", "Failed to resolve user storage root")
+    })?;
+    let mut warnings = Vec::new();
+    let (target, replace_existing) = match target_root {
+        Some(path) => (PathBuf::from(path.trim()), false),
+        None => (current.path.clone(), true),
+    };
+    if !replace_existing && target.starts_with(&current.path) {
+        return Err(ApiError {
+            code: "RESTORE_FAILED".into(),
+            message: "Restore target must be outside the current StagePilot storage folder.".into(),
+            export_pdf_path: None,
+            version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
+        });
+    }
+    let outcome = restore_backup(&archive, &target, replace_existing)
+        .map_err(|err| map_storage_error(err, "RESTORE_FAILED", "Failed to restore backup"))?;
+    // Older backups are upgraded by the regular migration pipeline here.
+    let meta = ensure_storage_at(&target).map_err(|err| {
+        map_storage_error(err, "RESTORE_FAILED", "Failed to open restored storage")
+    })?;
 
-        if id.is_empty() || name.is_empty() {
-            continue;
+    if target != current.path {
+        match current.source {
+            StorageRootSource::Configured | StorageRootSource::Default => {
+                set_configured_storage_root(&app, &target).map_err(|err| {
+                    map_storage_error(err, "RESTORE_FAILED", "Failed to save storage location")
+                })?;
+            }
+            StorageRootSource::Env | StorageRootSource::Portable => warnings.push(
+                "Storage root is fixed by STAGEPILOT_DATA_DIR or portable mode; the restored copy is not active.".into(),
+            ),
         }
+    }
+    eprintln!(
+        "[storage] restored backup path={} root={}",
+        archive.display(),
+        target.display()
+    );
 
-        let code = json
-            .get("code")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+    Ok(RestoreResult {
+        root: target.to_string_lossy().to_string(),
+        file_count: outcome.manifest.files.len(),
+        schema_version: meta.schema_version,
+        previous_root: outcome
+            .previous_root
+            .map(|path| path.to_string_lossy().to_string()),
+        carried_over: outcome.carried_over,
+        warnings,
+    })
+}
 
-        results.push(BandOption { id, name, code });
-    }
+/// Ids of all musicians, shipped and from the user library.
+fn known_musician_ids(app: &tauri::AppHandle, code: &str) -> Result<HashSet<String>, ApiError> {
+    Ok(data_repository(app, code)?
+        .musicians()
+        .iter()
+        .map(|musician| musician.id.clone())
+        .collect())
+}
 
-    results.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    Ok(results)
+#[tauri::command]
+fn scan_storage(app: tauri::AppHandle) -> Result<StorageScanReport, ApiError> {
+    let root = ensured_storage_root(&app, "STORAGE_SCAN_FAILED")?;
+    scan_storage_at(&root, &known_musician_ids(&app, "STORAGE_SCAN_FAILED")?)
+        .map_err(|err| map_storage_error(err, "STORAGE_SCAN_FAILED", "Failed to scan storage"))
 }
 
 #[tauri::command]
-fn get_band_setup_data(app: tauri::AppHandle, band_id: String) -> Result<BandSetupData, ApiError> {
-    let repo_root = resolve_repo_root();
-    let bands_dir = repo_root.join("data").join("bands");
-    let entries = fs::read_dir(&bands_dir).map_err(|err| ApiError {
-        code: "BAND_SETUP_LOAD_FAILED".into(),
-        message: format!("Failed to read bands at {} ({})", bands_dir.display(), err),
-        export_pdf_path: None,
-        version_pdf_path: None,
-    })?;
+fn repair_storage(app: tauri::AppHandle, dry_run: bool) -> Result<StorageRepairReport, ApiError> {
+    if !dry_run {
+        ensure_writable(&app, "STORAGE_REPAIR_FAILED")?;
+    }
+    let root = ensured_storage_root(&app, "STORAGE_REPAIR_FAILED")?;
+    let musician_ids = known_musician_ids(&app, "STORAGE_REPAIR_FAILED")?;
+    repair_storage_at(&root, &musician_ids, dry_run)
+        .map_err(|err| map_storage_error(err, "STORAGE_REPAIR_FAILED", "Failed to repair storage"))
+}
 
-    let mut selected: Option<Value> = None;
-    let requested = band_id.trim().to_string();
-    let requested_lower = requested.to_lowercase();
-    for entry in entries {
-        let path = entry
-            .map_err(|err| map_io_error(err, "BAND_SETUP_LOAD_FAILED", "Failed to read bands"))?
-            .path();
-        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+/// One project file `list_projects` had to skip.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectFileProblem {
+    path: String,
+    file_name: String,
+    /// `read`, `parse` or `notObject`.
+    kind: String,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectListing {
+    projects: Vec<ProjectSummary>,
+    problems: Vec<ProjectFileProblem>,
+}
+
+/// Answers listings from the project index, which is refreshed against the
+/// files' mtimes first. Unreadable files are reported as problems instead of
+/// failing the whole listing.
+fn collect_projects(app: &tauri::AppHandle) -> Result<ProjectListing, ApiError> {
+    let (root, index) = load_project_index(app, "PROJECT_LIST_FAILED")?;
+
+    let mut results = Vec::new();
+    let mut problems = Vec::new();
+    for (file_name, entry) in index.entries {
+        if let Some(problem) = entry.problem {
+            problems.push(ProjectFileProblem {
+                path: root
+                    .join("projects")
+                    .join(&file_name)
+                    .to_string_lossy()
+                    .to_string(),
+                file_name,
+                kind: problem.kind,
+                message: problem.message,
+            });
             continue;
         }
-        let contents = fs::read_to_string(&path).map_err(|err| {
-            map_io_error(err, "BAND_SETUP_LOAD_FAILED", "Failed to read band file")
-        })?;
-        let json: Value = serde_json::from_str(&contents).map_err(|err| ApiError {
-            code: "BAND_SETUP_LOAD_FAILED".into(),
-            message: format!("Invalid band JSON in {} ({})", path.display(), err),
-            export_pdf_path: None,
-            version_pdf_path: None,
-        })?;
+        results.push(ProjectSummary::from(entry));
+    }
 
-        let candidate_id = json
-            .get("id")
-            .and_then(|v| v.as_str())
-            .map(|v| v.trim().to_string())
-            .unwrap_or_default();
-        let candidate_code = json
-            .get("code")
-            .and_then(|v| v.as_str())
-            .map(|v| v.trim().to_string())
-            .unwrap_or_default();
+    results.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(ProjectListing {
+        projects: results,
+        problems,
+    })
+}
 
-        if candidate_id == requested
-            || candidate_code.eq_ignore_ascii_case(&requested)
-            || candidate_id.to_lowercase() == requested_lower
-        {
-            selected = Some(json);
-            break;
+fn load_project_index(
+    app: &tauri::AppHandle,
+    code: &str,
+) -> Result<(PathBuf, ProjectIndex), ApiError> {
+    let meta = ensure_user_storage(app)
+        .map_err(|err| map_storage_error(err, code, "Failed to initialize user storage"))?;
+    let root = ensured_storage_root(app, code)?;
+    let index = refresh_project_index(&root, !is_read_only(&meta))
+        .map_err(|err| map_storage_error(err, code, "Failed to read projects"))?;
+    Ok((root, index))
+}
+
+impl From<ProjectIndexEntry> for ProjectSummary {
+    fn from(entry: ProjectIndexEntry) -> Self {
+        ProjectSummary {
+            id: entry.id,
+            slug: entry.slug,
+            display_name: entry.display_name,
+            band_ref: entry.band_ref,
+            event_date: entry.event_date,
+            event_venue: entry.event_venue,
+            purpose: entry.purpose,
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
         }
     }
+}
 
-    let json = selected.ok_or(ApiError {
-        code: "BAND_NOT_FOUND".into(),
-        message: format!(
-            "Band not found for reference '{}' in {} (checked id and code)",
-            requested,
-            bands_dir.display()
-        ),
-        export_pdf_path: None,
-        version_pdf_path: None,
-    })?;
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectQueryResult {
+    total: usize,
+    offset: usize,
+    items: Vec<ProjectSummary>,
+}
 
-    let members_root = repo_root.join("data").join("musicians");
-    let mut members: HashMap<String, Vec<MemberOption>> = HashMap::new();
-    let mut musicians_by_id: HashMap<String, (String, String)> = HashMap::new();
+#[tauri::command]
+fn query_projects(
+    app: tauri::AppHandle,
+    query: ProjectQuery,
+) -> Result<ProjectQueryResult, ApiError> {
+    let (_, index) = load_project_index(&app, "PROJECT_QUERY_FAILED")?;
+    let entries = index
+        .entries
+        .into_values()
+        .filter(|entry| entry.problem.is_none())
+        .collect();
+    let (total, page) = query_entries(entries, &query);
+    Ok(ProjectQueryResult {
+        total,
+        offset: query.offset,
+        items: page.into_iter().map(ProjectSummary::from).collect(),
+    })
+}
+
+/// Keeps the project index in step after a command changed `projects/`.
+/// Failures only cost a re-read on the next listing, so they are logged.
+fn sync_project_index(app: &tauri::AppHandle, project_path: &Path) {
+    let result = user_storage_root(app).and_then(|root| update_project_index(&root, project_path));
+    if let Err(err) = result {
+        eprintln!("[project] index update failed: {:?}", err);
+    }
+}
+
+#[tauri::command]
+fn list_projects(app: tauri::AppHandle) -> Result<Vec<ProjectSummary>, ApiError> {
+    let listing = collect_projects(&app)?;
+    for problem in &listing.problems {
+        eprintln!(
+            "[project] skipped {} ({}: {})",
+            problem.path, problem.kind, problem.message
+        );
+    }
+    Ok(listing.projects)
+}
+
+#[tauri::command]
+fn list_projects_with_diagnostics(app: tauri::AppHandle) -> Result<ProjectListing, ApiError> {
+    collect_projects(&app)
+}
+
+/// Moves a broken file out of `projects/` into `quarantine/<timestamp>/projects/`
+/// and returns its new location.
+#[tauri::command]
+fn quarantine_project_file(app: tauri::AppHandle, file_name: String) -> Result<String, ApiError> {
+    ensure_writable(&app, "PROJECT_QUARANTINE_FAILED")?;
+    let root = ensured_storage_root(&app, "PROJECT_QUARANTINE_FAILED")?;
+    let target = quarantine_file_at(&root, &file_name).map_err(|err| {
+        map_storage_error(
+            err,
+            "PROJECT_QUARANTINE_FAILED",
+            "Failed to quarantine project file",
+        )
+    })?;
+    sync_project_index(&app, &root.join("projects").join(&file_name));
+    Ok(target.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn list_bands(app: tauri::AppHandle) -> Result<Vec<BandOption>, ApiError> {
+    let repository = data_repository(&app, "BAND_LIST_FAILED")?;
+    let mut results: Vec<BandOption> = repository
+        .bands()
+        .iter()
+        .filter_map(|band| {
+            Some(BandOption {
+                id: band.id.clone(),
+                name: band.text("name")?.to_string(),
+                code: band.text("code").map(|code| code.to_string()),
+                origin: band.origin,
+            })
+        })
+        .collect();
+    results.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Ok(results)
+}
+
+#[tauri::command]
+fn get_band_setup_data(app: tauri::AppHandle, band_id: String) -> Result<BandSetupData, ApiError> {
+    let repository = data_repository(&app, "BAND_SETUP_LOAD_FAILED")?;
+    let requested = band_id.trim().to_string();
+    let json = &repository
+        .band(&requested)
+        .ok_or(ApiError {
+            code: "BAND_NOT_FOUND".into(),
+            message: format!(
+                "Band not found for reference '{}' in shipped data or the library (checked id and code)",
+                requested
+            ),
+            export_pdf_path: None,
+            version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
+        })?
+        .value;
+
+    let setup_roles = ["drums", "bass", "guitar", "keys", "vocs", "talkback"];
+    let mut members: HashMap<String, Vec<MemberOption>> = setup_roles
+        .iter()
+        .map(|role| (role.to_string(), Vec::new()))
+        .collect();
+    let mut musicians_by_id: HashMap<String, (String, Vec<String>)> = HashMap::new();
     let mut musician_defaults: HashMap<String, Value> = HashMap::new();
     let mut musician_presets_by_id: HashMap<String, Vec<Value>> = HashMap::new();
-    for role in ["drums", "bass", "guitar", "keys", "vocs", "talkback"] {
-        let role_dir = members_root.join(role);
-        let mut role_members: Vec<MemberOption> = Vec::new();
-        if role_dir.exists() {
-            let role_entries = fs::read_dir(&role_dir).map_err(|err| {
-                map_io_error(err, "BAND_SETUP_LOAD_FAILED", "Failed to read musicians")
-            })?;
-            for role_entry in role_entries {
-                let role_path = role_entry
-                    .map_err(|err| {
-                        map_io_error(err, "BAND_SETUP_LOAD_FAILED", "Failed to read musicians")
-                    })?
-                    .path();
-                if role_path.extension().and_then(|s| s.to_str()) != Some("json") {
-                    continue;
-                }
-                let contents = fs::read_to_string(&role_path).map_err(|err| {
-                    map_io_error(
-                        err,
-                        "BAND_SETUP_LOAD_FAILED",
-                        "Failed to read musician file",
-                    )
-                })?;
-                let musician: Value = serde_json::from_str(&contents).map_err(|err| ApiError {
-                    code: "BAND_SETUP_LOAD_FAILED".into(),
-                    message: format!("Invalid musician JSON in {} ({})", role_path.display(), err),
-                    export_pdf_path: None,
-                    version_pdf_path: None,
-                })?;
-                let id = musician.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                if id.is_empty() {
-                    continue;
-                }
-                let monitor_ref =
-                    musician
-                        .get("presets")
-                        .and_then(|v| v.as_array())
-                        .and_then(|presets| {
-                            presets.iter().find_map(|preset| {
-                                if preset.get("kind").and_then(|v| v.as_str()) == Some("monitor") {
-                                    return preset
-                                        .get("ref")
-                                        .and_then(|v| v.as_str())
-                                        .map(|v| v.to_string());
-                                }
-                                None
-                            })
-                        });
-                if let Some(reference) = monitor_ref {
-                    musician_defaults.insert(
-                        id.to_string(),
-                        infer_monitoring_default_from_ref(&reference),
-                    );
-                }
-                musician_presets_by_id.insert(
-                    id.to_string(),
-                    musician
-                        .get("presets")
-                        .and_then(|v| v.as_array())
-                        .cloned()
-                        .unwrap_or_default(),
-                );
-                let first_name = musician
-                    .get("firstName")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-                let last_name = musician
-                    .get("lastName")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-                let name = format!("{} {}", last_name, first_name).trim().to_string();
-                musicians_by_id.insert(id.to_string(), (name.clone(), role.to_string()));
-                role_members.push(MemberOption {
+    for musician in repository.musicians() {
+        let id = musician.id.as_str();
+        let presets = musician
+            .value
+            .get("presets")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let monitor_ref = presets.iter().find_map(|preset| {
+            if preset.get("kind").and_then(|v| v.as_str()) == Some("monitor") {
+                return preset.get("ref").and_then(|v| v.as_str());
+            }
+            None
+        });
+        if let Some(reference) = monitor_ref {
+            musician_defaults.insert(id.to_string(), infer_monitoring_default_from_ref(reference));
+        }
+        musician_presets_by_id.insert(id.to_string(), presets);
+        let name = musician.display_name();
+        let roles = musician.roles();
+        for role in &roles {
+            if let Some(list) = members.get_mut(role) {
+                list.push(MemberOption {
                     id: id.to_string(),
-                    name,
+                    name: name.clone(),
                 });
             }
         }
-        role_members.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-        members.insert(role.to_string(), role_members);
+        musicians_by_id.insert(id.to_string(), (name, roles));
+    }
+    for value in members.values_mut() {
+        value.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
     }
 
     if let Some(band_members) = json.get("members").and_then(|v| v.as_array()) {
-        let mut restricted: HashMap<String, Vec<MemberOption>> = HashMap::new();
-        for role in ["drums", "bass", "guitar", "keys", "vocs", "talkback"] {
-            restricted.insert(role.to_string(), Vec::new());
-        }
+        let mut restricted: HashMap<String, Vec<MemberOption>> = setup_roles
+            .iter()
+            .map(|role| (role.to_string(), Vec::new()))
+            .collect();
         for member in band_members {
             let musician_id = member
                 .get("musicianId")
@@ -691,23 +1169,27 @@ fn get_band_setup_data(app: tauri::AppHandle, band_id: String) -> Result<BandSet
             if musician_id.is_empty() {
                 continue;
             }
-            let Some((name, default_group)) = musicians_by_id.get(musician_id) else {
+            let Some((name, default_roles)) = musicians_by_id.get(musician_id) else {
                 continue;
             };
-            if let Some(roles) = member.get("roles").and_then(|v| v.as_array()) {
-                for role in roles.iter().filter_map(|v| v.as_str()) {
-                    if let Some(list) = restricted.get_mut(role) {
-                        list.push(MemberOption {
-                            id: musician_id.to_string(),
-                            name: name.clone(),
-                        });
-                    }
+            let member_roles: Vec<String> = member
+                .get("roles")
+                .and_then(|v| v.as_array())
+                .map(|roles| {
+                    roles
+                        .iter()
+                        .filter_map(|v| v.as_str().map(|role| role.to_string()))
+                        .collect()
+                })
+                .filter(|roles: &Vec<String>| !roles.is_empty())
+                .unwrap_or_else(|| default_roles.clone());
+            for role in member_roles {
+                if let Some(list) = restricted.get_mut(&role) {
+                    list.push(MemberOption {
+                        id: musician_id.to_string(),
+                        name: name.clone(),
+                    });
                 }
-            } else if let Some(list) = restricted.get_mut(default_group) {
-                list.push(MemberOption {
-                    id: musician_id.to_string(),
-                    name: name.clone(),
-                });
             }
         }
         let has_any = restricted.values().any(|v| !v.is_empty());
@@ -730,6 +1212,8 @@ fn get_band_setup_data(app: tauri::AppHandle, band_id: String) -> Result<BandSet
         message: format!("Invalid constraints for band {} ({})", band_id, err),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })?;
 
     let mut load_warnings: Vec<String> = Vec::new();
@@ -778,9 +1262,18 @@ fn get_band_setup_data(app: tauri::AppHandle, band_id: String) -> Result<BandSet
             .get("bandLeader")
             .and_then(|v| v.as_str())
             .map(|v| v.to_string()),
+        // Library bands mark their default contact as `primary` instead.
         default_contact_id: json
             .get("defaultContactId")
             .and_then(|v| v.as_str())
+            .or_else(|| {
+                json.get("contacts")?
+                    .as_array()?
+                    .iter()
+                    .find(|contact| contact.get("primary").and_then(|v| v.as_bool()) == Some(true))?
+                    .get("id")?
+                    .as_str()
+            })
             .map(|v| v.to_string()),
         constraints,
         role_constraints: json.get("roleConstraints").cloned(),
@@ -792,65 +1285,1026 @@ fn get_band_setup_data(app: tauri::AppHandle, band_id: String) -> Result<BandSet
     })
 }
 
-#[tauri::command]
-fn read_project(app: tauri::AppHandle, project_id: String) -> Result<String, ApiError> {
-    let project_path = resolve_project_path_by_id(&app, &project_id)?.ok_or(ApiError {
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectDocument {
+    json: String,
+    /// Pass as `expectedRevision` to `save_project`.
+    revision: String,
+    /// Set when the file was upgraded while reading it.
+    migration: Option<ProjectMigrationReport>,
+}
+
+/// Reads a project and upgrades legacy shapes to `PROJECT_SCHEMA_VERSION`.
+/// The upgrade is written back once, atomically; read-only storage is only
+/// upgraded in memory. Unparseable files are returned as they are.
+fn load_project_document(
+    app: &tauri::AppHandle,
+    project_id: &str,
+) -> Result<ProjectDocument, ApiError> {
+    let project_path = resolve_project_path_by_id(app, project_id)?.ok_or(ApiError {
         code: "PROJECT_READ_FAILED".into(),
         message: format!("Project not found: {}", project_id),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })?;
     eprintln!(
         "[project] read path={} exists={}",
         project_path.display(),
         project_path.exists()
     );
-    fs::read_to_string(&project_path)
-        .map_err(|err| map_io_error(err, "PROJECT_READ_FAILED", "Failed to read project"))
+    let content = fs::read_to_string(&project_path)
+        .map_err(|err| map_io_error(err, "PROJECT_READ_FAILED", "Failed to read project"))?;
+    let mut json = match serde_json::from_str::<Value>(&content) {
+        Ok(json) => json,
+        Err(_) => {
+            return Ok(ProjectDocument {
+                revision: project_revision(content.as_bytes()),
+                json: content,
+                migration: None,
+            })
+        }
+    };
+    let Some(report) = migrate_project_json(&mut json) else {
+        return Ok(ProjectDocument {
+            revision: project_revision(content.as_bytes()),
+            json: content,
+            migration: None,
+        });
+    };
+    eprintln!(
+        "[project] migrated {} v{} -> v{}: {}",
+        project_id,
+        report.from_version,
+        report.to_version,
+        report.changes.join(", ")
+    );
+    let migrated = serde_json::to_string_pretty(&json).map_err(|err| ApiError {
+        code: "PROJECT_READ_FAILED".into(),
+        message: format!("Failed to serialize migrated project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    if ensure_writable_storage(app).is_ok() {
+        let written = lock_storage_file(&project_path)
+            .and_then(|_lock| atomic_write_bytes(&project_path, migrated.as_bytes()));
+        match written {
+            Ok(()) => sync_project_index(app, &project_path),
+            Err(err) => eprintln!("[project] could not write migrated project: {:?}", err),
+        }
+    }
+    Ok(ProjectDocument {
+        revision: project_revision(migrated.as_bytes()),
+        json: migrated,
+        migration: Some(report),
+    })
+}
+
+#[tauri::command]
+fn read_project(app: tauri::AppHandle, project_id: String) -> Result<String, ApiError> {
+    load_project_document(&app, &project_id).map(|document| document.json)
+}
+
+/// Like `read_project`, plus the revision and any upgrade that was applied.
+#[tauri::command]
+fn read_project_document(
+    app: tauri::AppHandle,
+    project_id: String,
+) -> Result<ProjectDocument, ApiError> {
+    load_project_document(&app, &project_id)
+}
+
+fn invalid_project_error(errors: Vec<ProjectFieldError>) -> ApiError {
+    ApiError {
+        code: "PROJECT_INVALID".into(),
+        message: format!(
+            "Project was not saved: {}",
+            errors
+                .iter()
+                .map(|error| format!("{} {}", error.field, error.message))
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: Some(errors.into_boxed_slice()),
+    }
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveProjectResult {
+    /// Pass back as `expectedRevision` on the next save.
+    revision: String,
+    created_at: String,
+    updated_at: String,
+}
+
+/// Writes a project. `createdAt`/`updatedAt` are stamped here, whatever the
+/// payload says. With `expected_revision` (content hash or `updatedAt` last
+/// read) the save fails with `PROJECT_CONFLICT` if the file changed since.
 #[tauri::command]
 fn save_project(
     app: tauri::AppHandle,
     project_id: String,
     json: String,
     legacy_project_id: Option<String>,
-) -> Result<(), ApiError> {
+    expected_revision: Option<String>,
+) -> Result<SaveProjectResult, ApiError> {
+    ensure_writable(&app, "PROJECT_SAVE_FAILED")?;
     let projects_dir = storage_projects_dir(&app).map_err(|err| {
         map_storage_error(err, "PROJECT_SAVE_FAILED", "Failed to resolve projects dir")
     })?;
-    let parsed: Value = serde_json::from_str(&json).map_err(|err| ApiError {
-        code: "PROJECT_SAVE_FAILED".into(),
-        message: format!("Invalid project JSON payload ({})", err),
-        export_pdf_path: None,
-        version_pdf_path: None,
+    let payload: Value = serde_json::from_str(&json).map_err(|err| ApiError {
+        code: "PROJECT_SAVE_FAILED".into(),
+        message: format!("Invalid project JSON payload ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let project = validate_project(&payload).map_err(invalid_project_error)?;
+    // Re-serialized from the typed model; unknown fields come back via `extra`.
+    let mut parsed = serde_json::to_value(&project).map_err(|err| ApiError {
+        code: "PROJECT_SAVE_FAILED".into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+
+    let project_path = project_json_path(&projects_dir, &project_id)
+        .map_err(|err| map_storage_error(err, "PROJECT_SAVE_FAILED", "Invalid project path"))?;
+    eprintln!("[project] save path={}", project_path.display());
+    let _lock = lock_storage_file(&project_path)
+        .map_err(|err| map_storage_error(err, "PROJECT_SAVE_FAILED", "Failed to lock project"))?;
+
+    let legacy = legacy_project_id
+        .filter(|legacy_id| *legacy_id != project_id)
+        .and_then(|legacy_id| {
+            let path = project_json_path(&projects_dir, &legacy_id).ok()?;
+            path.exists().then_some((legacy_id, path))
+        });
+    let _legacy_lock = match &legacy {
+        Some((_, path)) => Some(lock_storage_file(path).map_err(|err| {
+            map_storage_error(err, "PROJECT_SAVE_FAILED", "Failed to lock project")
+        })?),
+        None => None,
+    };
+    let previous_path = legacy
+        .as_ref()
+        .map(|(_, path)| path.clone())
+        .unwrap_or_else(|| project_path.clone());
+    let previous_bytes = fs::read(&previous_path).ok();
+    if let (Some(current), Some(expected)) = (&previous_bytes, expected_revision.as_deref()) {
+        check_revision(current, expected).map_err(|conflict| ApiError {
+            code: "PROJECT_CONFLICT".into(),
+            message: "The project was changed elsewhere since it was opened.".into(),
+            export_pdf_path: None,
+            version_pdf_path: None,
+            conflict: Some(conflict),
+            field_errors: None,
+        })?;
+    }
+    let previous =
+        previous_bytes.and_then(|content| serde_json::from_slice::<Value>(&content).ok());
+    let previous_slug = previous
+        .as_ref()
+        .and_then(|json| json.get("slug"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+
+    stamp_project(&mut parsed, previous.as_ref(), &SystemClock);
+    parsed[PROJECT_SCHEMA_VERSION_KEY] = Value::from(PROJECT_SCHEMA_VERSION);
+    let json = serde_json::to_string_pretty(&parsed).map_err(|err| ApiError {
+        code: "PROJECT_SAVE_FAILED".into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    // The file, its versions and exports (when the id or slug changed) and the
+    // removal of a legacy file form one transaction.
+    let previous_id = legacy
+        .as_ref()
+        .map(|(legacy_id, _)| legacy_id.as_str())
+        .unwrap_or(&project_id);
+    let root = ensured_storage_root(&app, "PROJECT_SAVE_FAILED")?;
+    let mut journal = FileJournal::new();
+    let result = (|| {
+        if legacy.is_some() && project_path.exists() {
+            return Err(StorageError::Resolve(format!(
+                "A project with id {} already exists",
+                project_id
+            )));
+        }
+        carry_project_artifacts(
+            &root,
+            previous_id,
+            &project_id,
+            previous_slug.as_deref(),
+            Some(project.slug.as_str()),
+            &mut journal,
+        )?;
+        journal.write(&project_path, json.as_bytes())?;
+        if let Some((_, legacy_path)) = &legacy {
+            journal.remove_file(legacy_path)?;
+        }
+        Ok(())
+    })();
+    if let Err(err) = result {
+        journal.rollback();
+        return Err(map_storage_error(
+            err,
+            "PROJECT_SAVE_FAILED",
+            "Failed to save project",
+        ));
+    }
+    sync_project_index(&app, &project_path);
+    if let Some((_, legacy_path)) = &legacy {
+        sync_project_index(&app, legacy_path);
+    }
+
+    Ok(SaveProjectResult {
+        revision: project_revision(json.as_bytes()),
+        created_at: parsed["createdAt"].as_str().unwrap_or_default().to_string(),
+        updated_at: parsed["updatedAt"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Changes a project's id and/or slug together with its version history and
+/// published PDFs; nothing is changed if any step fails.
+#[tauri::command]
+fn rename_project(
+    app: tauri::AppHandle,
+    project_id: String,
+    new_id: Option<String>,
+    new_slug: Option<String>,
+) -> Result<RenameOutcome, ApiError> {
+    ensure_writable(&app, "PROJECT_RENAME_FAILED")?;
+    let old_path = resolve_project_path_by_id(&app, &project_id)?.ok_or(ApiError {
+        code: "PROJECT_RENAME_FAILED".into(),
+        message: format!("Project not found: {}", project_id),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let new_id = new_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| project_id.clone());
+    let new_slug = new_slug
+        .map(|slug| slug.trim().to_string())
+        .filter(|slug| !slug.is_empty());
+    let (root, index) = load_project_index(&app, "PROJECT_RENAME_FAILED")?;
+    if let Some(slug) = new_slug.as_deref() {
+        let taken = index
+            .entries
+            .values()
+            .any(|entry| entry.slug.as_deref() == Some(slug) && entry.id != project_id);
+        if taken {
+            return Err(ApiError {
+                code: "PROJECT_RENAME_FAILED".into(),
+                message: format!("Slug {} is already used by another project", slug),
+                export_pdf_path: None,
+                version_pdf_path: None,
+                conflict: None,
+                field_errors: None,
+            });
+        }
+    }
+    let new_path = project_json_path(&root.join("projects"), &new_id)
+        .map_err(|err| map_storage_error(err, "PROJECT_RENAME_FAILED", "Invalid project path"))?;
+
+    let _old_lock = lock_storage_file(&old_path)
+        .map_err(|err| map_storage_error(err, "PROJECT_RENAME_FAILED", "Failed to lock project"))?;
+    let _new_lock = if new_path != old_path {
+        Some(lock_storage_file(&new_path).map_err(|err| {
+            map_storage_error(err, "PROJECT_RENAME_FAILED", "Failed to lock project")
+        })?)
+    } else {
+        None
+    };
+    let outcome = rename_project_files(&root, &old_path, &new_path, &new_id, new_slug.as_deref())
+        .map_err(|err| {
+        map_storage_error(err, "PROJECT_RENAME_FAILED", "Failed to rename project")
+    })?;
+    sync_project_index(&app, &old_path);
+    sync_project_index(&app, &new_path);
+
+    Ok(outcome)
+}
+
+fn find_naming_band(app: &tauri::AppHandle, band_ref: &str) -> Option<NamingBand> {
+    let repository = data_repository(app, "BAND_LIST_FAILED").ok()?;
+    let band = repository.band(band_ref)?;
+    Some(NamingBand {
+        id: band.id.clone(),
+        code: band.text("code").map(|code| code.to_string()),
+        name: band.text("name").unwrap_or(&band.id).to_string(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DuplicateProjectResult {
+    id: String,
+    slug: String,
+    display_name: String,
+}
+
+fn project_band_ref(project: &Value) -> String {
+    project
+        .get("bandRef")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn band_not_found(code: &str, band_ref: &str) -> ApiError {
+    ApiError {
+        code: code.into(),
+        message: format!("Band not found: {}", band_ref),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    }
+}
+
+/// Names a new project after its band and date, with a collision-free slug,
+/// and writes it to `projects/` under the project lock. Callers pass a
+/// migrated and validated project.
+fn write_project_copy(
+    app: &tauri::AppHandle,
+    project: Value,
+    code: &str,
+) -> Result<DuplicateProjectResult, ApiError> {
+    let band_ref = project_band_ref(&project);
+    let band = find_naming_band(app, &band_ref).ok_or_else(|| band_not_found(code, &band_ref))?;
+    let (project_path, json, created) = prepare_project_copy(app, project, &band, code)?;
+    let _lock = lock_storage_file(&project_path)
+        .map_err(|err| map_storage_error(err, code, "Failed to lock project"))?;
+    atomic_write_bytes(&project_path, json.as_bytes())
+        .map_err(|err| map_storage_error(err, code, "Failed to save project"))?;
+    sync_project_index(app, &project_path);
+    Ok(created)
+}
+
+/// Names `project` after `band` like `write_project_copy` and returns its path
+/// and JSON without writing anything.
+fn prepare_project_copy(
+    app: &tauri::AppHandle,
+    mut project: Value,
+    band: &NamingBand,
+    code: &str,
+) -> Result<(PathBuf, String, DuplicateProjectResult), ApiError> {
+    let new_id = project
+        .get("id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let (_, index) = load_project_index(app, code)?;
+    let (slug, display_name) = unique_project_name(
+        &format_project_slug(&project, band),
+        &format_project_display_name(&project, band),
+        |candidate| {
+            index
+                .entries
+                .values()
+                .any(|entry| entry.slug.as_deref() == Some(candidate))
+        },
+    );
+    project["slug"] = Value::String(slug.clone());
+    project["displayName"] = Value::String(display_name.clone());
+    project[PROJECT_SCHEMA_VERSION_KEY] = Value::from(PROJECT_SCHEMA_VERSION);
+
+    let projects_dir = storage_projects_dir(app)
+        .map_err(|err| map_storage_error(err, code, "Failed to resolve projects dir"))?;
+    let project_path = project_json_path(&projects_dir, &new_id)
+        .map_err(|err| map_storage_error(err, code, "Invalid project path"))?;
+    let json = serde_json::to_string_pretty(&project).map_err(|err| ApiError {
+        code: code.into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    Ok((
+        project_path,
+        json,
+        DuplicateProjectResult {
+            id: new_id,
+            slug,
+            display_name,
+        },
+    ))
+}
+
+/// Copies a project under a fresh UUIDv7 and a collision-free slug. Legacy
+/// sources are migrated and the copy is validated like a save. Event details
+/// are replaced by the given values (or cleared), lifecycle fields and
+/// timestamps are reset; versions and exports stay with the source.
+#[tauri::command]
+fn duplicate_project(
+    app: tauri::AppHandle,
+    project_id: String,
+    event_date: Option<String>,
+    event_venue: Option<String>,
+) -> Result<DuplicateProjectResult, ApiError> {
+    ensure_writable(&app, "PROJECT_DUPLICATE_FAILED")?;
+    let source_path = resolve_project_path_by_id(&app, &project_id)?.ok_or(ApiError {
+        code: "PROJECT_DUPLICATE_FAILED".into(),
+        message: format!("Project not found: {}", project_id),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let contents = fs::read_to_string(&source_path)
+        .map_err(|err| map_io_error(err, "PROJECT_DUPLICATE_FAILED", "Failed to read project"))?;
+    let mut project: Value = serde_json::from_str(&contents).map_err(|err| ApiError {
+        code: "PROJECT_DUPLICATE_FAILED".into(),
+        message: format!("Invalid project JSON ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    // Copies are written in the current shape, like every other project write.
+    migrate_project_json(&mut project);
+    let Some(fields) = project.as_object_mut() else {
+        return Err(ApiError {
+            code: "PROJECT_DUPLICATE_FAILED".into(),
+            message: "Project file is not a JSON object".into(),
+            export_pdf_path: None,
+            version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
+        });
+    };
+
+    let now = now_iso();
+    for (key, value) in [("eventDate", event_date), ("eventVenue", event_venue)] {
+        match value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+        {
+            Some(value) => fields.insert(key.into(), Value::String(value)),
+            None => fields.remove(key),
+        };
+    }
+    for key in LIFECYCLE_KEYS {
+        fields.remove(key);
+    }
+    fields.insert("id".into(), Value::String(generate_uuid_v7()));
+    fields.insert("status".into(), Value::String("active".into()));
+    fields.insert("createdAt".into(), Value::String(now.clone()));
+    fields.insert("updatedAt".into(), Value::String(now));
+
+    let project = validate_project(&project).map_err(invalid_project_error)?;
+    let project = serde_json::to_value(&project).map_err(|err| ApiError {
+        code: "PROJECT_DUPLICATE_FAILED".into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    write_project_copy(&app, project, "PROJECT_DUPLICATE_FAILED")
+}
+
+#[tauri::command]
+fn list_project_versions(
+    app: tauri::AppHandle,
+    project_id: String,
+) -> Result<Vec<ProjectSnapshotMeta>, ApiError> {
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(err, "VERSION_READ_FAILED", "Failed to resolve versions dir")
+    })?;
+    list_versions(&versions_root, &project_id)
+        .map_err(|err| map_storage_error(err, "VERSION_READ_FAILED", "Failed to list versions"))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectVersionDocument {
+    meta: ProjectSnapshotMeta,
+    /// The project exactly as it was exported.
+    project_json: String,
+    /// Blob hashes of the snapshot files, once stored as blobs.
+    manifest: Option<VersionManifest>,
+}
+
+#[tauri::command]
+fn read_project_version(
+    app: tauri::AppHandle,
+    project_id: String,
+    version_id: String,
+) -> Result<ProjectVersionDocument, ApiError> {
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(err, "VERSION_READ_FAILED", "Failed to resolve versions dir")
+    })?;
+    let (meta, project_json) = read_version(&versions_root, &project_id, &version_id)
+        .map_err(|err| map_storage_error(err, "VERSION_READ_FAILED", "Failed to read version"))?;
+    let manifest = version_dir(&versions_root, &project_id, &version_id)
+        .ok()
+        .and_then(|dir| read_manifest(&dir));
+    Ok(ProjectVersionDocument {
+        meta,
+        project_json,
+        manifest,
+    })
+}
+
+#[tauri::command]
+fn open_version_pdf(
+    app: tauri::AppHandle,
+    project_id: String,
+    version_id: String,
+) -> Result<(), ApiError> {
+    let versions_root = storage_versions_dir(&app)
+        .map_err(|err| map_storage_error(err, "OPEN_FAILED", "Failed to resolve versions dir"))?;
+    let pdf = version_pdf_path(&versions_root, &project_id, &version_id)
+        .map_err(|err| map_storage_error(err, "OPEN_FAILED", "Failed to find version PDF"))?;
+    open_path(&pdf.to_string_lossy(), false)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreVersionResult {
+    restored: ProjectSnapshotMeta,
+    /// Snapshot of the state that was replaced; restore it to undo.
+    restore_point: Option<ProjectSnapshotMeta>,
+}
+
+/// Makes a snapshot the current project. The current file is first saved as a
+/// restore point, so the restore can itself be undone.
+#[tauri::command]
+fn restore_project_version(
+    app: tauri::AppHandle,
+    project_id: String,
+    version_id: String,
+) -> Result<RestoreVersionResult, ApiError> {
+    ensure_writable(&app, "VERSION_RESTORE_FAILED")?;
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "VERSION_RESTORE_FAILED",
+            "Failed to resolve versions dir",
+        )
+    })?;
+    let (meta, snapshot) =
+        read_version(&versions_root, &project_id, &version_id).map_err(|err| {
+            map_storage_error(err, "VERSION_RESTORE_FAILED", "Failed to read version")
+        })?;
+    let mut project: Value = serde_json::from_str(&snapshot).map_err(|err| ApiError {
+        code: "VERSION_RESTORE_FAILED".into(),
+        message: format!("Invalid project JSON in version {} ({})", version_id, err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    migrate_project_json(&mut project);
+    project["id"] = Value::String(project_id.clone());
+    let project = validate_project(&project).map_err(invalid_project_error)?;
+
+    let project_path = match resolve_project_path_by_id(&app, &project_id)? {
+        Some(path) => path,
+        None => {
+            let projects_dir = storage_projects_dir(&app).map_err(|err| {
+                map_storage_error(
+                    err,
+                    "VERSION_RESTORE_FAILED",
+                    "Failed to resolve projects dir",
+                )
+            })?;
+            project_json_path(&projects_dir, &project_id).map_err(|err| {
+                map_storage_error(err, "VERSION_RESTORE_FAILED", "Invalid project path")
+            })?
+        }
+    };
+    let _lock = lock_storage_file(&project_path).map_err(|err| {
+        map_storage_error(err, "VERSION_RESTORE_FAILED", "Failed to lock project")
+    })?;
+    let current = fs::read(&project_path).ok();
+    let restore_point = current
+        .as_deref()
+        .map(|bytes| create_restore_point(&versions_root, &project_id, bytes, SystemClock.now()))
+        .transpose()
+        .map_err(|err| {
+            map_storage_error(
+                err,
+                "VERSION_RESTORE_FAILED",
+                "Failed to save restore point",
+            )
+        })?;
+    if let Some(point) = &restore_point {
+        store_version_blobs(&app, &project_id, &point.version_id);
+    }
+
+    let previous = current.and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok());
+    let mut restored = serde_json::to_value(&project).map_err(|err| ApiError {
+        code: "VERSION_RESTORE_FAILED".into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    // Like the id, the slug stays: exports and other projects' slugs were
+    // checked against the current one, not the snapshot's.
+    if let Some(slug) = previous.as_ref().and_then(|json| json.get("slug")) {
+        restored["slug"] = slug.clone();
+    }
+    stamp_project(&mut restored, previous.as_ref(), &SystemClock);
+    restored[PROJECT_SCHEMA_VERSION_KEY] = Value::from(PROJECT_SCHEMA_VERSION);
+    let json = serde_json::to_vec_pretty(&restored).map_err(|err| ApiError {
+        code: "VERSION_RESTORE_FAILED".into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    atomic_write_bytes(&project_path, &json).map_err(|err| {
+        map_storage_error(err, "VERSION_RESTORE_FAILED", "Failed to write project")
+    })?;
+    sync_project_index(&app, &project_path);
+
+    Ok(RestoreVersionResult {
+        restored: meta,
+        restore_point,
+    })
+}
+
+/// Starts a new project from a snapshot, e.g. last year's tour rider as the
+/// base for this year's. Given event details replace the snapshot's, empty
+/// values clear them. The source project and its versions are not touched.
+#[tauri::command]
+fn create_project_from_version(
+    app: tauri::AppHandle,
+    project_id: String,
+    version_id: String,
+    event_date: Option<String>,
+    event_venue: Option<String>,
+) -> Result<DuplicateProjectResult, ApiError> {
+    ensure_writable(&app, "VERSION_FORK_FAILED")?;
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(err, "VERSION_FORK_FAILED", "Failed to resolve versions dir")
+    })?;
+    let (meta, snapshot) = read_version(&versions_root, &project_id, &version_id)
+        .map_err(|err| map_storage_error(err, "VERSION_FORK_FAILED", "Failed to read version"))?;
+    let source: Value = serde_json::from_str(&snapshot).map_err(|err| ApiError {
+        code: "VERSION_FORK_FAILED".into(),
+        message: format!("Invalid project JSON in version {} ({})", version_id, err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let origin = ForkOrigin {
+        project_id,
+        version_id,
+        generated_at: meta.generated_at,
+        project_revision: project_revision(snapshot.as_bytes()),
+        forked_at: now_iso(),
+    };
+    let mut project = fork_project(source, &generate_uuid_v7(), &origin)
+        .map_err(|err| map_storage_error(err, "VERSION_FORK_FAILED", "Failed to fork version"))?;
+    for (key, value) in [("eventDate", event_date), ("eventVenue", event_venue)] {
+        let Some(value) = value else {
+            continue;
+        };
+        if let Some(fields) = project.as_object_mut() {
+            match value.trim() {
+                "" => fields.remove(key),
+                value => fields.insert(key.into(), Value::String(value.into())),
+            };
+        }
+    }
+    let project = validate_project(&project).map_err(invalid_project_error)?;
+    let project = serde_json::to_value(&project).map_err(|err| ApiError {
+        code: "VERSION_FORK_FAILED".into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+
+    write_project_copy(&app, project, "VERSION_FORK_FAILED")
+}
+
+/// Project JSON for one side of a diff: a snapshot id, or `current` for the
+/// project file. Both are upgraded to the current schema first.
+fn load_diff_side(
+    app: &tauri::AppHandle,
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+) -> Result<Value, ApiError> {
+    let json = if version_id == CURRENT_PROJECT_STATE {
+        load_project_document(app, project_id)?.json
+    } else {
+        read_version(versions_root, project_id, version_id)
+            .map_err(|err| map_storage_error(err, "VERSION_DIFF_FAILED", "Failed to read version"))?
+            .1
+    };
+    let mut project: Value = serde_json::from_str(&json).map_err(|err| ApiError {
+        code: "VERSION_DIFF_FAILED".into(),
+        message: format!("Invalid project JSON in {} ({})", version_id, err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    migrate_project_json(&mut project);
+    Ok(project)
+}
+
+/// What changed between two snapshots (or a snapshot and `current`), in terms
+/// of lineup, roles and event details rather than lines of JSON.
+#[tauri::command]
+fn diff_project_versions(
+    app: tauri::AppHandle,
+    project_id: String,
+    from: String,
+    to: String,
+) -> Result<ProjectDiff, ApiError> {
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(err, "VERSION_DIFF_FAILED", "Failed to resolve versions dir")
+    })?;
+    let before = load_diff_side(&app, &versions_root, &project_id, &from)?;
+    let after = load_diff_side(&app, &versions_root, &project_id, &to)?;
+    Ok(diff_projects(&before, &after))
+}
+
+#[tauri::command]
+fn set_version_sent(
+    app: tauri::AppHandle,
+    project_id: String,
+    version_id: String,
+    sent: bool,
+) -> Result<ProjectSnapshotMeta, ApiError> {
+    ensure_writable(&app, "VERSION_UPDATE_FAILED")?;
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "VERSION_UPDATE_FAILED",
+            "Failed to resolve versions dir",
+        )
+    })?;
+    flag_version_sent(&versions_root, &project_id, &version_id, sent)
+        .map_err(|err| map_storage_error(err, "VERSION_UPDATE_FAILED", "Failed to update version"))
+}
+
+/// Sets the note and/or tags of a snapshot; omitted fields are unchanged.
+#[tauri::command]
+fn annotate_version(
+    app: tauri::AppHandle,
+    project_id: String,
+    version_id: String,
+    note: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<ProjectSnapshotMeta, ApiError> {
+    ensure_writable(&app, "VERSION_UPDATE_FAILED")?;
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "VERSION_UPDATE_FAILED",
+            "Failed to resolve versions dir",
+        )
+    })?;
+    annotate_version_meta(&versions_root, &project_id, &version_id, note, tags).map_err(|err| {
+        map_storage_error(err, "VERSION_UPDATE_FAILED", "Failed to annotate version")
+    })
+}
+
+/// Logs that a snapshot was sent to `contact` via `channel`; `sentAt`
+/// defaults to now.
+#[tauri::command]
+fn record_version_sent(
+    app: tauri::AppHandle,
+    project_id: String,
+    version_id: String,
+    contact: String,
+    channel: String,
+    sent_at: Option<String>,
+) -> Result<ProjectSnapshotMeta, ApiError> {
+    ensure_writable(&app, "VERSION_UPDATE_FAILED")?;
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "VERSION_UPDATE_FAILED",
+            "Failed to resolve versions dir",
+        )
+    })?;
+    record_sent(
+        &versions_root,
+        &project_id,
+        &version_id,
+        &contact,
+        &channel,
+        sent_at.as_deref(),
+        SystemClock.now(),
+    )
+    .map_err(|err| map_storage_error(err, "VERSION_UPDATE_FAILED", "Failed to record delivery"))
+}
+
+/// Who received which version, newest delivery first, optionally filtered by
+/// contact.
+#[tauri::command]
+fn list_version_distribution(
+    app: tauri::AppHandle,
+    project_id: String,
+    contact: Option<String>,
+) -> Result<Vec<DistributionEntry>, ApiError> {
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(err, "VERSION_READ_FAILED", "Failed to resolve versions dir")
+    })?;
+    distribution_log(&versions_root, &project_id, contact.as_deref()).map_err(|err| {
+        map_storage_error(
+            err,
+            "VERSION_READ_FAILED",
+            "Failed to read distribution log",
+        )
+    })
+}
+
+/// The most recent delivery to `contact` (e.g. the venue), if any.
+#[tauri::command]
+fn last_sent_version(
+    app: tauri::AppHandle,
+    project_id: String,
+    contact: String,
+) -> Result<Option<DistributionEntry>, ApiError> {
+    list_version_distribution(app, project_id, Some(contact))
+        .map(|entries| entries.into_iter().next())
+}
+
+#[tauri::command]
+fn get_version_retention(app: tauri::AppHandle) -> Result<VersionRetention, ApiError> {
+    let meta = ensure_user_storage(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "APP_DATA_DIR_FAILED",
+            "Failed to initialize user storage",
+        )
+    })?;
+    Ok(meta.version_retention.unwrap_or_default())
+}
+
+#[tauri::command]
+fn set_version_retention(
+    app: tauri::AppHandle,
+    retention: VersionRetention,
+) -> Result<VersionRetention, ApiError> {
+    ensure_writable(&app, "STORAGE_UPDATE_FAILED")?;
+    let root = ensured_storage_root(&app, "STORAGE_UPDATE_FAILED")?;
+    let meta = update_storage_meta(&root, |meta| {
+        meta.version_retention = Some(retention);
+    })
+    .map_err(|err| {
+        map_storage_error(
+            err,
+            "STORAGE_UPDATE_FAILED",
+            "Failed to save retention policy",
+        )
+    })?;
+    Ok(meta.version_retention.unwrap_or_default())
+}
+
+/// Deletes snapshots the retention policy no longer keeps, for one project or
+/// all of them. `retention` overrides the stored policy; `dry_run` only
+/// reports what would be deleted and how many bytes that frees.
+#[tauri::command]
+fn prune_versions(
+    app: tauri::AppHandle,
+    project_id: Option<String>,
+    dry_run: bool,
+    retention: Option<VersionRetention>,
+) -> Result<PruneReport, ApiError> {
+    if !dry_run {
+        ensure_writable(&app, "VERSION_PRUNE_FAILED")?;
+    }
+    let retention = match retention {
+        Some(retention) => retention,
+        None => get_version_retention(app.clone())?,
+    };
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "VERSION_PRUNE_FAILED",
+            "Failed to resolve versions dir",
+        )
     })?;
-    if parsed.get("slug").and_then(|v| v.as_str()).is_none() {
-        return Err(ApiError {
-            code: "PROJECT_SAVE_FAILED".into(),
-            message: "Project slug is required.".into(),
-            export_pdf_path: None,
-            version_pdf_path: None,
-        });
+    let report = prune_version_dirs(
+        &versions_root,
+        project_id.as_deref(),
+        &retention,
+        SystemClock.now(),
+        dry_run,
+    )
+    .map_err(|err| map_storage_error(err, "VERSION_PRUNE_FAILED", "Failed to prune versions"))?;
+    if !report.dry_run && !report.pruned.is_empty() {
+        collect_blob_garbage(&app);
     }
+    Ok(report)
+}
 
-    let project_path = project_json_path(&projects_dir, &project_id)
-        .map_err(|err| map_storage_error(err, "PROJECT_SAVE_FAILED", "Invalid project path"))?;
-    eprintln!("[project] save path={}", project_path.display());
-    atomic_write_bytes(&project_path, json.as_bytes())
-        .map_err(|err| map_storage_error(err, "PROJECT_SAVE_FAILED", "Failed to save project"))?;
+/// Runs the stored retention policy for a project after an export, if
+/// `autoPrune` is on. Failures are logged; the export itself succeeded.
+fn auto_prune_versions(app: &tauri::AppHandle, project_id: &str) {
+    let retention = match ensure_user_storage(app) {
+        Ok(meta) => meta.version_retention.unwrap_or_default(),
+        Err(_) => return,
+    };
+    if !retention.auto_prune {
+        return;
+    }
+    let pruned = storage_versions_dir(app).and_then(|versions_root| {
+        prune_version_dirs(
+            &versions_root,
+            Some(project_id),
+            &retention,
+            SystemClock.now(),
+            false,
+        )
+    });
+    match pruned {
+        Ok(report) if report.pruned.is_empty() => {}
+        Ok(report) => {
+            eprintln!(
+                "[versions] pruned {} versions of {} ({} bytes)",
+                report.pruned.len(),
+                project_id,
+                report.reclaimable_bytes
+            );
+            collect_blob_garbage(app);
+        }
+        Err(err) => eprintln!("[versions] auto-prune failed: {:?}", err),
+    }
+}
 
-    if let Some(legacy_id) = legacy_project_id {
-        if legacy_id != project_id {
-            if let Ok(legacy_path) = project_json_path(&projects_dir, &legacy_id) {
-                if legacy_path.exists() {
-                    let _ = fs::remove_file(legacy_path);
-                }
-            }
+/// Stores a new snapshot's files as blobs. Failures are logged; the snapshot
+/// stays readable as plain files either way.
+fn store_version_blobs(
+    app: &tauri::AppHandle,
+    project_id: &str,
+    version_id: &str,
+) -> Option<VersionManifest> {
+    let stored = user_storage_root(app).and_then(|root| {
+        let dir = version_dir(&root.join("versions"), project_id, version_id)?;
+        ingest_version(&root, &dir)
+    });
+    match stored {
+        Ok(manifest) => Some(manifest),
+        Err(err) => {
+            eprintln!(
+                "[versions] could not store {} as blobs: {:?}",
+                version_id, err
+            );
+            None
         }
     }
+}
 
-    Ok(())
+/// Stores a fresh export as blobs and reports whether it has the same content
+/// as the export before it (restore points are not compared).
+fn store_export_blobs(app: &tauri::AppHandle, project_id: &str, version_id: &str) -> bool {
+    let Some(manifest) = store_version_blobs(app, project_id, version_id) else {
+        return false;
+    };
+    let Ok(versions_root) = storage_versions_dir(app) else {
+        return false;
+    };
+    let previous = list_versions(&versions_root, project_id)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|meta| meta.version_id != version_id && meta.kind.is_none());
+    previous
+        .and_then(|meta| version_dir(&versions_root, project_id, &meta.version_id).ok())
+        .and_then(|dir| read_manifest(&dir))
+        .is_some_and(|previous| previous.same_content(&manifest))
+}
+
+fn collect_blob_garbage(app: &tauri::AppHandle) {
+    match user_storage_root(app).and_then(|root| collect_garbage(&root)) {
+        Ok(0) => {}
+        Ok(count) => eprintln!("[versions] removed {} unused blobs", count),
+        Err(err) => eprintln!("[versions] blob cleanup failed: {:?}", err),
+    }
 }
 
 fn remove_export_artifacts(app: &tauri::AppHandle, project_path: &Path, project_id: &str) {
@@ -870,23 +2324,8 @@ fn remove_export_artifacts(app: &tauri::AppHandle, project_path: &Path, project_
                     Ok(path) => path,
                     Err(_) => return,
                 };
-                if exports_dir.exists() {
-                    if let Ok(entries) = fs::read_dir(&exports_dir) {
-                        for entry in entries.flatten() {
-                            let path = entry.path();
-                            if path.extension().and_then(|v| v.to_str()) != Some("pdf") {
-                                continue;
-                            }
-                            if let Some(file_name) = path.file_name().and_then(|v| v.to_str()) {
-                                if file_name == format!("{}.pdf", slug)
-                                    || (file_name.starts_with(&format!("{}__", slug))
-                                        && file_name.ends_with(".pdf"))
-                                {
-                                    let _ = fs::remove_file(path);
-                                }
-                            }
-                        }
-                    }
+                for path in export_files_for_slug(&exports_dir, slug) {
+                    let _ = fs::remove_file(path);
                 }
             }
         }
@@ -895,24 +2334,105 @@ fn remove_export_artifacts(app: &tauri::AppHandle, project_path: &Path, project_
 
 #[tauri::command]
 fn delete_project_permanently(app: tauri::AppHandle, project_id: String) -> Result<(), ApiError> {
+    ensure_writable(&app, "PROJECT_DELETE_FAILED")?;
     let project_path = resolve_project_path_by_id(&app, &project_id)?.ok_or(ApiError {
         code: "PROJECT_DELETE_FAILED".into(),
         message: format!("Project not found: {}", project_id),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })?;
 
+    let _lock = lock_storage_file(&project_path)
+        .map_err(|err| map_storage_error(err, "PROJECT_DELETE_FAILED", "Failed to lock project"))?;
     remove_export_artifacts(&app, &project_path, &project_id);
 
     fs::remove_file(&project_path)
         .map_err(|err| map_io_error(err, "PROJECT_DELETE_FAILED", "Failed to delete project"))?;
+    sync_project_index(&app, &project_path);
 
     Ok(())
 }
 
 #[tauri::command]
-fn delete_project(app: tauri::AppHandle, project_id: String) -> Result<(), ApiError> {
-    delete_project_permanently(app, project_id)
+fn delete_project(app: tauri::AppHandle, project_id: String) -> Result<TrashEntry, ApiError> {
+    ensure_writable(&app, "PROJECT_DELETE_FAILED")?;
+    let project_path = resolve_project_path_by_id(&app, &project_id)?.ok_or(ApiError {
+        code: "PROJECT_DELETE_FAILED".into(),
+        message: format!("Project not found: {}", project_id),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let root = user_storage_root(&app).map_err(|err| {
+        map_storage_error(
+            err,
+            "PROJECT_DELETE_FAILED",
+            "Failed to resolve user storage root",
+        )
+    })?;
+
+    let _lock = lock_storage_file(&project_path)
+        .map_err(|err| map_storage_error(err, "PROJECT_DELETE_FAILED", "Failed to lock project"))?;
+    let entry = move_project_to_trash(&root, &project_path, &project_id).map_err(|err| {
+        map_storage_error(
+            err,
+            "PROJECT_DELETE_FAILED",
+            "Failed to move project to trash",
+        )
+    })?;
+    sync_project_index(&app, &project_path);
+    Ok(entry)
+}
+
+#[tauri::command]
+fn list_trash(app: tauri::AppHandle) -> Result<Vec<TrashEntry>, ApiError> {
+    let root = ensured_storage_root(&app, "TRASH_READ_FAILED")?;
+    list_trash_entries(&root)
+        .map_err(|err| map_storage_error(err, "TRASH_READ_FAILED", "Failed to list trash"))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashRestoreResult {
+    entry: TrashEntry,
+    warnings: Vec<String>,
+}
+
+#[tauri::command]
+fn restore_trashed_project(
+    app: tauri::AppHandle,
+    entry_id: String,
+) -> Result<TrashRestoreResult, ApiError> {
+    ensure_writable(&app, "TRASH_RESTORE_FAILED")?;
+    let root = ensured_storage_root(&app, "TRASH_RESTORE_FAILED")?;
+    let (entry, warnings) = restore_from_trash(&root, &entry_id).map_err(|err| {
+        map_storage_error(err, "TRASH_RESTORE_FAILED", "Failed to restore project")
+    })?;
+    sync_project_index(&app, &root.join("projects").join(&entry.project_file_name));
+    Ok(TrashRestoreResult { entry, warnings })
+}
+
+#[tauri::command]
+fn purge_trash(app: tauri::AppHandle, entry_id: Option<String>) -> Result<usize, ApiError> {
+    ensure_writable(&app, "TRASH_PURGE_FAILED")?;
+    let root = ensured_storage_root(&app, "TRASH_PURGE_FAILED")?;
+    purge_trash_entries(&root, entry_id.as_deref())
+        .map_err(|err| map_storage_error(err, "TRASH_PURGE_FAILED", "Failed to purge trash"))
+}
+
+#[tauri::command]
+fn purge_expired_trash(
+    app: tauri::AppHandle,
+    max_age_days: Option<u64>,
+) -> Result<usize, ApiError> {
+    ensure_writable(&app, "TRASH_PURGE_FAILED")?;
+    let root = ensured_storage_root(&app, "TRASH_PURGE_FAILED")?;
+    let days = max_age_days.unwrap_or(TRASH_RETENTION_DAYS);
+    purge_expired(&root, Duration::from_secs(days * 24 * 60 * 60))
+        .map_err(|err| map_storage_error(err, "TRASH_PURGE_FAILED", "Failed to purge trash"))
 }
 
 #[tauri::command]
@@ -920,14 +2440,14 @@ fn export_pdf(app: tauri::AppHandle, project_id: String) -> Result<ExportPdfResu
     let user_data_dir = user_storage_root(&app).map_err(|err| {
         map_storage_error(err, "EXPORT_FAILED", "Failed to resolve user storage root")
     })?;
-    ensure_user_storage(&app).map_err(|err| {
-        map_storage_error(err, "EXPORT_FAILED", "Failed to initialize user storage")
-    })?;
+    ensure_writable(&app, "EXPORT_FAILED")?;
     let project_path = resolve_project_path_by_id(&app, &project_id)?.ok_or(ApiError {
         code: "PROJECT_NOT_FOUND".into(),
         message: format!("Project file not found for id: {}", project_id),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })?;
 
     if !project_path.exists() {
@@ -936,21 +2456,22 @@ fn export_pdf(app: tauri::AppHandle, project_id: String) -> Result<ExportPdfResu
             message: format!("Project file not found: {}", project_path.display()),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         });
     }
 
-    let repo_root = resolve_repo_root();
-    let script_path = repo_root.join("scripts").join("desktop_export.ts");
+    let engine = engine_root(&app)?;
 
     let output = Command::new("node")
-        .arg("--import")
-        .arg("tsx")
-        .arg(script_path.as_os_str())
+        .args(engine.node_args("desktop_export"))
         .arg("--project-id")
         .arg(&project_id)
         .arg("--user-data-dir")
         .arg(user_data_dir.as_os_str())
-        .current_dir(&repo_root)
+        .arg("--library-dir")
+        .arg(library_dir(&app)?.as_os_str())
+        .current_dir(&engine.root)
         .output()
         .map_err(|err| map_io_error(err, "EXPORT_FAILED", "Failed to execute export"))?;
 
@@ -966,6 +2487,8 @@ fn export_pdf(app: tauri::AppHandle, project_id: String) -> Result<ExportPdfResu
             ),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         })?;
 
     if response.ok {
@@ -974,13 +2497,36 @@ fn export_pdf(app: tauri::AppHandle, project_id: String) -> Result<ExportPdfResu
             message: "Export succeeded but no result returned.".into(),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         })?;
-        let parsed: ExportPdfResult = serde_json::from_value(result).map_err(|err| ApiError {
-            code: "EXPORT_FAILED".into(),
-            message: format!("Export payload is invalid: {}", err),
-            export_pdf_path: None,
-            version_pdf_path: None,
-        })?;
+        let mut parsed: ExportPdfResult =
+            serde_json::from_value(result).map_err(|err| ApiError {
+                code: "EXPORT_FAILED".into(),
+                message: format!("Export payload is invalid: {}", err),
+                export_pdf_path: None,
+                version_pdf_path: None,
+                conflict: None,
+                field_errors: None,
+            })?;
+        let recorded = data_repository(&app, "EXPORT_FAILED").and_then(|repository| {
+            storage_versions_dir(&app)
+                .and_then(|versions_root| {
+                    record_source_hashes(
+                        &versions_root,
+                        &engine.data_dir(),
+                        &repository,
+                        &project_id,
+                        &parsed.version_id,
+                    )
+                })
+                .map_err(|err| map_storage_error(err, "EXPORT_FAILED", "Failed to hash sources"))
+        });
+        if let Err(err) = recorded {
+            eprintln!("[export] could not record source hashes: {:?}", err);
+        }
+        parsed.identical_to_previous = store_export_blobs(&app, &project_id, &parsed.version_id);
+        auto_prune_versions(&app, &project_id);
         return Ok(parsed);
     }
 
@@ -992,6 +2538,279 @@ fn export_pdf(app: tauri::AppHandle, project_id: String) -> Result<ExportPdfResu
         message,
         export_pdf_path: response.export_pdf_path,
         version_pdf_path: response.version_pdf_path,
+        conflict: None,
+        field_errors: None,
+    })
+}
+
+/// Whether the latest export is still current: compares the project file and
+/// the band, musician and preset data with the hashes recorded at export time,
+/// and `exports/<slug>.pdf` with the snapshot's PDF.
+#[tauri::command]
+fn get_export_status(app: tauri::AppHandle, project_id: String) -> Result<ExportStatus, ApiError> {
+    let document = load_project_document(&app, &project_id)?;
+    let project: Value = serde_json::from_str(&document.json).map_err(|err| ApiError {
+        code: "PROJECT_READ_FAILED".into(),
+        message: format!("Invalid project JSON ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let versions_root = storage_versions_dir(&app).map_err(|err| {
+        map_storage_error(err, "VERSION_READ_FAILED", "Failed to resolve versions dir")
+    })?;
+    let exports_root = exports_dir(&app).map_err(|err| {
+        map_storage_error(err, "VERSION_READ_FAILED", "Failed to resolve exports dir")
+    })?;
+    export_status(
+        &versions_root,
+        &exports_root,
+        &data_root(&app)?,
+        &data_repository(&app, "VERSION_READ_FAILED")?,
+        &project_id,
+        &project,
+    )
+    .map_err(|err| map_storage_error(err, "VERSION_READ_FAILED", "Failed to check export status"))
+}
+
+/// Packs a project with every band, musician, contact and preset it references
+/// into a `.stagepilot` bundle, optionally with its latest PDF.
+#[tauri::command]
+fn export_project_bundle(
+    app: tauri::AppHandle,
+    project_id: String,
+    archive_path: String,
+    include_pdf: bool,
+) -> Result<BundleManifest, ApiError> {
+    let document = load_project_document(&app, &project_id)?;
+    let project: Value = serde_json::from_str(&document.json).map_err(|err| ApiError {
+        code: "BUNDLE_EXPORT_FAILED".into(),
+        message: format!("Invalid project JSON ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let mut archive = PathBuf::from(&archive_path);
+    if archive.extension().is_none() {
+        archive.set_extension(BUNDLE_EXTENSION);
+    }
+
+    let pdf = if include_pdf {
+        let versions_root = storage_versions_dir(&app).map_err(|err| {
+            map_storage_error(
+                err,
+                "BUNDLE_EXPORT_FAILED",
+                "Failed to resolve versions dir",
+            )
+        })?;
+        let exports_root = exports_dir(&app).map_err(|err| {
+            map_storage_error(err, "BUNDLE_EXPORT_FAILED", "Failed to resolve exports dir")
+        })?;
+        let latest_version_pdf = list_versions(&versions_root, &project_id)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|meta| meta.kind.is_none() && meta.pdf_file_name.is_some())
+            .and_then(|meta| version_pdf_path(&versions_root, &project_id, &meta.version_id).ok());
+        let export_pdf = project
+            .get("slug")
+            .and_then(|v| v.as_str())
+            .map(|slug| exports_root.join(format!("{}.pdf", slug)));
+        let path = latest_version_pdf
+            .into_iter()
+            .chain(export_pdf)
+            .find(|path| path.is_file())
+            .ok_or(ApiError {
+                code: "BUNDLE_EXPORT_FAILED".into(),
+                message: "The project has no exported PDF to include.".into(),
+                export_pdf_path: None,
+                version_pdf_path: None,
+                conflict: None,
+                field_errors: None,
+            })?;
+        let bytes = fs::read(&path)
+            .map_err(|err| map_io_error(err, "BUNDLE_EXPORT_FAILED", "Failed to read PDF"))?;
+        let name = path
+            .file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_else(|| "export.pdf".into());
+        Some((name, bytes))
+    } else {
+        None
+    };
+
+    create_bundle(
+        &archive,
+        &project,
+        &data_root(&app)?,
+        &library_dir(&app)?,
+        pdf.as_ref()
+            .map(|(name, bytes)| (name.as_str(), bytes.as_slice())),
+    )
+    .map_err(|err| map_storage_error(err, "BUNDLE_EXPORT_FAILED", "Failed to write bundle"))
+}
+
+/// Reads a bundle and reports, per record, whether it is new, identical to
+/// the local one or colliding with it.
+#[tauri::command]
+fn inspect_project_bundle(
+    app: tauri::AppHandle,
+    archive_path: String,
+) -> Result<BundlePreview, ApiError> {
+    let bundle = read_bundle(Path::new(&archive_path))
+        .map_err(|err| map_storage_error(err, "BUNDLE_READ_FAILED", "Failed to read bundle"))?;
+    Ok(preview_bundle(
+        &bundle,
+        &data_root(&app)?,
+        &library_dir(&app)?,
+    ))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleImportResult {
+    id: String,
+    slug: String,
+    display_name: String,
+    records: Vec<ImportedRecord>,
+    pdf_path: Option<String>,
+}
+
+/// The band a project refers to when it arrives with the bundle being imported.
+fn imported_naming_band(records: &[(String, String, Value)], band_ref: &str) -> Option<NamingBand> {
+    let (_, id, band) = records.iter().find(|(file, id, band)| {
+        file == LIBRARY_BANDS
+            && (id == band_ref
+                || band
+                    .get("code")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|code| code.trim().eq_ignore_ascii_case(band_ref.trim())))
+    })?;
+    let text = |key: &str| {
+        band.get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    Some(NamingBand {
+        id: id.clone(),
+        code: text("code"),
+        name: text("name").unwrap_or_else(|| id.clone()),
+    })
+}
+
+/// Imports a bundle as a new project. Each collision reported by
+/// `inspect_project_bundle` needs a resolution: map, rename or skip.
+#[tauri::command]
+fn import_project_bundle(
+    app: tauri::AppHandle,
+    archive_path: String,
+    resolutions: Option<Vec<RecordResolution>>,
+) -> Result<BundleImportResult, ApiError> {
+    ensure_writable(&app, "BUNDLE_IMPORT_FAILED")?;
+    let bundle = read_bundle(Path::new(&archive_path))
+        .map_err(|err| map_storage_error(err, "BUNDLE_READ_FAILED", "Failed to read bundle"))?;
+    let library = library_dir(&app)?;
+    let plan = plan_import(
+        &bundle,
+        &data_root(&app)?,
+        &library,
+        &resolutions.unwrap_or_default(),
+    )
+    .map_err(|err| map_storage_error(err, "BUNDLE_IMPORT_FAILED", "Failed to import bundle"))?;
+
+    // The project is validated and named before anything is written.
+    let mut project = plan.project;
+    let project_id = project
+        .get("id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    if project_id.is_empty() || resolve_project_path_by_id(&app, &project_id)?.is_some() {
+        project["id"] = Value::String(generate_uuid_v7());
+    }
+    let project = validate_project(&project).map_err(invalid_project_error)?;
+    let project = serde_json::to_value(&project).map_err(|err| ApiError {
+        code: "BUNDLE_IMPORT_FAILED".into(),
+        message: format!("Failed to serialize project ({})", err),
+        export_pdf_path: None,
+        version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
+    })?;
+    let band_ref = project_band_ref(&project);
+    let band = find_naming_band(&app, &band_ref)
+        .or_else(|| imported_naming_band(&plan.library, &band_ref))
+        .ok_or_else(|| band_not_found("BUNDLE_IMPORT_FAILED", &band_ref))?;
+    let (project_path, json, created) =
+        prepare_project_copy(&app, project, &band, "BUNDLE_IMPORT_FAILED")?;
+    let pdf_target = match &plan.pdf {
+        Some(pdf) => {
+            let exports_root = exports_dir(&app).map_err(|err| {
+                map_storage_error(err, "BUNDLE_IMPORT_FAILED", "Failed to resolve exports dir")
+            })?;
+            let path =
+                safe_join(&exports_root, &format!("{}.pdf", created.slug)).map_err(|err| {
+                    map_storage_error(err, "BUNDLE_IMPORT_FAILED", "Invalid export path")
+                })?;
+            (!path.exists()).then_some((exports_root, path, pdf))
+        }
+        None => None,
+    };
+
+    fs::create_dir_all(&library).map_err(|err| {
+        map_io_error(
+            err,
+            "BUNDLE_IMPORT_FAILED",
+            "Failed to create library folder",
+        )
+    })?;
+    let library_files: BTreeSet<&str> = plan
+        .library
+        .iter()
+        .map(|(file, _, _)| file.as_str())
+        .collect();
+    let _locks = library_files
+        .into_iter()
+        .map(|file| lock_storage_file(&library.join(file)))
+        .chain(std::iter::once(lock_storage_file(&project_path)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| map_storage_error(err, "BUNDLE_IMPORT_FAILED", "Failed to lock files"))?;
+    let library_writes = merge_library_writes(&library, &plan.library)
+        .map_err(|err| map_storage_error(err, "BUNDLE_IMPORT_FAILED", "Failed to read library"))?;
+
+    // Library records, project and PDF land together or not at all.
+    let mut journal = FileJournal::new();
+    let result = (|| {
+        for (path, bytes) in &library_writes {
+            journal.write(path, bytes)?;
+        }
+        journal.write(&project_path, json.as_bytes())?;
+        if let Some((exports_root, path, pdf)) = &pdf_target {
+            fs::create_dir_all(exports_root)?;
+            journal.write(path, pdf)?;
+        }
+        Ok(())
+    })();
+    if let Err(err) = result {
+        journal.rollback();
+        return Err(map_storage_error(
+            err,
+            "BUNDLE_IMPORT_FAILED",
+            "Failed to import bundle",
+        ));
+    }
+    sync_project_index(&app, &project_path);
+    let pdf_path = pdf_target.map(|(_, path, _)| path.to_string_lossy().to_string());
+
+    Ok(BundleImportResult {
+        id: created.id,
+        slug: created.slug,
+        display_name: created.display_name,
+        records: plan.records,
+        pdf_path,
     })
 }
 
@@ -1012,24 +2831,24 @@ fn build_project_pdf_preview(
     ensure_user_storage(&app).map_err(|err| {
         map_storage_error(err, "PREVIEW_FAILED", "Failed to initialize user storage")
     })?;
-    let repo_root = resolve_repo_root();
-    let script_path = repo_root.join("scripts").join("desktop_preview.ts");
+    let engine = engine_root(&app)?;
+    let script_path = engine.script("desktop_preview");
     eprintln!(
         "[preview] command start project_id={} cwd={} script={}",
         project_id,
-        repo_root.display(),
+        engine.root.display(),
         script_path.display()
     );
 
     let output = Command::new("node")
-        .arg("--import")
-        .arg("tsx")
-        .arg(script_path.as_os_str())
+        .args(engine.node_args("desktop_preview"))
         .arg("--project-id")
         .arg(&project_id)
         .arg("--user-data-dir")
         .arg(user_data_dir.as_os_str())
-        .current_dir(&repo_root)
+        .arg("--library-dir")
+        .arg(library_dir(&app)?.as_os_str())
+        .current_dir(&engine.root)
         .output()
         .map_err(|err| map_io_error(err, "PREVIEW_FAILED", "Failed to execute preview"))?;
 
@@ -1049,6 +2868,8 @@ fn build_project_pdf_preview(
             ),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         })?;
 
     if response.ok {
@@ -1061,6 +2882,8 @@ fn build_project_pdf_preview(
                     message: "Preview succeeded but no preview path was returned.".into(),
                     export_pdf_path: None,
                     version_pdf_path: None,
+                    conflict: None,
+                    field_errors: None,
                 })?
                 .to_string();
             eprintln!("[preview] write success path={}", preview_pdf_path);
@@ -1086,6 +2909,8 @@ fn build_project_pdf_preview(
         message: "Preview could not be generated. Please retry. Check desktop logs for Chromium diagnostics.".into(),
         export_pdf_path: response.export_pdf_path,
         version_pdf_path: response.version_pdf_path,
+        conflict: None,
+        field_errors: None,
     })
 }
 
@@ -1216,30 +3041,50 @@ fn pick_export_pdf_path(
         message: format!("Failed to receive selected file path: {}", err),
         export_pdf_path: None,
         version_pdf_path: None,
+        conflict: None,
+        field_errors: None,
     })?;
 
     Ok(selected.map(|path| path.to_string_lossy().to_string()))
 }
 
 #[tauri::command]
-fn list_library_bands(app: tauri::AppHandle) -> Result<Vec<LibraryBand>, ApiError> {
-    let mut items = load_library_list::<LibraryBand>(&app, "bands.json")?;
-    items.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+fn list_library_bands(app: tauri::AppHandle) -> Result<Vec<Layered<LibraryBand>>, ApiError> {
+    let repository = data_repository(&app, "LIBRARY_READ_FAILED")?;
+    let mut items = readable_records(
+        repository
+            .bands()
+            .iter()
+            .map(|band| library_band(band, "LIBRARY_READ_FAILED")),
+    );
+    items.sort_by(|a, b| {
+        a.record
+            .name
+            .to_lowercase()
+            .cmp(&b.record.name.to_lowercase())
+    });
     Ok(items)
 }
 
 #[tauri::command]
-fn read_library_band(app: tauri::AppHandle, band_id: String) -> Result<LibraryBand, ApiError> {
-    let items = load_library_list::<LibraryBand>(&app, "bands.json")?;
-    items
-        .into_iter()
+fn read_library_band(
+    app: tauri::AppHandle,
+    band_id: String,
+) -> Result<Layered<LibraryBand>, ApiError> {
+    let repository = data_repository(&app, "LIBRARY_READ_FAILED")?;
+    let band = repository
+        .bands()
+        .iter()
         .find(|item| item.id == band_id)
         .ok_or(ApiError {
             code: "LIBRARY_NOT_FOUND".into(),
             message: format!("Band not found: {}", band_id),
             export_pdf_path: None,
             version_pdf_path: None,
-        })
+            conflict: None,
+            field_errors: None,
+        })?;
+    library_band(band, "LIBRARY_READ_FAILED")
 }
 
 #[tauri::command]
@@ -1250,79 +3095,137 @@ fn upsert_library_band(app: tauri::AppHandle, band: LibraryBand) -> Result<(), A
             message: "Band id, name, and code are required.".into(),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         });
     }
-    let mut items = load_library_list::<LibraryBand>(&app, "bands.json")?;
-    if items
+    let repository = data_repository(&app, "LIBRARY_WRITE_FAILED")?;
+    let shipped_codes: Vec<String> = repository
+        .bands()
         .iter()
-        .any(|existing| existing.id != band.id && existing.code.eq_ignore_ascii_case(&band.code))
-    {
+        .filter(|existing| existing.id != band.id)
+        .filter_map(|existing| existing.text("code").map(|code| code.to_string()))
+        .collect();
+    update_library_list::<LibraryBand, _>(&app, "bands.json", |items| {
+        if shipped_codes
+            .iter()
+            .any(|code| code.eq_ignore_ascii_case(&band.code))
+            || items.iter().any(|existing| {
+                existing.id != band.id && existing.code.eq_ignore_ascii_case(&band.code)
+            })
+        {
+            return Err(ApiError {
+                code: "LIBRARY_VALIDATION_FAILED".into(),
+                message: format!("Band code '{}' is already used.", band.code),
+                export_pdf_path: None,
+                version_pdf_path: None,
+                conflict: None,
+                field_errors: None,
+            });
+        }
+        if let Some(existing) = items.iter_mut().find(|existing| existing.id == band.id) {
+            *existing = band;
+        } else {
+            items.push(band);
+        }
+        Ok(())
+    })
+}
+
+/// Deleting a library band that overrides a shipped one reverts to the
+/// shipped band; shipped bands themselves cannot be deleted.
+#[tauri::command]
+fn delete_library_band(app: tauri::AppHandle, band_id: String) -> Result<(), ApiError> {
+    let repository = data_repository(&app, "LIBRARY_WRITE_FAILED")?;
+    let Some(band) = repository.bands().iter().find(|item| item.id == band_id) else {
+        return Ok(());
+    };
+    if band.origin == RecordOrigin::Shipped {
         return Err(ApiError {
-            code: "LIBRARY_VALIDATION_FAILED".into(),
-            message: format!("Band code '{}' is already used.", band.code),
+            code: "LIBRARY_DELETE_BLOCKED".into(),
+            message:
+                "Band ships with StagePilot and cannot be deleted; edit it to override it instead."
+                    .into(),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         });
     }
-    if let Some(existing) = items.iter_mut().find(|existing| existing.id == band.id) {
-        *existing = band;
-    } else {
-        items.push(band);
-    }
-    save_library_list(&app, "bands.json", &items)
-}
-
-#[tauri::command]
-fn delete_library_band(app: tauri::AppHandle, band_id: String) -> Result<(), ApiError> {
     let projects = list_projects(app.clone())?;
-    if projects
-        .iter()
-        .any(|project| project.band_ref.as_deref() == Some(band_id.as_str()))
+    if !band.overrides_shipped
+        && projects
+            .iter()
+            .any(|project| project.band_ref.as_deref() == Some(band_id.as_str()))
     {
         return Err(ApiError {
             code: "LIBRARY_DELETE_BLOCKED".into(),
             message: "Band is referenced by existing projects and cannot be deleted.".into(),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         });
     }
-    let mut items = load_library_list::<LibraryBand>(&app, "bands.json")?;
-    items.retain(|item| item.id != band_id);
-    save_library_list(&app, "bands.json", &items)
+    update_library_list::<LibraryBand, _>(&app, "bands.json", |items| {
+        items.retain(|item| item.id != band_id);
+        Ok(())
+    })
 }
 
 #[tauri::command]
 fn duplicate_library_band(app: tauri::AppHandle, band_id: String) -> Result<LibraryBand, ApiError> {
-    let mut items = load_library_list::<LibraryBand>(&app, "bands.json")?;
-    let existing = items
+    let repository = data_repository(&app, "LIBRARY_WRITE_FAILED")?;
+    let existing = repository
+        .bands()
         .iter()
         .find(|item| item.id == band_id)
-        .cloned()
         .ok_or(ApiError {
             code: "LIBRARY_NOT_FOUND".into(),
             message: format!("Band not found: {}", band_id),
             export_pdf_path: None,
             version_pdf_path: None,
-        })?;
-    let mut candidate_id = format!("{}_copy", existing.id);
-    let mut index: usize = 2;
-    while items.iter().any(|item| item.id == candidate_id) {
-        candidate_id = format!("{}_copy_{}", existing.id, index);
-        index += 1;
-    }
-    let mut duplicate = existing.clone();
-    duplicate.id = candidate_id;
-    duplicate.name = format!("{} Copy", existing.name);
-    duplicate.code = format!("{}-COPY{}", existing.code, index.saturating_sub(1));
-    items.push(duplicate.clone());
-    save_library_list(&app, "bands.json", &items)?;
-    Ok(duplicate)
+            conflict: None,
+            field_errors: None,
+        })
+        .and_then(|band| library_band(band, "LIBRARY_WRITE_FAILED"))?
+        .record;
+    update_library_list::<LibraryBand, _>(&app, "bands.json", |items| {
+        let taken = |id: &str| {
+            items.iter().any(|item| item.id == id) || repository.bands().iter().any(|b| b.id == id)
+        };
+        let mut candidate_id = format!("{}_copy", existing.id);
+        let mut index: usize = 2;
+        while taken(&candidate_id) {
+            candidate_id = format!("{}_copy_{}", existing.id, index);
+            index += 1;
+        }
+        let mut duplicate = existing.clone();
+        duplicate.id = candidate_id;
+        duplicate.name = format!("{} Copy", existing.name);
+        duplicate.code = format!("{}-COPY{}", existing.code, index.saturating_sub(1));
+        items.push(duplicate.clone());
+        Ok(duplicate)
+    })
 }
 
 #[tauri::command]
-fn list_library_musicians(app: tauri::AppHandle) -> Result<Vec<LibraryMusician>, ApiError> {
-    let mut items = load_library_list::<LibraryMusician>(&app, "musicians.json")?;
-    items.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+fn list_library_musicians(
+    app: tauri::AppHandle,
+) -> Result<Vec<Layered<LibraryMusician>>, ApiError> {
+    let repository = data_repository(&app, "LIBRARY_READ_FAILED")?;
+    let mut items = readable_records(
+        repository
+            .musicians()
+            .iter()
+            .map(|musician| library_musician(musician, "LIBRARY_READ_FAILED")),
+    );
+    items.sort_by(|a, b| {
+        a.record
+            .name
+            .to_lowercase()
+            .cmp(&b.record.name.to_lowercase())
+    });
     Ok(items)
 }
 
@@ -1337,15 +3240,18 @@ fn upsert_library_musician(
             message: "Musician id and name are required.".into(),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         });
     }
-    let mut items = load_library_list::<LibraryMusician>(&app, "musicians.json")?;
-    if let Some(existing) = items.iter_mut().find(|item| item.id == musician.id) {
-        *existing = musician;
-    } else {
-        items.push(musician);
-    }
-    save_library_list(&app, "musicians.json", &items)
+    update_library_list::<LibraryMusician, _>(&app, "musicians.json", |items| {
+        if let Some(existing) = items.iter_mut().find(|item| item.id == musician.id) {
+            *existing = musician;
+        } else {
+            items.push(musician);
+        }
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -1361,33 +3267,59 @@ fn update_musician_defaults(
             message: "Musician id is required.".into(),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         });
     }
     let normalized_role = role.trim().to_lowercase();
     let key = format!("{}:{}", musician_id.trim(), normalized_role);
-    let mut defaults = load_library_map::<Value>(&app, "musician_defaults.json")?;
-    defaults.insert(key, setup);
-    save_library_map(&app, "musician_defaults.json", &defaults)
+    update_library_map::<Value, _>(&app, "musician_defaults.json", |defaults| {
+        defaults.insert(key, setup);
+        Ok(())
+    })
 }
 
+/// Like bands, deleting a user override reverts to the shipped musician.
 #[tauri::command]
 fn delete_library_musician(app: tauri::AppHandle, musician_id: String) -> Result<(), ApiError> {
-    let bands = load_library_list::<LibraryBand>(&app, "bands.json")?;
-    if bands.iter().any(|band| {
-        band.members
-            .iter()
-            .any(|member| member.musician_id == musician_id)
-    }) {
+    let repository = data_repository(&app, "LIBRARY_WRITE_FAILED")?;
+    let Some(musician) = repository.musician(&musician_id) else {
+        return Ok(());
+    };
+    if musician.origin == RecordOrigin::Shipped {
+        return Err(ApiError {
+            code: "LIBRARY_DELETE_BLOCKED".into(),
+            message: "Musician ships with StagePilot and cannot be deleted; edit it to override it instead.".into(),
+            export_pdf_path: None,
+            version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
+        });
+    }
+    let referenced = repository.bands().iter().any(|band| {
+        band.value
+            .get("members")
+            .and_then(|v| v.as_array())
+            .is_some_and(|members| {
+                members.iter().any(|member| {
+                    member.get("musicianId").and_then(|v| v.as_str()) == Some(musician_id.as_str())
+                })
+            })
+    });
+    if referenced && !musician.overrides_shipped {
         return Err(ApiError {
             code: "LIBRARY_DELETE_BLOCKED".into(),
             message: "Musician is referenced by a band and cannot be deleted.".into(),
             export_pdf_path: None,
             version_pdf_path: None,
+            conflict: None,
+            field_errors: None,
         });
     }
-    let mut items = load_library_list::<LibraryMusician>(&app, "musicians.json")?;
-    items.retain(|item| item.id != musician_id);
-    save_library_list(&app, "musicians.json", &items)
+    update_library_list::<LibraryMusician, _>(&app, "musicians.json", |items| {
+        items.retain(|item| item.id != musician_id);
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -1399,19 +3331,21 @@ fn upsert_library_instrument(
     app: tauri::AppHandle,
     instrument: LibraryInstrument,
 ) -> Result<(), ApiError> {
-    let mut items = load_library_list::<LibraryInstrument>(&app, "instruments.json")?;
-    if let Some(existing) = items.iter_mut().find(|item| item.id == instrument.id) {
-        *existing = instrument;
-    } else {
-        items.push(instrument);
-    }
-    save_library_list(&app, "instruments.json", &items)
+    update_library_list::<LibraryInstrument, _>(&app, "instruments.json", |items| {
+        if let Some(existing) = items.iter_mut().find(|item| item.id == instrument.id) {
+            *existing = instrument;
+        } else {
+            items.push(instrument);
+        }
+        Ok(())
+    })
 }
 #[tauri::command]
 fn delete_library_instrument(app: tauri::AppHandle, instrument_id: String) -> Result<(), ApiError> {
-    let mut items = load_library_list::<LibraryInstrument>(&app, "instruments.json")?;
-    items.retain(|item| item.id != instrument_id);
-    save_library_list(&app, "instruments.json", &items)
+    update_library_list::<LibraryInstrument, _>(&app, "instruments.json", |items| {
+        items.retain(|item| item.id != instrument_id);
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -1420,19 +3354,21 @@ fn list_library_contacts(app: tauri::AppHandle) -> Result<Vec<LibraryContact>, A
 }
 #[tauri::command]
 fn upsert_library_contact(app: tauri::AppHandle, contact: LibraryContact) -> Result<(), ApiError> {
-    let mut items = load_library_list::<LibraryContact>(&app, "contacts.json")?;
-    if let Some(existing) = items.iter_mut().find(|item| item.id == contact.id) {
-        *existing = contact;
-    } else {
-        items.push(contact);
-    }
-    save_library_list(&app, "contacts.json", &items)
+    update_library_list::<LibraryContact, _>(&app, "contacts.json", |items| {
+        if let Some(existing) = items.iter_mut().find(|item| item.id == contact.id) {
+            *existing = contact;
+        } else {
+            items.push(contact);
+        }
+        Ok(())
+    })
 }
 #[tauri::command]
 fn delete_library_contact(app: tauri::AppHandle, contact_id: String) -> Result<(), ApiError> {
-    let mut items = load_library_list::<LibraryContact>(&app, "contacts.json")?;
-    items.retain(|item| item.id != contact_id);
-    save_library_list(&app, "contacts.json", &items)
+    update_library_list::<LibraryContact, _>(&app, "contacts.json", |items| {
+        items.retain(|item| item.id != contact_id);
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -1444,19 +3380,21 @@ fn upsert_library_message(
     app: tauri::AppHandle,
     message_item: LibraryMessage,
 ) -> Result<(), ApiError> {
-    let mut items = load_library_list::<LibraryMessage>(&app, "messages.json")?;
-    if let Some(existing) = items.iter_mut().find(|item| item.id == message_item.id) {
-        *existing = message_item;
-    } else {
-        items.push(message_item);
-    }
-    save_library_list(&app, "messages.json", &items)
+    update_library_list::<LibraryMessage, _>(&app, "messages.json", |items| {
+        if let Some(existing) = items.iter_mut().find(|item| item.id == message_item.id) {
+            *existing = message_item;
+        } else {
+            items.push(message_item);
+        }
+        Ok(())
+    })
 }
 #[tauri::command]
 fn delete_library_message(app: tauri::AppHandle, message_id: String) -> Result<(), ApiError> {
-    let mut items = load_library_list::<LibraryMessage>(&app, "messages.json")?;
-    items.retain(|item| item.id != message_id);
-    save_library_list(&app, "messages.json", &items)
+    update_library_list::<LibraryMessage, _>(&app, "messages.json", |items| {
+        items.retain(|item| item.id != message_id);
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -1529,26 +3467,90 @@ pub fn run() {
                     format!("Failed to apply dev storage wipe: {:?}", err),
                 ))
             })?;
-            ensure_user_storage(&app.handle()).map_err(|err| {
+            let meta = ensure_user_storage(&app.handle()).map_err(|err| {
                 tauri::Error::from(std::io::Error::new(
                     std::io::ErrorKind::Other,
                     format!("Failed to initialize user storage: {:?}", err),
                 ))
             })?;
+            match locate_engine(&engine_candidates(app.path().resource_dir().ok())) {
+                Ok(engine) => eprintln!(
+                    "[resources] engine root={} origin={:?}",
+                    engine.root.display(),
+                    engine.origin
+                ),
+                Err(err) => eprintln!("[resources] {:?}", err),
+            }
+            let root = user_storage_root(app.handle());
+            if let (false, Ok(root)) = (is_read_only(&meta), root) {
+                if let Err(err) = refresh_project_index(&root, true) {
+                    eprintln!("[project] index refresh failed: {:?}", err);
+                }
+                let max_age = Duration::from_secs(TRASH_RETENTION_DAYS * 24 * 60 * 60);
+                match purge_expired(&root, max_age) {
+                    Ok(0) => {}
+                    Ok(count) => eprintln!("[trash] purged {} expired entries", count),
+                    Err(err) => eprintln!("[trash] auto-purge failed: {:?}", err),
+                }
+                // Snapshots written by the CLI, or carried over by a storage
+                // move or restore, are stored as blobs here.
+                match dedupe_versions(&root) {
+                    Ok(0) => {}
+                    Ok(count) => eprintln!("[versions] stored {} versions as blobs", count),
+                    Err(err) => eprintln!("[versions] blob store update failed: {:?}", err),
+                }
+                if let Err(err) = collect_garbage(&root) {
+                    eprintln!("[versions] blob cleanup failed: {:?}", err);
+                }
+            }
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
             get_user_data_dir,
+            get_storage_info,
+            move_storage_root,
+            backup_storage,
+            restore_storage,
+            scan_storage,
+            repair_storage,
             list_projects,
+            list_projects_with_diagnostics,
+            query_projects,
+            quarantine_project_file,
             list_bands,
             get_band_setup_data,
             read_project,
+            read_project_document,
+            list_project_versions,
+            read_project_version,
+            open_version_pdf,
+            restore_project_version,
+            create_project_from_version,
+            diff_project_versions,
+            set_version_sent,
+            annotate_version,
+            record_version_sent,
+            list_version_distribution,
+            last_sent_version,
+            get_version_retention,
+            set_version_retention,
+            prune_versions,
             save_project,
+            duplicate_project,
+            rename_project,
             delete_project,
             delete_project_permanently,
+            list_trash,
+            restore_trashed_project,
+            purge_trash,
+            purge_expired_trash,
             export_pdf,
+            get_export_status,
+            export_project_bundle,
+            inspect_project_bundle,
+            import_project_bundle,
             build_project_pdf_preview,
             read_preview_pdf_bytes,
             cleanup_preview_pdf,
@@ -0,0 +1,1139 @@
+//! Portable `.stagepilot` bundles: one project together with every band,
+//! musician, contact and preset it references, so a colleague can open and
+//! re-export it. A bundle is a gzip-compressed tar holding `bundle.json` (the
+//! manifest), `project.json`, one `records/<n>.json` per record and
+//! optionally the latest PDF. Records come from the shipped `data/` folder
+//! and from the user `library/` files. Imports only ever write the library:
+//! the shipped folder is part of the read-only app bundle.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::data_repository::resolve_record;
+use crate::export_status::collect_files;
+use crate::project_migrations::{migrate_project_json, LIFECYCLE_KEYS};
+use crate::storage_backup::append_bytes;
+use crate::storage_paths::{now_iso, sha256_hex, StorageError};
+
+pub const BUNDLE_EXTENSION: &str = "stagepilot";
+const BUNDLE_FORMAT: &str = "stagepilot-bundle";
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "bundle.json";
+const PROJECT_ENTRY: &str = "project.json";
+const PDF_ENTRY: &str = "export.pdf";
+
+pub const LIBRARY_BANDS: &str = "bands.json";
+pub const LIBRARY_MUSICIANS: &str = "musicians.json";
+pub const LIBRARY_CONTACTS: &str = "contacts.json";
+/// Presets imported from bundles; the app's own presets live in `data/`.
+pub const LIBRARY_PRESETS: &str = "presets.json";
+/// Keyed `<musicianId>:<role>`; bundled per musician as a role -> setup map.
+pub const LIBRARY_MUSICIAN_DEFAULTS: &str = "musician_defaults.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordKind {
+    Band,
+    Musician,
+    Contact,
+    Preset,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordSource {
+    /// A file under the shipped `data/` folder.
+    Shipped,
+    /// An entry of a `library/` JSON file.
+    Library,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordRef {
+    pub kind: RecordKind,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRecord {
+    pub kind: RecordKind,
+    pub id: String,
+    pub source: RecordSource,
+    /// Path below `data/` for shipped records, library file name otherwise.
+    pub file: String,
+    /// Archive entry holding the record's JSON.
+    pub entry: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePdf {
+    pub file_name: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: String,
+    pub project_id: String,
+    pub project_sha256: String,
+    pub records: Vec<BundleRecord>,
+    /// References the sender could not resolve; the bundle works without them
+    /// only if the receiver has them.
+    #[serde(default)]
+    pub missing: Vec<RecordRef>,
+    #[serde(default)]
+    pub pdf: Option<BundlePdf>,
+}
+
+/// A bundle read into memory and verified against its manifest.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub project: Value,
+    pub records: Vec<(BundleRecord, Value)>,
+    pub pdf: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordStatus {
+    /// The local record has the same content.
+    Identical,
+    /// Nothing with this id exists locally.
+    New,
+    /// A different local record has this id; needs a `RecordResolution`.
+    Collision,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordPreview {
+    pub kind: RecordKind,
+    pub id: String,
+    pub source: RecordSource,
+    pub status: RecordStatus,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePreview {
+    pub project_id: String,
+    pub display_name: Option<String>,
+    pub created_at: String,
+    pub has_pdf: bool,
+    pub records: Vec<RecordPreview>,
+    pub missing: Vec<RecordRef>,
+}
+
+/// What to do with a bundled record whose id is taken locally. Also accepted
+/// for new records, e.g. to map a musician onto an existing local one.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum CollisionAction {
+    /// Use the local record `target` instead of the bundled one.
+    Map { target: String },
+    /// Import under `newId`, or `<id>_2`, `<id>_3`, ... when not given.
+    Rename {
+        #[serde(default, rename = "newId")]
+        new_id: Option<String>,
+    },
+    /// Keep the local record with the same id.
+    Skip,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordResolution {
+    pub kind: RecordKind,
+    pub id: String,
+    #[serde(flatten)]
+    pub action: CollisionAction,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportOutcome {
+    Imported,
+    Unchanged,
+    Mapped,
+    Renamed,
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedRecord {
+    pub kind: RecordKind,
+    pub id: String,
+    pub outcome: ImportOutcome,
+    /// Id the imported project now refers to.
+    pub local_id: String,
+}
+
+/// Everything an import writes; the caller applies it under the usual locks.
+#[derive(Debug, Clone)]
+pub struct ImportPlan {
+    /// Project with references rewritten to local ids.
+    pub project: Value,
+    /// Records as (library file name, id, JSON), whatever layer the sender
+    /// had them in. Musician defaults are a role -> setup map.
+    pub library: Vec<(String, String, Value)>,
+    pub pdf: Option<Vec<u8>>,
+    pub records: Vec<ImportedRecord>,
+}
+
+#[derive(Debug, Clone)]
+struct LocalRecord {
+    kind: RecordKind,
+    id: String,
+    source: RecordSource,
+    file: String,
+    value: Value,
+}
+
+impl LocalRecord {
+    /// Bands are also referenced by their code.
+    fn answers(&self, kind: RecordKind, reference: &str) -> bool {
+        self.kind == kind
+            && (self.id == reference
+                || (kind == RecordKind::Band
+                    && self
+                        .value
+                        .get("code")
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|code| code.eq_ignore_ascii_case(reference))))
+    }
+}
+
+fn ref_kind(key: &str) -> Option<RecordKind> {
+    match key {
+        "bandRef" => Some(RecordKind::Band),
+        "lineup" | "defaultLineup" | "default_lineup" | "backVocalIds" | "bandLeaderId"
+        | "talkbackOwnerId" | "bandLeader" | "musicianId" | "musician_id" => {
+            Some(RecordKind::Musician)
+        }
+        "contactRef" | "defaultContactId" => Some(RecordKind::Contact),
+        "ref" | "monitorRef" => Some(RecordKind::Preset),
+        _ => None,
+    }
+}
+
+type Visit<'a> = dyn FnMut(RecordKind, &mut String) + 'a;
+
+/// Calls `visit` for every id a project, band, musician or setup refers to.
+fn walk_refs(value: &mut Value, visit: &mut Visit) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                match ref_kind(key) {
+                    Some(kind) => visit_ref_values(field, kind, visit),
+                    None => walk_refs(field, visit),
+                }
+            }
+            if let Some(Value::Object(overrides)) = fields.get_mut("powerOverridesByMusician") {
+                *overrides = std::mem::take(overrides)
+                    .into_iter()
+                    .map(|(mut id, value)| {
+                        visit(RecordKind::Musician, &mut id);
+                        (id, value)
+                    })
+                    .collect();
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                walk_refs(item, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A reference value: an id, a list of ids, a role -> id(s) map such as a
+/// lineup, or a lineup slot carrying `musicianId`.
+fn visit_ref_values(value: &mut Value, kind: RecordKind, visit: &mut Visit) {
+    if value.get("musicianId").is_some() || value.get("musician_id").is_some() {
+        walk_refs(value, visit);
+        return;
+    }
+    match value {
+        Value::String(id) => visit(kind, id),
+        Value::Array(items) => {
+            for item in items {
+                visit_ref_values(item, kind, visit);
+            }
+        }
+        Value::Object(fields) => {
+            for field in fields.values_mut() {
+                visit_ref_values(field, kind, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn references(value: &Value) -> Vec<(RecordKind, String)> {
+    let mut refs = Vec::new();
+    walk_refs(&mut value.clone(), &mut |kind, id| {
+        refs.push((kind, id.clone()))
+    });
+    refs
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn record_id(value: &Value) -> Option<String> {
+    value
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn shipped_records(data_root: &Path) -> Vec<LocalRecord> {
+    let mut records = Vec::new();
+    for (kind, folder) in [
+        (RecordKind::Band, "bands"),
+        (RecordKind::Musician, "musicians"),
+        (RecordKind::Contact, "contacts"),
+        (RecordKind::Preset, "assets/presets"),
+    ] {
+        let mut files = Vec::new();
+        collect_files(&data_root.join(folder), &mut files);
+        files.sort();
+        for path in files {
+            if path.extension().and_then(|v| v.to_str()) != Some("json") {
+                continue;
+            }
+            let (Some(value), Ok(relative)) = (read_json(&path), path.strip_prefix(data_root))
+            else {
+                continue;
+            };
+            let stem = path.file_stem().and_then(|v| v.to_str()).unwrap_or("");
+            records.push(LocalRecord {
+                kind,
+                id: record_id(&value).unwrap_or_else(|| stem.to_string()),
+                source: RecordSource::Shipped,
+                file: relative.to_string_lossy().replace('\\', "/"),
+                value,
+            });
+        }
+    }
+    records
+}
+
+fn library_records(library_dir: &Path) -> Vec<LocalRecord> {
+    let mut records = Vec::new();
+    for (kind, file) in [
+        (RecordKind::Band, LIBRARY_BANDS),
+        (RecordKind::Musician, LIBRARY_MUSICIANS),
+        (RecordKind::Contact, LIBRARY_CONTACTS),
+        (RecordKind::Preset, LIBRARY_PRESETS),
+    ] {
+        let Some(Value::Array(items)) = read_json(&library_dir.join(file)) else {
+            continue;
+        };
+        records.extend(items.into_iter().filter_map(|value| {
+            Some(LocalRecord {
+                kind,
+                id: record_id(&value)?,
+                source: RecordSource::Library,
+                file: file.into(),
+                value,
+            })
+        }));
+    }
+    if let Some(Value::Object(defaults)) = read_json(&library_dir.join(LIBRARY_MUSICIAN_DEFAULTS)) {
+        let mut by_musician: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+        for (key, setup) in defaults {
+            if let Some((musician_id, role)) = key.split_once(':') {
+                by_musician
+                    .entry(musician_id.to_string())
+                    .or_default()
+                    .insert(role.to_string(), setup);
+            }
+        }
+        records.extend(by_musician.into_iter().map(|(id, roles)| LocalRecord {
+            kind: RecordKind::Musician,
+            id,
+            source: RecordSource::Library,
+            file: LIBRARY_MUSICIAN_DEFAULTS.into(),
+            value: Value::Object(roles),
+        }));
+    }
+    records
+}
+
+fn local_records(data_root: &Path, library_dir: &Path) -> Vec<LocalRecord> {
+    let mut records = shipped_records(data_root);
+    records.extend(library_records(library_dir));
+    records
+}
+
+/// Writes a bundle of `project` and the records it references, directly or
+/// through its band and musicians.
+pub fn create_bundle(
+    archive_path: &Path,
+    project: &Value,
+    data_root: &Path,
+    library_dir: &Path,
+    pdf: Option<(&str, &[u8])>,
+) -> Result<BundleManifest, StorageError> {
+    let locals = local_records(data_root, library_dir);
+    let mut chosen = BTreeSet::new();
+    let mut missing = BTreeSet::new();
+    let mut seen = BTreeSet::new();
+    let mut pending = references(project);
+    while let Some((kind, id)) = pending.pop() {
+        if id.trim().is_empty() || !seen.insert((kind, id.clone())) {
+            continue;
+        }
+        let mut found = false;
+        for (index, record) in locals.iter().enumerate() {
+            if record.answers(kind, &id) {
+                found = true;
+                if chosen.insert(index) {
+                    pending.extend(references(&record.value));
+                }
+            }
+        }
+        if !found {
+            missing.insert(RecordRef { kind, id });
+        }
+    }
+
+    let mut selected: Vec<&LocalRecord> = chosen.iter().map(|index| &locals[*index]).collect();
+    selected.sort_by(|a, b| (a.kind, &a.id, a.source).cmp(&(b.kind, &b.id, b.source)));
+    let project_bytes = serde_json::to_vec_pretty(project)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize project: {e}")))?;
+    let mut entries: Vec<(String, Vec<u8>)> = vec![(PROJECT_ENTRY.into(), project_bytes.clone())];
+    let mut records = Vec::with_capacity(selected.len());
+    for (index, record) in selected.into_iter().enumerate() {
+        let bytes = serde_json::to_vec_pretty(&record.value)
+            .map_err(|e| StorageError::Resolve(format!("Failed to serialize record: {e}")))?;
+        let entry = format!("records/{}.json", index + 1);
+        records.push(BundleRecord {
+            kind: record.kind,
+            id: record.id.clone(),
+            source: record.source,
+            file: record.file.clone(),
+            entry: entry.clone(),
+            sha256: sha256_hex(&bytes),
+        });
+        entries.push((entry, bytes));
+    }
+    if let Some((_, bytes)) = pdf {
+        entries.push((PDF_ENTRY.into(), bytes.to_vec()));
+    }
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.into(),
+        format_version: BUNDLE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").into(),
+        created_at: now_iso(),
+        project_id: record_id(project).unwrap_or_default(),
+        project_sha256: sha256_hex(&project_bytes),
+        records,
+        missing: missing.into_iter().collect(),
+        pdf: pdf.map(|(file_name, bytes)| BundlePdf {
+            file_name: file_name.into(),
+            sha256: sha256_hex(bytes),
+        }),
+    };
+
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let partial_path = archive_path.with_extension("partial");
+    if let Err(err) = write_bundle(&partial_path, &manifest, &entries) {
+        let _ = fs::remove_file(&partial_path);
+        return Err(err);
+    }
+    fs::rename(&partial_path, archive_path)?;
+    Ok(manifest)
+}
+
+fn write_bundle(
+    archive_path: &Path,
+    manifest: &BundleManifest,
+    entries: &[(String, Vec<u8>)],
+) -> Result<(), StorageError> {
+    let file = File::create(archive_path)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let manifest_json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize bundle manifest: {e}")))?;
+    append_bytes(&mut builder, MANIFEST_ENTRY, &manifest_json, mtime)?;
+    for (path, bytes) in entries {
+        append_bytes(&mut builder, path, bytes, mtime)?;
+    }
+    let encoder = builder.into_inner()?;
+    encoder.finish()?.sync_all()?;
+    Ok(())
+}
+
+/// Reads a bundle and checks every entry against the manifest checksums.
+pub fn read_bundle(archive_path: &Path) -> Result<Bundle, StorageError> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+    let mut entries: HashMap<String, Vec<u8>> = HashMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().replace('\\', "/");
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        entries.insert(path, bytes);
+    }
+
+    let manifest: BundleManifest = entries
+        .get(MANIFEST_ENTRY)
+        .ok_or(StorageError::Resolve("Bundle has no bundle.json".into()))
+        .and_then(|bytes| {
+            serde_json::from_slice(bytes)
+                .map_err(|e| StorageError::Resolve(format!("Invalid bundle manifest JSON: {e}")))
+        })?;
+    if manifest.format != BUNDLE_FORMAT || manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(StorageError::Resolve(format!(
+            "Unsupported bundle format {} v{}",
+            manifest.format, manifest.format_version
+        )));
+    }
+    let verified = |entry: &str, sha256: &str| -> Result<&Vec<u8>, StorageError> {
+        match entries.get(entry) {
+            Some(bytes) if sha256_hex(bytes) == sha256 => Ok(bytes),
+            Some(_) => Err(StorageError::Resolve(format!(
+                "Checksum mismatch for {} in bundle",
+                entry
+            ))),
+            None => Err(StorageError::Resolve(format!(
+                "{} is listed in the bundle manifest but missing",
+                entry
+            ))),
+        }
+    };
+    let parse = |entry: &str, bytes: &[u8]| -> Result<Value, StorageError> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| StorageError::Resolve(format!("Invalid JSON in {}: {e}", entry)))
+    };
+
+    let project = parse(
+        PROJECT_ENTRY,
+        verified(PROJECT_ENTRY, &manifest.project_sha256)?,
+    )?;
+    let mut records = Vec::with_capacity(manifest.records.len());
+    for record in &manifest.records {
+        let value = parse(&record.entry, verified(&record.entry, &record.sha256)?)?;
+        records.push((record.clone(), value));
+    }
+    let pdf = manifest
+        .pdf
+        .as_ref()
+        .map(|pdf| verified(PDF_ENTRY, &pdf.sha256).cloned())
+        .transpose()?;
+    Ok(Bundle {
+        manifest,
+        project,
+        records,
+        pdf,
+    })
+}
+
+/// `(kind, id)` as the app reads it from one side of an import: the library
+/// entry layered over the shipped record, as in `DataRepository`, and the
+/// musician defaults, which live in a file of their own.
+#[derive(Default)]
+struct Resolved {
+    record: Option<Value>,
+    defaults: Option<Value>,
+}
+
+/// Shipped musicians take their role from `musicians/<role>/`.
+fn shipped_group(file: &str) -> Option<&str> {
+    file.strip_prefix("musicians/")
+        .and_then(|rest| rest.rsplit_once('/'))
+        .map(|(folder, _)| folder)
+}
+
+fn resolve<'a>(parts: impl Iterator<Item = (RecordSource, &'a str, &'a Value)>) -> Resolved {
+    let (mut shipped, mut library, mut defaults) = (None, None, None);
+    for (source, file, value) in parts {
+        let mut value = value.clone();
+        match source {
+            RecordSource::Shipped => {
+                if let (Some(fields), Some(group)) = (value.as_object_mut(), shipped_group(file)) {
+                    fields
+                        .entry("group")
+                        .or_insert(Value::String(group.to_string()));
+                }
+                shipped = Some(value);
+            }
+            RecordSource::Library if file == LIBRARY_MUSICIAN_DEFAULTS => defaults = Some(value),
+            RecordSource::Library => library = Some(value),
+        }
+    }
+    Resolved {
+        record: resolve_record(shipped, library),
+        defaults,
+    }
+}
+
+fn resolve_local(locals: &[LocalRecord], kind: RecordKind, id: &str) -> Resolved {
+    resolve(
+        locals
+            .iter()
+            .filter(|local| local.kind == kind && local.id == id)
+            .map(|local| (local.source, local.file.as_str(), &local.value)),
+    )
+}
+
+fn resolve_bundled(bundle: &Bundle, kind: RecordKind, id: &str) -> Resolved {
+    resolve(
+        bundle
+            .records
+            .iter()
+            .filter(|(record, _)| record.kind == kind && record.id == id)
+            .map(|(record, value)| (record.source, record.file.as_str(), value)),
+    )
+}
+
+/// `None` when the bundle does not carry this part.
+fn part_status(local: &Option<Value>, bundled: &Option<Value>) -> Option<RecordStatus> {
+    let bundled = bundled.as_ref()?;
+    Some(match local {
+        None => RecordStatus::New,
+        Some(local) if local == bundled => RecordStatus::Identical,
+        Some(_) => RecordStatus::Collision,
+    })
+}
+
+/// Compares what the receiver resolves for `(kind, id)` with what the bundle
+/// resolves, whichever layer each side keeps it in: a bundled shipped record
+/// collides with a different library entry of the same id and vice versa.
+fn record_status(
+    locals: &[LocalRecord],
+    bundle: &Bundle,
+    kind: RecordKind,
+    id: &str,
+) -> RecordStatus {
+    let local = resolve_local(locals, kind, id);
+    let bundled = resolve_bundled(bundle, kind, id);
+    [
+        part_status(&local.record, &bundled.record),
+        part_status(&local.defaults, &bundled.defaults),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(RecordStatus::Identical)
+}
+
+/// How each bundled record relates to the local data and library.
+pub fn preview_bundle(bundle: &Bundle, data_root: &Path, library_dir: &Path) -> BundlePreview {
+    let locals = local_records(data_root, library_dir);
+    BundlePreview {
+        project_id: bundle.manifest.project_id.clone(),
+        display_name: bundle
+            .project
+            .get("displayName")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        created_at: bundle.manifest.created_at.clone(),
+        has_pdf: bundle.pdf.is_some(),
+        records: bundle
+            .records
+            .iter()
+            .map(|(record, _)| RecordPreview {
+                kind: record.kind,
+                id: record.id.clone(),
+                source: record.source,
+                status: record_status(&locals, bundle, record.kind, &record.id),
+            })
+            .collect(),
+        missing: bundle.manifest.missing.clone(),
+    }
+}
+
+/// Library file that receives an imported record of `kind`.
+fn library_file_for(kind: RecordKind) -> &'static str {
+    match kind {
+        RecordKind::Band => LIBRARY_BANDS,
+        RecordKind::Musician => LIBRARY_MUSICIANS,
+        RecordKind::Contact => LIBRARY_CONTACTS,
+        RecordKind::Preset => LIBRARY_PRESETS,
+    }
+}
+
+/// Works out the writes of an import. Every collision must have a resolution.
+pub fn plan_import(
+    bundle: &Bundle,
+    data_root: &Path,
+    library_dir: &Path,
+    resolutions: &[RecordResolution],
+) -> Result<ImportPlan, StorageError> {
+    let locals = local_records(data_root, library_dir);
+    let exists = |kind: RecordKind, id: &str| locals.iter().any(|l| l.kind == kind && l.id == id);
+    let bundled: BTreeSet<(RecordKind, String)> = bundle
+        .records
+        .iter()
+        .map(|(record, _)| (record.kind, record.id.clone()))
+        .collect();
+
+    let statuses: BTreeMap<(RecordKind, String), RecordStatus> = bundled
+        .iter()
+        .map(|(kind, id)| {
+            (
+                (*kind, id.clone()),
+                record_status(&locals, bundle, *kind, id),
+            )
+        })
+        .collect();
+
+    let mut ids: HashMap<(RecordKind, String), String> = HashMap::new();
+    let mut writes: BTreeSet<(RecordKind, String)> = BTreeSet::new();
+    let mut outcomes = Vec::new();
+    let mut unresolved = Vec::new();
+    for ((kind, id), status) in &statuses {
+        let key = (*kind, id.clone());
+        let action = resolutions
+            .iter()
+            .find(|r| r.kind == *kind && r.id == *id)
+            .map(|r| &r.action);
+        let (outcome, local_id) = match action {
+            Some(CollisionAction::Map { target }) => {
+                let target = target.trim();
+                if !exists(*kind, target) {
+                    return Err(StorageError::Resolve(format!(
+                        "Cannot map {:?} {} to unknown {}",
+                        kind, id, target
+                    )));
+                }
+                (ImportOutcome::Mapped, target.to_string())
+            }
+            Some(CollisionAction::Rename { new_id }) => {
+                let new_id = match new_id.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                    Some(new_id) => new_id.to_string(),
+                    None => (2..)
+                        .map(|n| format!("{}_{}", id, n))
+                        .find(|candidate| {
+                            !exists(*kind, candidate)
+                                && !bundled.contains(&(*kind, candidate.clone()))
+                        })
+                        .unwrap_or_default(),
+                };
+                if new_id.contains(['/', '\\', ':']) || exists(*kind, &new_id) {
+                    return Err(StorageError::Resolve(format!(
+                        "Cannot rename {:?} {} to {}",
+                        kind, id, new_id
+                    )));
+                }
+                writes.insert(key.clone());
+                (ImportOutcome::Renamed, new_id)
+            }
+            Some(CollisionAction::Skip) => (ImportOutcome::Skipped, id.clone()),
+            None if *status == RecordStatus::Collision => {
+                unresolved.push(format!("{:?} {}", kind, id).to_lowercase());
+                continue;
+            }
+            None if *status == RecordStatus::Identical => (ImportOutcome::Unchanged, id.clone()),
+            None => {
+                writes.insert(key.clone());
+                (ImportOutcome::Imported, id.clone())
+            }
+        };
+        outcomes.push(ImportedRecord {
+            kind: *kind,
+            id: id.clone(),
+            outcome,
+            local_id: local_id.clone(),
+        });
+        ids.insert(key, local_id);
+    }
+    if !unresolved.is_empty() {
+        return Err(StorageError::Resolve(format!(
+            "Unresolved collisions: {}",
+            unresolved.join(", ")
+        )));
+    }
+
+    let band_codes: HashMap<String, String> = bundle
+        .records
+        .iter()
+        .filter(|(record, _)| record.kind == RecordKind::Band)
+        .filter_map(|(record, value)| {
+            let code = value.get("code")?.as_str()?.to_lowercase();
+            Some((code, record.id.clone()))
+        })
+        .collect();
+    let rewrite = |value: &mut Value| {
+        walk_refs(value, &mut |kind, reference| {
+            let mut key = (kind, reference.clone());
+            if kind == RecordKind::Band && !ids.contains_key(&key) {
+                if let Some(id) = band_codes.get(&reference.to_lowercase()) {
+                    key.1 = id.clone();
+                }
+            }
+            if let Some(local_id) = ids.get(&key).filter(|local_id| **local_id != key.1) {
+                *reference = local_id.clone();
+            }
+        });
+    };
+
+    // One library entry per written record, holding what the sender resolved;
+    // parts the receiver already has unchanged are left alone unless renamed.
+    let mut library = Vec::new();
+    for (kind, id) in &writes {
+        let key = (*kind, id.clone());
+        let local_id = &ids[&key];
+        let renamed = local_id != id;
+        let local = resolve_local(&locals, *kind, id);
+        let bundled = resolve_bundled(bundle, *kind, id);
+        for (file, local_part, bundled_part) in [
+            (library_file_for(*kind), local.record, bundled.record),
+            (LIBRARY_MUSICIAN_DEFAULTS, local.defaults, bundled.defaults),
+        ] {
+            let status = part_status(&local_part, &bundled_part);
+            let Some(mut value) = bundled_part else {
+                continue;
+            };
+            if !renamed && status == Some(RecordStatus::Identical) {
+                continue;
+            }
+            rewrite(&mut value);
+            if value.get("id").is_some() {
+                value["id"] = Value::String(local_id.clone());
+            }
+            library.push((file.to_string(), local_id.clone(), value));
+        }
+    }
+
+    let mut project = bundle.project.clone();
+    migrate_project_json(&mut project);
+    rewrite(&mut project);
+    if let Some(fields) = project.as_object_mut() {
+        for key in LIFECYCLE_KEYS {
+            fields.remove(key);
+        }
+        fields.insert("status".into(), Value::String("active".into()));
+    }
+    Ok(ImportPlan {
+        project,
+        library,
+        pdf: bundle.pdf.clone(),
+        records: outcomes,
+    })
+}
+
+/// The new contents of every library file an import touches: list records
+/// replace the entry with the same id, musician defaults are merged per role.
+pub fn merge_library_writes(
+    library_dir: &Path,
+    records: &[(String, String, Value)],
+) -> Result<Vec<(PathBuf, Vec<u8>)>, StorageError> {
+    let mut files: BTreeMap<&str, Value> = BTreeMap::new();
+    for (file, id, record) in records {
+        if !files.contains_key(file.as_str()) {
+            let path = library_dir.join(file);
+            let current = if path.exists() {
+                serde_json::from_str(&fs::read_to_string(&path)?)
+                    .map_err(|e| StorageError::Resolve(format!("Invalid {} JSON: {e}", file)))?
+            } else if file == LIBRARY_MUSICIAN_DEFAULTS {
+                Value::Object(Map::new())
+            } else {
+                Value::Array(Vec::new())
+            };
+            files.insert(file, current);
+        }
+        match files.get_mut(file.as_str()) {
+            Some(Value::Object(defaults)) if file == LIBRARY_MUSICIAN_DEFAULTS => {
+                for (role, setup) in record.as_object().into_iter().flatten() {
+                    defaults.insert(format!("{}:{}", id, role), setup.clone());
+                }
+            }
+            Some(Value::Array(items)) => {
+                items.retain(|item| item.get("id").and_then(|v| v.as_str()) != Some(id));
+                items.push(record.clone());
+            }
+            _ => {
+                return Err(StorageError::Resolve(format!(
+                    "Unexpected shape of library file {}",
+                    file
+                )))
+            }
+        }
+    }
+    files
+        .into_iter()
+        .map(|(file, value)| {
+            let json = serde_json::to_vec_pretty(&value)
+                .map_err(|e| StorageError::Resolve(format!("Failed to serialize {file}: {e}")))?;
+            Ok((library_dir.join(file), json))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        create_bundle, merge_library_writes, plan_import, preview_bundle, read_bundle, Bundle,
+        BundleManifest, CollisionAction, ImportOutcome, RecordKind, RecordResolution, RecordStatus,
+    };
+    use crate::test_support::TempDir;
+    use serde_json::{json, Value};
+
+    /// Shipped band `b` with drummer `d1` (contact and monitor preset), an
+    /// unrelated drummer `d9` and library guest `g1`.
+    fn sender() -> TempDir {
+        let root = TempDir::new("bundle");
+        root.write(
+            "data/bands/b.json",
+            r#"{"id":"b","code":"B","defaultLineup":{"drums":"d1"}}"#,
+        );
+        root.write(
+            "data/musicians/drums/d1.json",
+            r#"{"id":"d1","contactRef":"d1","presets":[{"kind":"monitor","ref":"wedge"}]}"#,
+        );
+        root.write("data/musicians/drums/d9.json", r#"{"id":"d9"}"#);
+        root.write("data/contacts/d1.json", r#"{"id":"d1"}"#);
+        root.write(
+            "data/assets/presets/monitors/wedge.json",
+            r#"{"id":"wedge"}"#,
+        );
+        root.write("library/musicians.json", r#"[{"id":"g1","name":"Guest"}]"#);
+        root
+    }
+
+    fn project() -> Value {
+        json!({
+            "id": "p1",
+            "slug": "gig",
+            "bandRef": "B",
+            "lineup": {"drums": "d1", "guitar": {"musicianId": "g1"}},
+            "archivedAt": "2025-01-01T00:00:00.000Z"
+        })
+    }
+
+    fn bundle(root: &TempDir) -> BundleManifest {
+        create_bundle(
+            &root.join("gig.stagepilot"),
+            &project(),
+            &root.join("data"),
+            &root.join("library"),
+            Some(("gig.pdf", b"%PDF")),
+        )
+        .expect("create")
+    }
+
+    /// Bundles on the sender, then turns the tree into a receiver that has a
+    /// different `d1` and no `g1`.
+    fn received() -> (TempDir, Bundle) {
+        let root = sender();
+        bundle(&root);
+        root.write("data/musicians/drums/d1.json", r#"{"id":"d1","x":1}"#);
+        root.write("library/musicians.json", "[]");
+        let bundle = read_bundle(&root.join("gig.stagepilot")).expect("read");
+        (root, bundle)
+    }
+
+    fn rename_d1() -> RecordResolution {
+        RecordResolution {
+            kind: RecordKind::Musician,
+            id: "d1".into(),
+            action: CollisionAction::Rename { new_id: None },
+        }
+    }
+
+    #[test]
+    fn bundle_contains_only_referenced_records() {
+        let root = sender();
+        let manifest = bundle(&root);
+        let mut bundled: Vec<(RecordKind, &str)> = manifest
+            .records
+            .iter()
+            .map(|r| (r.kind, r.id.as_str()))
+            .collect();
+        bundled.sort();
+        assert_eq!(
+            bundled,
+            vec![
+                (RecordKind::Band, "b"),
+                (RecordKind::Musician, "d1"),
+                (RecordKind::Musician, "g1"),
+                (RecordKind::Contact, "d1"),
+                (RecordKind::Preset, "wedge"),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_bundle_returns_pdf() {
+        let (_root, bundle) = received();
+        assert_eq!(bundle.pdf.as_deref(), Some(&b"%PDF"[..]));
+    }
+
+    #[test]
+    fn preview_classifies_records_against_receiver() {
+        let (root, bundle) = received();
+        let preview = preview_bundle(&bundle, &root.join("data"), &root.join("library"));
+        let status = |kind, id: &str| {
+            preview
+                .records
+                .iter()
+                .find(|r| r.kind == kind && r.id == id)
+                .map(|r| r.status)
+        };
+        assert_eq!(
+            status(RecordKind::Musician, "d1"),
+            Some(RecordStatus::Collision)
+        );
+        assert_eq!(status(RecordKind::Musician, "g1"), Some(RecordStatus::New));
+        assert_eq!(status(RecordKind::Band, "b"), Some(RecordStatus::Identical));
+    }
+
+    #[test]
+    fn library_entry_collides_with_bundled_shipped_record() {
+        let root = sender();
+        bundle(&root);
+        // The receiver ships the same `d1` but keeps their own `d1` in the library.
+        root.write(
+            "library/musicians.json",
+            r#"[{"id":"d1","name":"My own Dan"}]"#,
+        );
+        let bundle = read_bundle(&root.join("gig.stagepilot")).expect("read");
+        let preview = preview_bundle(&bundle, &root.join("data"), &root.join("library"));
+        let d1 = preview
+            .records
+            .iter()
+            .find(|r| r.kind == RecordKind::Musician && r.id == "d1")
+            .expect("d1");
+        assert_eq!(d1.status, RecordStatus::Collision);
+        assert!(plan_import(&bundle, &root.join("data"), &root.join("library"), &[]).is_err());
+    }
+
+    #[test]
+    fn bundled_library_record_collides_with_shipped_record() {
+        let root = sender();
+        bundle(&root);
+        // The receiver ships a different `g1` and has no library entry for it.
+        root.write("library/musicians.json", "[]");
+        root.write(
+            "data/musicians/guitar/g1.json",
+            r#"{"id":"g1","firstName":"Gil"}"#,
+        );
+        let bundle = read_bundle(&root.join("gig.stagepilot")).expect("read");
+        let preview = preview_bundle(&bundle, &root.join("data"), &root.join("library"));
+        let g1 = preview
+            .records
+            .iter()
+            .find(|r| r.kind == RecordKind::Musician && r.id == "g1")
+            .expect("g1");
+        assert_eq!(g1.status, RecordStatus::Collision);
+    }
+
+    #[test]
+    fn unresolved_collision_blocks_import() {
+        let (root, bundle) = received();
+        assert!(plan_import(&bundle, &root.join("data"), &root.join("library"), &[]).is_err());
+    }
+
+    #[test]
+    fn renamed_record_is_rewritten_in_project() {
+        let (root, bundle) = received();
+        let plan = plan_import(
+            &bundle,
+            &root.join("data"),
+            &root.join("library"),
+            &[rename_d1()],
+        )
+        .expect("plan");
+        assert_eq!(plan.project["lineup"]["drums"], "d1_2");
+        assert_eq!(plan.project["lineup"]["guitar"]["musicianId"], "g1");
+        assert_eq!(plan.project["bandRef"], "B");
+        assert!(plan.project.get("archivedAt").is_none());
+        let d1 = plan
+            .records
+            .iter()
+            .find(|r| r.kind == RecordKind::Musician && r.id == "d1")
+            .expect("d1 outcome");
+        assert_eq!(d1.outcome, ImportOutcome::Renamed);
+    }
+
+    #[test]
+    fn import_plan_writes_only_new_records_into_the_library() {
+        let (root, bundle) = received();
+        let plan = plan_import(
+            &bundle,
+            &root.join("data"),
+            &root.join("library"),
+            &[rename_d1()],
+        )
+        .expect("plan");
+        let written: Vec<(&str, &str)> = plan
+            .library
+            .iter()
+            .map(|(file, id, _)| (file.as_str(), id.as_str()))
+            .collect();
+        assert_eq!(
+            written,
+            vec![("musicians.json", "d1_2"), ("musicians.json", "g1")]
+        );
+        let d1 = &plan.library[0].2;
+        assert_eq!(d1["id"], "d1_2");
+        assert_eq!(d1["group"], "drums");
+    }
+
+    #[test]
+    fn merged_library_writes_replace_by_id() {
+        let (root, bundle) = received();
+        root.write(
+            "library/musicians.json",
+            r#"[{"id":"g1","name":"Old"},{"id":"keep"}]"#,
+        );
+        let plan = plan_import(
+            &bundle,
+            &root.join("data"),
+            &root.join("library"),
+            &[
+                rename_d1(),
+                RecordResolution {
+                    kind: RecordKind::Musician,
+                    id: "g1".into(),
+                    action: CollisionAction::Rename {
+                        new_id: Some("g2".into()),
+                    },
+                },
+            ],
+        )
+        .expect("plan");
+        let writes = merge_library_writes(&root.join("library"), &plan.library).expect("merge");
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].0, root.join("library/musicians.json"));
+        let items: Value = serde_json::from_slice(&writes[0].1).expect("json");
+        let ids: Vec<&str> = items
+            .as_array()
+            .expect("list")
+            .iter()
+            .filter_map(|item| item["id"].as_str())
+            .collect();
+        assert_eq!(ids, vec!["g1", "keep", "d1_2", "g2"]);
+        assert!(!root.join("data/musicians/drums/d1_2.json").exists());
+    }
+}
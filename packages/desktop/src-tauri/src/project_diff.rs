@@ -0,0 +1,302 @@
+//! Semantic comparison of two states of a project ("what changed since the
+//! last rider?"). Inputs are raw project JSON, already upgraded with
+//! `migrate_project_json`; fields are read leniently so old snapshots that
+//! would not pass `validate_project` can still be compared.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Stands for the project file itself instead of a snapshot id.
+pub const CURRENT_PROJECT_STATE: &str = "current";
+
+/// Event and document metadata compared field by field.
+const METADATA_FIELDS: [&str; 8] = [
+    "purpose",
+    "eventDate",
+    "eventVenue",
+    "documentDate",
+    "note",
+    "displayName",
+    "slug",
+    "bandRef",
+];
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleChange {
+    pub role: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Musicians kept in the role whose `presetOverride` changed.
+    pub preset_changed: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueChange {
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerOverrideChange {
+    pub musician_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectDiff {
+    pub metadata: Vec<FieldChange>,
+    pub lineup: Vec<RoleChange>,
+    /// Musicians in the lineup of `to` but in no role of `from`, and vice versa.
+    pub musicians_added: Vec<String>,
+    pub musicians_removed: Vec<String>,
+    pub back_vocals_added: Vec<String>,
+    pub back_vocals_removed: Vec<String>,
+    pub band_leader: Option<ValueChange>,
+    /// Talkback defaults to the band leader when not set.
+    pub talkback_owner: Option<ValueChange>,
+    pub power_overrides: Vec<PowerOverrideChange>,
+}
+
+fn text(project: &Value, key: &str) -> Option<String> {
+    project
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
+/// Musicians of one role in slot order, with their preset override.
+fn role_slots(value: &Value) -> Vec<(String, Option<Value>)> {
+    let entry = |value: &Value| match value {
+        Value::String(id) if !id.trim().is_empty() => Some((id.trim().to_string(), None)),
+        Value::Object(slot) => slot
+            .get("musicianId")
+            .and_then(|v| v.as_str())
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| (id.trim().to_string(), slot.get("presetOverride").cloned())),
+        _ => None,
+    };
+    match value {
+        Value::Array(items) => items.iter().filter_map(entry).collect(),
+        value => entry(value).into_iter().collect(),
+    }
+}
+
+/// Every musician id in a lineup object (`lineup` of a project or
+/// `defaultLineup` of a band), in role order.
+pub(crate) fn lineup_musician_ids(lineup: &Value) -> Vec<String> {
+    lineup
+        .as_object()
+        .map(|roles| {
+            roles
+                .values()
+                .flat_map(|value| ids(&role_slots(value)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn lineup(project: &Value) -> BTreeMap<String, Vec<(String, Option<Value>)>> {
+    project
+        .get("lineup")
+        .and_then(|v| v.as_object())
+        .map(|roles| {
+            roles
+                .iter()
+                .map(|(role, value)| (role.clone(), role_slots(value)))
+                .filter(|(_, slots)| !slots.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn ids(slots: &[(String, Option<Value>)]) -> Vec<String> {
+    slots.iter().map(|(id, _)| id.clone()).collect()
+}
+
+fn string_set(project: &Value, key: &str) -> BTreeSet<String> {
+    project
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|v| v.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn value_change(before: Option<String>, after: Option<String>) -> Option<ValueChange> {
+    (before != after).then_some(ValueChange { before, after })
+}
+
+fn power_overrides(project: &Value) -> BTreeMap<String, Value> {
+    project
+        .pointer("/stageplan/powerOverridesByMusician")
+        .and_then(|v| v.as_object())
+        .map(|overrides| {
+            overrides
+                .iter()
+                .map(|(id, value)| (id.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn diff_projects(from: &Value, to: &Value) -> ProjectDiff {
+    let mut diff = ProjectDiff::default();
+
+    for field in METADATA_FIELDS {
+        let (before, after) = (from.get(field).cloned(), to.get(field).cloned());
+        let normalize = |value: Option<Value>| value.filter(|v| !v.is_null());
+        let (before, after) = (normalize(before), normalize(after));
+        if before != after {
+            diff.metadata.push(FieldChange {
+                field: field.into(),
+                before,
+                after,
+            });
+        }
+    }
+
+    let (old_lineup, new_lineup) = (lineup(from), lineup(to));
+    let roles: BTreeSet<&String> = old_lineup.keys().chain(new_lineup.keys()).collect();
+    for role in roles {
+        let before = old_lineup.get(role).cloned().unwrap_or_default();
+        let after = new_lineup.get(role).cloned().unwrap_or_default();
+        if before == after {
+            continue;
+        }
+        let (before_ids, after_ids) = (ids(&before), ids(&after));
+        let preset_changed = after
+            .iter()
+            .filter(|(id, preset)| {
+                before
+                    .iter()
+                    .any(|(old_id, old_preset)| old_id == id && old_preset != preset)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        diff.lineup.push(RoleChange {
+            role: role.clone(),
+            added: after_ids
+                .iter()
+                .filter(|id| !before_ids.contains(id))
+                .cloned()
+                .collect(),
+            removed: before_ids
+                .iter()
+                .filter(|id| !after_ids.contains(id))
+                .cloned()
+                .collect(),
+            before: before_ids,
+            after: after_ids,
+            preset_changed,
+        });
+    }
+
+    let members = |lineup: &BTreeMap<String, Vec<(String, Option<Value>)>>| -> BTreeSet<String> {
+        lineup.values().flat_map(|slots| ids(slots)).collect()
+    };
+    let (old_members, new_members) = (members(&old_lineup), members(&new_lineup));
+    diff.musicians_added = new_members.difference(&old_members).cloned().collect();
+    diff.musicians_removed = old_members.difference(&new_members).cloned().collect();
+
+    let (old_vocals, new_vocals) = (
+        string_set(from, "backVocalIds"),
+        string_set(to, "backVocalIds"),
+    );
+    diff.back_vocals_added = new_vocals.difference(&old_vocals).cloned().collect();
+    diff.back_vocals_removed = old_vocals.difference(&new_vocals).cloned().collect();
+
+    let (old_leader, new_leader) = (text(from, "bandLeaderId"), text(to, "bandLeaderId"));
+    diff.talkback_owner = value_change(
+        text(from, "talkbackOwnerId").or_else(|| old_leader.clone()),
+        text(to, "talkbackOwnerId").or_else(|| new_leader.clone()),
+    );
+    diff.band_leader = value_change(old_leader, new_leader);
+
+    let (old_power, new_power) = (power_overrides(from), power_overrides(to));
+    let musicians: BTreeSet<&String> = old_power.keys().chain(new_power.keys()).collect();
+    for musician in musicians {
+        let (before, after) = (old_power.get(musician), new_power.get(musician));
+        if before != after {
+            diff.power_overrides.push(PowerOverrideChange {
+                musician_id: musician.clone(),
+                before: before.cloned(),
+                after: after.cloned(),
+            });
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_projects, ProjectDiff};
+    use serde_json::json;
+
+    #[test]
+    fn reports_semantic_changes() {
+        let from = json!({
+            "eventVenue": "Lucerna",
+            "eventDate": "2025-03-07",
+            "lineup": {"drums": "d1", "guitar": ["g1", "g2"], "vocs": {"musicianId": "v1"}},
+            "backVocalIds": ["g1"],
+            "bandLeaderId": "g1",
+            "stageplan": {"powerOverridesByMusician": {"g1": {"voltage": 230, "sockets": 2}}}
+        });
+        let to = json!({
+            "eventVenue": "Roxy",
+            "eventDate": "2025-03-07",
+            "lineup": {
+                "drums": "d1",
+                "guitar": ["g1", "g3"],
+                "vocs": {"musicianId": "v1", "presetOverride": {"monitoring": {"mode": "stereo"}}}
+            },
+            "backVocalIds": ["g1", "g3"],
+            "bandLeaderId": "g1",
+            "talkbackOwnerId": "v1",
+            "stageplan": {"powerOverridesByMusician": {"g1": {"voltage": 230, "sockets": 4}}}
+        });
+        let diff = diff_projects(&from, &to);
+        assert_eq!(diff.metadata.len(), 1);
+        assert_eq!(diff.metadata[0].field, "eventVenue");
+        assert_eq!(diff.lineup.len(), 2);
+        assert_eq!(diff.lineup[0].role, "guitar");
+        assert_eq!(diff.lineup[0].added, vec!["g3"]);
+        assert_eq!(diff.lineup[0].removed, vec!["g2"]);
+        assert_eq!(diff.lineup[1].preset_changed, vec!["v1"]);
+        assert_eq!(diff.musicians_added, vec!["g3"]);
+        assert_eq!(diff.musicians_removed, vec!["g2"]);
+        assert_eq!(diff.back_vocals_added, vec!["g3"]);
+        assert!(diff.band_leader.is_none());
+        assert_eq!(
+            diff.talkback_owner
+                .as_ref()
+                .and_then(|c| c.before.as_deref()),
+            Some("g1")
+        );
+        assert_eq!(diff.power_overrides[0].musician_id, "g1");
+        assert_eq!(diff_projects(&to, &to), ProjectDiff::default());
+    }
+}
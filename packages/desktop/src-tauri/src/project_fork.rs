@@ -0,0 +1,117 @@
+//! Forking a snapshot from `versions/` into a new project, e.g. last summer's
+//! tour rider as the base for next year's. The source project and its history
+//! are not touched; the fork records its origin in `forkedFrom`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::project_migrations::{
+    migrate_project_json, LIFECYCLE_KEYS, PROJECT_SCHEMA_VERSION, PROJECT_SCHEMA_VERSION_KEY,
+};
+use crate::storage_paths::StorageError;
+
+pub const FORKED_FROM_KEY: &str = "forkedFrom";
+
+/// Stored as `forkedFrom` in the new project.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkOrigin {
+    pub project_id: String,
+    pub version_id: String,
+    /// `generatedAt` of the snapshot.
+    pub generated_at: String,
+    /// Content hash of the snapshot's `project.json`.
+    pub project_revision: String,
+    pub forked_at: String,
+}
+
+/// Turns a snapshot's project JSON into a new project with `new_id`. Legacy
+/// shapes are upgraded, lifecycle fields dropped and timestamps set to the
+/// fork time. Slug and display name are left for the caller to rebuild.
+pub fn fork_project(
+    mut snapshot: Value,
+    new_id: &str,
+    origin: &ForkOrigin,
+) -> Result<Value, StorageError> {
+    migrate_project_json(&mut snapshot);
+    let origin = serde_json::to_value(origin)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize fork origin: {e}")))?;
+    let Some(fields) = snapshot.as_object_mut() else {
+        return Err(StorageError::Resolve(
+            "Snapshot project is not a JSON object".into(),
+        ));
+    };
+    for key in LIFECYCLE_KEYS {
+        fields.remove(key);
+    }
+    let forked_at = origin["forkedAt"].clone();
+    fields.insert("id".into(), Value::String(new_id.into()));
+    fields.insert("status".into(), Value::String("active".into()));
+    fields.insert("createdAt".into(), forked_at.clone());
+    fields.insert("updatedAt".into(), forked_at);
+    fields.insert(
+        PROJECT_SCHEMA_VERSION_KEY.into(),
+        Value::from(PROJECT_SCHEMA_VERSION),
+    );
+    fields.insert(FORKED_FROM_KEY.into(), origin);
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fork_project, ForkOrigin, FORKED_FROM_KEY};
+    use serde_json::{json, Value};
+
+    fn snapshot() -> Value {
+        json!({
+            "id": "tour-2025",
+            "slug": "band_summer_2025",
+            "bandRef": "band",
+            "purpose": "generic",
+            "documentDate": "2025-05-01",
+            "status": "archived",
+            "archivedAt": "2025-09-01T00:00:00.000Z",
+            "createdAt": "2025-04-01T00:00:00.000Z",
+            "lineup": {"drums": "d1"}
+        })
+    }
+
+    fn origin() -> ForkOrigin {
+        ForkOrigin {
+            project_id: "tour-2025".into(),
+            version_id: "v3".into(),
+            generated_at: "2025-05-01T10:00:00.000Z".into(),
+            project_revision: "abc".into(),
+            forked_at: "2026-01-10T08:00:00.000Z".into(),
+        }
+    }
+
+    #[test]
+    fn fork_is_an_active_project_under_the_new_id() {
+        let fork = fork_project(snapshot(), "new-id", &origin()).expect("fork");
+        assert_eq!(fork["id"], "new-id");
+        assert_eq!(fork["status"], "active");
+        assert!(fork.get("archivedAt").is_none());
+        assert_eq!(fork["lineup"]["drums"], "d1");
+    }
+
+    #[test]
+    fn fork_timestamps_are_the_fork_time() {
+        let fork = fork_project(snapshot(), "new-id", &origin()).expect("fork");
+        assert_eq!(fork["createdAt"], "2026-01-10T08:00:00.000Z");
+        assert_eq!(fork["updatedAt"], "2026-01-10T08:00:00.000Z");
+    }
+
+    #[test]
+    fn fork_records_its_origin() {
+        let fork = fork_project(snapshot(), "new-id", &origin()).expect("fork");
+        let recorded: ForkOrigin =
+            serde_json::from_value(fork[FORKED_FROM_KEY].clone()).expect("origin");
+        assert_eq!(recorded, origin());
+    }
+
+    #[test]
+    fn non_object_snapshot_is_rejected() {
+        assert!(fork_project(json!([]), "new-id", &origin()).is_err());
+    }
+}
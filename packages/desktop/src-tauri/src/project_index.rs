@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::storage_lock::lock_storage_file;
+use crate::storage_paths::{atomic_write_bytes, sha256_hex, StorageError};
+use crate::timestamps::format_rfc3339;
+
+pub const PROJECT_INDEX_FILE_NAME: &str = "project_index.json";
+const PROJECT_INDEX_VERSION: u32 = 4;
+/// Coarsest mtime resolution we expect (FAT). A file indexed within this long
+/// of its mtime can change again without its mtime moving.
+const MTIME_GRANULARITY_MS: u64 = 2_000;
+
+/// Why a project file could not be indexed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexProblem {
+    /// `read`, `parse` or `notObject`.
+    pub kind: String,
+    pub message: String,
+}
+
+/// Cached summary of one file in `projects/`, keyed by file name in the index.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectIndexEntry {
+    pub id: String,
+    pub slug: Option<String>,
+    pub display_name: Option<String>,
+    pub band_ref: Option<String>,
+    pub event_date: Option<String>,
+    pub event_venue: Option<String>,
+    pub document_date: Option<String>,
+    pub purpose: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    /// File modification time in milliseconds, used to detect outside edits.
+    pub modified_ms: u64,
+    pub size: u64,
+    pub content_hash: String,
+    /// When the entry was built or its hash last confirmed, in milliseconds.
+    #[serde(default)]
+    pub indexed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<IndexProblem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectIndex {
+    pub version: u32,
+    pub entries: BTreeMap<String, ProjectIndexEntry>,
+}
+
+impl Default for ProjectIndex {
+    fn default() -> Self {
+        Self {
+            version: PROJECT_INDEX_VERSION,
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+fn index_path(root: &Path) -> PathBuf {
+    root.join(PROJECT_INDEX_FILE_NAME)
+}
+
+/// Reads the index; a missing, outdated or corrupt file yields an empty index
+/// that the next refresh rebuilds.
+fn load_index(root: &Path) -> ProjectIndex {
+    fs::read_to_string(index_path(root))
+        .ok()
+        .and_then(|content| serde_json::from_str::<ProjectIndex>(&content).ok())
+        .filter(|index| index.version == PROJECT_INDEX_VERSION)
+        .unwrap_or_default()
+}
+
+fn write_index(root: &Path, index: &ProjectIndex) -> Result<(), StorageError> {
+    let json = serde_json::to_vec_pretty(index)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize project index: {e}")))?;
+    atomic_write_bytes(&index_path(root), &json)
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as u64;
+    Some((modified, meta.len()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Whether an unchanged (mtime, size) stamp is enough to trust `entry`. If the
+/// entry was indexed within the mtime granularity, an edit may have kept the
+/// same stamp, so the content hash decides; a matching hash confirms the entry
+/// for later refreshes.
+fn entry_is_current(path: &Path, entry: &mut ProjectIndexEntry) -> bool {
+    if entry.indexed_ms >= entry.modified_ms.saturating_add(MTIME_GRANULARITY_MS) {
+        return true;
+    }
+    let Ok(bytes) = fs::read(path) else {
+        return false;
+    };
+    if sha256_hex(&bytes) != entry.content_hash {
+        return false;
+    }
+    entry.indexed_ms = now_ms();
+    true
+}
+
+fn text(json: &Value, key: &str) -> Option<String> {
+    json.get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// Builds the index entry for one project file from its current contents.
+pub fn index_project_file(path: &Path) -> ProjectIndexEntry {
+    let (modified_ms, size) = file_stamp(path).unwrap_or_default();
+    let mut entry = ProjectIndexEntry {
+        id: path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        modified_ms,
+        size,
+        indexed_ms: now_ms(),
+        ..ProjectIndexEntry::default()
+    };
+    let problem = |kind: &str, message: String| {
+        Some(IndexProblem {
+            kind: kind.into(),
+            message,
+        })
+    };
+
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            entry.problem = problem("read", err.to_string());
+            return entry;
+        }
+    };
+    entry.content_hash = sha256_hex(&bytes);
+    let json: Value = match serde_json::from_slice(&bytes) {
+        Ok(json) => json,
+        Err(err) => {
+            entry.problem = problem("parse", format!("Invalid project JSON: {}", err));
+            return entry;
+        }
+    };
+    if !json.is_object() {
+        entry.problem = problem("notObject", "Project file is not a JSON object".into());
+        return entry;
+    }
+    // Legacy files are listed as stored; `read_project` writes the upgrade and
+    // re-indexes them.
+    if let Some(id) = text(&json, "id") {
+        entry.id = id;
+    }
+    entry.slug = text(&json, "slug");
+    entry.display_name = text(&json, "displayName");
+    entry.band_ref = text(&json, "bandRef");
+    entry.event_date = text(&json, "eventDate");
+    entry.event_venue = text(&json, "eventVenue");
+    entry.document_date = text(&json, "documentDate");
+    entry.purpose = text(&json, "purpose");
+    entry.created_at = text(&json, "createdAt");
+    // Files saved before the backend stamped `updatedAt` fall back to their mtime.
+    entry.updated_at = text(&json, "updatedAt")
+        .or_else(|| entry.created_at.clone())
+        .or_else(|| {
+            (modified_ms > 0)
+                .then(|| format_rfc3339(UNIX_EPOCH + Duration::from_millis(modified_ms)))
+        });
+    entry
+}
+
+fn project_files(projects_dir: &Path) -> Result<Vec<PathBuf>, StorageError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(projects_dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Brings the index in line with `projects/`: new files and files whose mtime
+/// or size changed are re-read, files indexed within the mtime granularity are
+/// re-hashed, removed files are dropped. The index file is
+/// only rewritten when something changed and `persist` is set (read-only
+/// storage is refreshed in memory only).
+pub fn refresh_project_index(root: &Path, persist: bool) -> Result<ProjectIndex, StorageError> {
+    let _lock = lock_storage_file(&index_path(root))?;
+    let mut index = load_index(root);
+    let mut changed = !index_path(root).exists();
+    let mut seen = BTreeMap::new();
+
+    for path in project_files(&root.join("projects"))? {
+        let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let stamp = file_stamp(&path);
+        let entry = match index.entries.remove(file_name) {
+            Some(mut entry) if stamp == Some((entry.modified_ms, entry.size)) => {
+                let confirmed_ms = entry.indexed_ms;
+                if entry_is_current(&path, &mut entry) {
+                    changed |= entry.indexed_ms != confirmed_ms;
+                    entry
+                } else {
+                    changed = true;
+                    index_project_file(&path)
+                }
+            }
+            _ => {
+                changed = true;
+                index_project_file(&path)
+            }
+        };
+        seen.insert(file_name.to_string(), entry);
+    }
+    changed |= !index.entries.is_empty();
+    index.entries = seen;
+
+    if changed && persist {
+        write_index(root, &index)?;
+    }
+    Ok(index)
+}
+
+/// Re-indexes one project file after a write, or drops it if it is gone.
+pub fn update_project_index(root: &Path, project_path: &Path) -> Result<(), StorageError> {
+    let Some(file_name) = project_path.file_name().and_then(|s| s.to_str()) else {
+        return Ok(());
+    };
+    let _lock = lock_storage_file(&index_path(root))?;
+    let mut index = load_index(root);
+    if project_path.exists() {
+        index
+            .entries
+            .insert(file_name.to_string(), index_project_file(project_path));
+    } else {
+        index.entries.remove(file_name);
+    }
+    write_index(root, &index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{refresh_project_index, update_project_index, PROJECT_INDEX_FILE_NAME};
+    use crate::test_support::TempDir;
+    use std::fs::{self, File};
+
+    fn projects() -> TempDir {
+        let root = TempDir::new("index");
+        root.write(
+            "projects/a.json",
+            r#"{"id":"a","slug":"gig-a","bandRef":"band"}"#,
+        );
+        root.write("projects/bad.json", "{");
+        root
+    }
+
+    #[test]
+    fn refresh_indexes_projects_and_parse_problems() {
+        let root = projects();
+        let index = refresh_project_index(root.path(), true).expect("refresh");
+        assert!(root.join(PROJECT_INDEX_FILE_NAME).exists());
+        assert_eq!(index.entries["a.json"].band_ref.as_deref(), Some("band"));
+        assert_eq!(
+            index.entries["bad.json"]
+                .problem
+                .as_ref()
+                .map(|p| p.kind.as_str()),
+            Some("parse")
+        );
+    }
+
+    #[test]
+    fn update_picks_up_changed_file() {
+        let root = projects();
+        refresh_project_index(root.path(), true).expect("refresh");
+        let a = root.write(
+            "projects/a.json",
+            r#"{"id":"a","slug":"gig-a","bandRef":"other-band"}"#,
+        );
+        update_project_index(root.path(), &a).expect("update");
+        let index = refresh_project_index(root.path(), true).expect("refresh again");
+        assert_eq!(
+            index.entries["a.json"].band_ref.as_deref(),
+            Some("other-band")
+        );
+    }
+
+    #[test]
+    fn refresh_drops_removed_files() {
+        let root = projects();
+        refresh_project_index(root.path(), true).expect("refresh");
+        std::fs::remove_file(root.join("projects/bad.json")).expect("remove");
+        let index = refresh_project_index(root.path(), true).expect("refresh again");
+        assert_eq!(index.entries.len(), 1);
+    }
+
+    #[test]
+    fn refresh_rehashes_edits_that_keep_the_stamp() {
+        let root = projects();
+        let a = root.join("projects/a.json");
+        let modified = fs::metadata(&a).and_then(|m| m.modified()).expect("mtime");
+        refresh_project_index(root.path(), true).expect("refresh");
+        // Same size, same mtime: what a quick second write looks like on FAT.
+        root.write(
+            "projects/a.json",
+            r#"{"id":"a","slug":"gig-a","bandRef":"bnd2"}"#,
+        );
+        File::options()
+            .write(true)
+            .open(&a)
+            .and_then(|file| file.set_modified(modified))
+            .expect("reset mtime");
+        let index = refresh_project_index(root.path(), true).expect("refresh again");
+        assert_eq!(index.entries["a.json"].band_ref.as_deref(), Some("bnd2"));
+    }
+
+    #[test]
+    fn refresh_lists_legacy_files_without_migrating_them() {
+        let root = projects();
+        let legacy = r#"{"id":"old","bandRef":"band","date":"2024-05-01"}"#;
+        root.write("projects/old.json", legacy);
+        let index = refresh_project_index(root.path(), true).expect("refresh");
+        assert_eq!(index.entries["old.json"].event_date, None);
+        assert_eq!(
+            fs::read_to_string(root.join("projects/old.json")).expect("read"),
+            legacy
+        );
+    }
+}
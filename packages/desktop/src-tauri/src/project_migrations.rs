@@ -0,0 +1,147 @@
+//! Upgrades of single project files, tracked by `projectSchemaVersion` inside
+//! the project (storage-wide changes live in `storage_migrations.rs`).
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Files without `projectSchemaVersion` are version 1.
+pub const PROJECT_SCHEMA_VERSION: u64 = 2;
+pub const PROJECT_SCHEMA_VERSION_KEY: &str = "projectSchemaVersion";
+
+/// Lifecycle fields that stay with a project file: a duplicate, fork or
+/// imported copy starts without them.
+pub const LIFECYCLE_KEYS: [&str; 4] = ["legacyId", "archivedAt", "trashedAt", "purgeAt"];
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectMigrationReport {
+    pub from_version: u64,
+    pub to_version: u64,
+    /// Human-readable list of what was rewritten.
+    pub changes: Vec<String>,
+}
+
+/// Renames the legacy lead vocal roles (`lead_vocs`, `lead_voc`) to `vocs`.
+/// Returns the renamed keys.
+pub fn normalize_lineup_keys(lineup: &mut Map<String, Value>) -> Vec<&'static str> {
+    for legacy in ["lead_vocs", "lead_voc"] {
+        if let Some(value) = lineup.remove(legacy) {
+            lineup.insert("vocs".to_string(), value);
+            return vec![legacy];
+        }
+    }
+    Vec::new()
+}
+
+fn non_empty_text(fields: &Map<String, Value>, key: &str) -> Option<String> {
+    fields
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
+/// v1 -> v2: legacy `{date, venue}` projects become event projects, `title`
+/// becomes `note`, and lineup roles use the current keys.
+fn migrate_v1(fields: &mut Map<String, Value>, changes: &mut Vec<String>) {
+    if !fields.contains_key("purpose") {
+        if let Some(date) = non_empty_text(fields, "date") {
+            fields.insert("purpose".into(), Value::String("event".into()));
+            fields.insert("eventDate".into(), Value::String(date.clone()));
+            if !fields.contains_key("documentDate") {
+                fields.insert("documentDate".into(), Value::String(date));
+            }
+            fields.remove("date");
+            changes.push("date -> purpose=event, eventDate, documentDate".into());
+            if let Some(venue) = non_empty_text(fields, "venue") {
+                fields.insert("eventVenue".into(), Value::String(venue));
+            }
+            if fields.remove("venue").is_some() {
+                changes.push("venue -> eventVenue".into());
+            }
+        }
+    }
+    if !fields.contains_key("documentDate") {
+        if let Some(event_date) = non_empty_text(fields, "eventDate") {
+            fields.insert("documentDate".into(), Value::String(event_date));
+            changes.push("documentDate set from eventDate".into());
+        }
+    }
+    if let Some(title) = fields.remove("title") {
+        if non_empty_text(fields, "note").is_none() {
+            if let Some(title) = title.as_str().map(str::trim).filter(|t| !t.is_empty()) {
+                fields.insert("note".into(), Value::String(title.to_string()));
+            }
+        }
+        changes.push("title -> note".into());
+    }
+    if let Some(Value::Object(lineup)) = fields.get_mut("lineup") {
+        for key in normalize_lineup_keys(lineup) {
+            changes.push(format!("lineup.{} -> lineup.vocs", key));
+        }
+    }
+}
+
+/// Upgrades `project` in place to `PROJECT_SCHEMA_VERSION`. Returns `None`
+/// when nothing had to change (current, newer or not an object).
+pub fn migrate_project_json(project: &mut Value) -> Option<ProjectMigrationReport> {
+    let fields = project.as_object_mut()?;
+    let from_version = fields
+        .get(PROJECT_SCHEMA_VERSION_KEY)
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1);
+    if from_version >= PROJECT_SCHEMA_VERSION {
+        return None;
+    }
+
+    let mut changes = Vec::new();
+    if from_version < 2 {
+        migrate_v1(fields, &mut changes);
+    }
+    fields.insert(
+        PROJECT_SCHEMA_VERSION_KEY.into(),
+        Value::from(PROJECT_SCHEMA_VERSION),
+    );
+    Some(ProjectMigrationReport {
+        from_version,
+        to_version: PROJECT_SCHEMA_VERSION,
+        changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::migrate_project_json;
+    use serde_json::json;
+
+    #[test]
+    fn upgrades_legacy_shape_once() {
+        let mut project = json!({
+            "id": "p",
+            "bandRef": "band",
+            "date": "2024-05-01",
+            "venue": " Lucerna ",
+            "title": "Spring tour",
+            "lineup": {"lead_voc": "singer", "drums": "drummer"}
+        });
+        let report = migrate_project_json(&mut project).expect("migrated");
+        assert_eq!((report.from_version, report.to_version), (1, 2));
+        assert_eq!(report.changes.len(), 4);
+        assert_eq!(
+            project,
+            json!({
+                "id": "p",
+                "bandRef": "band",
+                "purpose": "event",
+                "eventDate": "2024-05-01",
+                "eventVenue": "Lucerna",
+                "documentDate": "2024-05-01",
+                "note": "Spring tour",
+                "lineup": {"vocs": "singer", "drums": "drummer"},
+                "projectSchemaVersion": 2
+            })
+        );
+        assert!(migrate_project_json(&mut project).is_none());
+    }
+}
@@ -0,0 +1,336 @@
+//! Typed project model, mirroring `Project` in `src/domain/model/types.ts`
+//! and `docs/architecture/project-model.md`. Fields the backend does not know
+//! are kept in `extra` and written back unchanged.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
+
+use crate::timestamps::is_iso_date;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectPurpose {
+    Event,
+    Generic,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineupSlot {
+    pub musician_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset_override: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A lineup slot: a bare musician id or a musician with preset overrides.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum LineupEntry {
+    MusicianId(String),
+    Slot(LineupSlot),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum LineupValue {
+    One(LineupEntry),
+    Many(Vec<LineupEntry>),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerRequirement {
+    /// Kept as a JSON number so `230` is not rewritten as `230.0`.
+    pub voltage: Number,
+    pub sockets: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStageplan {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_overrides_by_musician: Option<BTreeMap<String, PowerRequirement>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    pub band_ref: String,
+    pub purpose: ProjectPurpose,
+    /// `YYYY-MM-DD`; required for event projects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_venue: Option<String>,
+    /// `YYYY-MM-DD`.
+    pub document_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    /// Role key -> musician(s); `null` leaves the role empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lineup: Option<BTreeMap<String, Option<LineupValue>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub back_vocal_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub band_leader_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub talkback_owner_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stageplan: Option<ProjectStageplan>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One rejected field; `field` is a dotted path such as `lineup.drums`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFieldError {
+    pub field: String,
+    pub message: String,
+}
+
+struct FieldReader<'a> {
+    fields: &'a Map<String, Value>,
+    errors: Vec<ProjectFieldError>,
+}
+
+impl FieldReader<'_> {
+    fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(ProjectFieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    fn parse<T: DeserializeOwned>(&mut self, field: &str, value: &Value) -> Option<T> {
+        match serde_json::from_value(value.clone()) {
+            Ok(parsed) => Some(parsed),
+            Err(err) => {
+                self.error(field, err.to_string());
+                None
+            }
+        }
+    }
+
+    /// `null` counts as absent.
+    fn optional<T: DeserializeOwned>(&mut self, key: &str) -> Option<T> {
+        match self.fields.get(key) {
+            None | Some(Value::Null) => None,
+            Some(value) => self.parse(key, value),
+        }
+    }
+
+    fn required<T: DeserializeOwned>(&mut self, key: &str) -> Option<T> {
+        if matches!(self.fields.get(key), None | Some(Value::Null)) {
+            self.error(key, "is required");
+            return None;
+        }
+        self.optional(key)
+    }
+
+    fn required_text(&mut self, key: &str) -> Option<String> {
+        let value: String = self.required(key)?;
+        if value.trim().is_empty() {
+            self.error(key, "must not be empty");
+            return None;
+        }
+        Some(value)
+    }
+
+    fn date(&mut self, key: &str, value: Option<String>) -> Option<String> {
+        let value = value?;
+        if !is_iso_date(&value) {
+            self.error(
+                key,
+                format!("must be an ISO date (YYYY-MM-DD), got {:?}", value),
+            );
+            return None;
+        }
+        Some(value)
+    }
+
+    fn lineup(&mut self) -> Option<BTreeMap<String, Option<LineupValue>>> {
+        let roles: Map<String, Value> = self.optional("lineup")?;
+        let mut lineup = BTreeMap::new();
+        for (role, value) in &roles {
+            let entry = match value {
+                Value::Null => None,
+                value => Some(self.parse(&format!("lineup.{}", role), value)?),
+            };
+            lineup.insert(role.clone(), entry);
+        }
+        Some(lineup)
+    }
+
+    fn stageplan(&mut self) -> Option<ProjectStageplan> {
+        let mut fields: Map<String, Value> = self.optional("stageplan")?;
+        let mut stageplan = ProjectStageplan::default();
+        if let Some(overrides) = fields.remove("powerOverridesByMusician") {
+            let overrides: Map<String, Value> =
+                self.parse("stageplan.powerOverridesByMusician", &overrides)?;
+            let mut parsed = BTreeMap::new();
+            for (musician, value) in &overrides {
+                let field = format!("stageplan.powerOverridesByMusician.{}", musician);
+                parsed.insert(musician.clone(), self.parse(&field, value)?);
+            }
+            stageplan.power_overrides_by_musician = Some(parsed);
+        }
+        stageplan.extra = fields;
+        Some(stageplan)
+    }
+}
+
+const KNOWN_FIELDS: [&str; 16] = [
+    "id",
+    "slug",
+    "displayName",
+    "bandRef",
+    "purpose",
+    "eventDate",
+    "eventVenue",
+    "documentDate",
+    "note",
+    "createdAt",
+    "updatedAt",
+    "lineup",
+    "backVocalIds",
+    "bandLeaderId",
+    "talkbackOwnerId",
+    "stageplan",
+];
+
+/// Checks a project payload and returns the typed project, or every problem
+/// found (not just the first).
+pub fn validate_project(json: &Value) -> Result<Project, Vec<ProjectFieldError>> {
+    let Some(fields) = json.as_object() else {
+        return Err(vec![ProjectFieldError {
+            field: String::new(),
+            message: "Project must be a JSON object".into(),
+        }]);
+    };
+    let mut reader = FieldReader {
+        fields,
+        errors: Vec::new(),
+    };
+
+    let id = reader.required_text("id");
+    let slug = reader.required_text("slug");
+    let band_ref = reader.required_text("bandRef");
+    let purpose: Option<ProjectPurpose> = reader.required("purpose");
+    let event_date = reader.optional("eventDate");
+    let event_date = reader.date("eventDate", event_date);
+    if purpose == Some(ProjectPurpose::Event)
+        && matches!(fields.get("eventDate"), None | Some(Value::Null))
+    {
+        reader.error("eventDate", "is required for event projects");
+    }
+    let document_date = reader.required("documentDate");
+    let document_date = reader.date("documentDate", document_date);
+    let display_name = reader.optional("displayName");
+    let event_venue = reader.optional("eventVenue");
+    let note = reader.optional("note");
+    let created_at = reader.optional("createdAt");
+    let updated_at = reader.optional("updatedAt");
+    let lineup = reader.lineup();
+    let back_vocal_ids = reader.optional("backVocalIds");
+    let band_leader_id = reader.optional("bandLeaderId");
+    let talkback_owner_id = reader.optional("talkbackOwnerId");
+    let stageplan = reader.stageplan();
+
+    if !reader.errors.is_empty() {
+        return Err(reader.errors);
+    }
+    let extra = fields
+        .iter()
+        .filter(|(key, _)| !KNOWN_FIELDS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    // Every required value is present once no errors were recorded.
+    Ok(Project {
+        id: id.unwrap_or_default(),
+        slug: slug.unwrap_or_default(),
+        display_name,
+        band_ref: band_ref.unwrap_or_default(),
+        purpose: purpose.unwrap_or(ProjectPurpose::Generic),
+        event_date,
+        event_venue,
+        document_date: document_date.unwrap_or_default(),
+        note,
+        created_at,
+        updated_at,
+        lineup,
+        back_vocal_ids,
+        band_leader_id,
+        talkback_owner_id,
+        stageplan,
+        extra,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_project, ProjectPurpose};
+    use serde_json::json;
+
+    #[test]
+    fn validates_fields_and_keeps_unknown_ones() {
+        let input = json!({
+            "id": "p1",
+            "slug": "gig",
+            "bandRef": "band",
+            "purpose": "event",
+            "eventDate": "2025-03-07",
+            "documentDate": "2025-01-01",
+            "status": "archived",
+            "lineup": {"drums": "m1", "vocs": [{"musicianId": "m2", "presetOverride": {}}], "keys": null},
+            "stageplan": {"powerOverridesByMusician": {"m1": {"voltage": 230, "sockets": 2}}, "layout": "wide"}
+        });
+        let project = validate_project(&input).expect("valid project");
+        assert_eq!(project.purpose, ProjectPurpose::Event);
+        assert_eq!(project.extra["status"], "archived");
+        assert_eq!(serde_json::to_value(&project).unwrap(), input);
+
+        let errors = validate_project(&json!({
+            "id": "p2",
+            "slug": "gig",
+            "bandRef": "band",
+            "purpose": "festival",
+            "documentDate": "07.03.2025",
+            "backVocalIds": "m1",
+            "lineup": {"drums": 5},
+            "stageplan": {"powerOverridesByMusician": {"m1": {"voltage": 230}}}
+        }))
+        .unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "purpose",
+                "documentDate",
+                "lineup.drums",
+                "backVocalIds",
+                "stageplan.powerOverridesByMusician.m1"
+            ]
+        );
+
+        let errors = validate_project(&json!({
+            "id": "p3", "slug": "gig", "bandRef": "band", "purpose": "event", "documentDate": "2025-01-01"
+        }))
+        .unwrap_err();
+        assert_eq!(errors[0].field, "eventDate");
+    }
+}
@@ -0,0 +1,230 @@
+//! Rust port of the naming rules in `src/domain/projectNaming.ts`. Keep the two
+//! in sync: the hub rewrites slugs and display names that differ from these.
+
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::project_query::fold_char;
+
+const MAX_VENUE_SLUG_LEN: usize = 80;
+
+pub struct NamingBand {
+    pub id: String,
+    pub code: Option<String>,
+    pub name: String,
+}
+
+fn text<'a>(project: &'a Value, key: &str) -> &'a str {
+    project.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn is_event(project: &Value) -> bool {
+    text(project, "purpose") == "event"
+}
+
+fn date_parts(iso: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = iso.splitn(3, '-');
+    let year = parts.next().filter(|v| !v.is_empty())?;
+    let month = parts.next().filter(|v| !v.is_empty())?;
+    let day = parts.next().filter(|v| !v.is_empty())?;
+    Some((year, month, day))
+}
+
+fn document_year(project: &Value) -> String {
+    let year: String = text(project, "documentDate").chars().take(4).collect();
+    if year.is_empty() {
+        "0000".into()
+    } else {
+        year
+    }
+}
+
+/// Strips diacritics but keeps letter case ("Brně" -> "Brne").
+fn strip_diacritics(value: &str) -> String {
+    value
+        .chars()
+        .map(|ch| {
+            if ch.is_uppercase() {
+                let lower = ch.to_lowercase().next().unwrap_or(ch);
+                let folded = fold_char(lower);
+                if folded == lower {
+                    ch
+                } else {
+                    folded.to_ascii_uppercase()
+                }
+            } else {
+                fold_char(ch)
+            }
+        })
+        .collect()
+}
+
+pub fn sanitize_venue_for_slug(value: &str) -> String {
+    let cleaned: String = strip_diacritics(value.trim())
+        .chars()
+        .map(|ch| if "<>:\"/\\|?*".contains(ch) { ' ' } else { ch })
+        .filter(|ch| ch.is_alphanumeric() || ch.is_whitespace() || *ch == '-')
+        .collect();
+    let joined = cleaned
+        .split_whitespace()
+        .map(|segment| {
+            let mut chars = segment.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("-");
+    let mut collapsed = String::with_capacity(joined.len());
+    for ch in joined.chars() {
+        if ch == '-' && collapsed.ends_with('-') {
+            continue;
+        }
+        collapsed.push(ch);
+    }
+    collapsed
+        .trim_matches('-')
+        .chars()
+        .take(MAX_VENUE_SLUG_LEN)
+        .collect()
+}
+
+pub fn format_project_slug(project: &Value, band: &NamingBand) -> String {
+    let band_code = band
+        .code
+        .as_deref()
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .unwrap_or(&band.id);
+    if is_event(project) {
+        let event_date = date_parts(text(project, "eventDate"))
+            .map(|(year, month, day)| format!("{}-{}-{}", day, month, year))
+            .unwrap_or_else(|| "00-00-0000".into());
+        let venue = sanitize_venue_for_slug(text(project, "eventVenue"));
+        let venue = if venue.is_empty() {
+            "Venue".into()
+        } else {
+            venue
+        };
+        return format!("{}_Inputlist_Stageplan_{}_{}", band_code, event_date, venue);
+    }
+    format!(
+        "{}_Inputlist_Stageplan_{}",
+        band_code,
+        document_year(project)
+    )
+}
+
+pub fn format_project_display_name(project: &Value, band: &NamingBand) -> String {
+    if is_event(project) {
+        let event_date = date_parts(text(project, "eventDate"))
+            .map(|(year, month, day)| format!("{}/{}/{}", day, month, year))
+            .unwrap_or_else(|| "00/00/0000".into());
+        let venue = text(project, "eventVenue").trim();
+        let venue = if venue.is_empty() { "Venue" } else { venue };
+        return format!("{} – {} – {}", band.name, event_date, venue);
+    }
+    let note = text(project, "note").trim();
+    if note.is_empty() {
+        format!("{} – {}", band.name, document_year(project))
+    } else {
+        format!("{} – {} – {}", band.name, note, document_year(project))
+    }
+}
+
+/// First free `(slug, display name)` pair: the base names, then `<slug>_2` /
+/// `<name> (2)` and so on. The hub keeps these numbered forms as they are.
+pub fn unique_project_name(
+    slug: &str,
+    display_name: &str,
+    is_taken: impl Fn(&str) -> bool,
+) -> (String, String) {
+    if !is_taken(slug) {
+        return (slug.to_string(), display_name.to_string());
+    }
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{}_{}", slug, counter);
+        if !is_taken(&candidate) {
+            return (candidate, format!("{} ({})", display_name, counter));
+        }
+        counter += 1;
+    }
+}
+
+/// UUIDv7 like `generateUuidV7` in the frontend: 48-bit Unix millis followed by
+/// random bits. Randomness comes from std's per-process hash keys.
+pub fn generate_uuid_v7() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let unix_ms = now.as_millis() as u64;
+    let mut random = [0u8; 16];
+    for (chunk_index, chunk) in random.chunks_mut(8).enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(now.as_nanos());
+        hasher.write_usize(chunk_index);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+
+    let mut bytes = [0u8; 16];
+    bytes[..6].copy_from_slice(&unix_ms.to_be_bytes()[2..]);
+    bytes[6..].copy_from_slice(&random[..10]);
+    bytes[6] = 0x70 | (bytes[6] & 0x0f);
+    bytes[8] = 0x80 | (bytes[8] & 0x3f);
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_project_display_name, format_project_slug, generate_uuid_v7, unique_project_name,
+        NamingBand,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn matches_frontend_naming_rules() {
+        let band = NamingBand {
+            id: "band-1".into(),
+            code: Some("ABC".into()),
+            name: "The Band".into(),
+        };
+        let event = json!({"purpose": "event", "eventDate": "2025-03-07", "eventVenue": "Sono  Brně / sál"});
+        assert_eq!(
+            format_project_slug(&event, &band),
+            "ABC_Inputlist_Stageplan_07-03-2025_Sono-Brne-Sal"
+        );
+        assert_eq!(
+            format_project_display_name(&event, &band),
+            "The Band – 07/03/2025 – Sono  Brně / sál"
+        );
+        let generic = json!({"purpose": "generic", "documentDate": "2024-01-01"});
+        assert_eq!(
+            format_project_slug(&generic, &band),
+            "ABC_Inputlist_Stageplan_2024"
+        );
+
+        let (slug, name) = unique_project_name("s", "n", |candidate| candidate == "s");
+        assert_eq!((slug.as_str(), name.as_str()), ("s_2", "n (2)"));
+
+        let id = generate_uuid_v7();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "7");
+    }
+}
@@ -0,0 +1,288 @@
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::time::SystemTime;
+
+use crate::project_index::ProjectIndexEntry;
+use crate::timestamps::utc_date;
+
+const DEFAULT_LIMIT: usize = 50;
+/// Query words shorter than this are only matched as plain substrings.
+const MIN_STEM_LEN: usize = 3;
+/// How many letters a declined form may add after the stem ("Brn" + "ě").
+const MAX_SUFFIX_LEN: usize = 3;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DateScope {
+    #[default]
+    All,
+    /// `eventDate` today or later.
+    Upcoming,
+    /// `eventDate` before today.
+    Past,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectSortKey {
+    #[default]
+    UpdatedAt,
+    CreatedAt,
+    EventDate,
+    DisplayName,
+    EventVenue,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectQuery {
+    pub band_ref: Option<String>,
+    pub purpose: Option<String>,
+    #[serde(default)]
+    pub when: DateScope,
+    /// Inclusive `YYYY-MM-DD` bounds on `eventDate`.
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    /// Reference date for `when`; defaults to today (UTC). The UI passes its local date.
+    pub today: Option<String>,
+    pub venue: Option<String>,
+    /// Free text matched against display name, slug and venue.
+    pub text: Option<String>,
+    #[serde(default)]
+    pub sort: ProjectSortKey,
+    /// Defaults to descending for timestamps and ascending for names.
+    pub descending: Option<bool>,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// Base letter of a lowercase accented Latin letter ("ě" -> "e").
+pub fn fold_char(ch: char) -> char {
+    match ch {
+        'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' | 'ą' => 'a',
+        'č' | 'ć' | 'ç' => 'c',
+        'ď' => 'd',
+        'é' | 'è' | 'ê' | 'ë' | 'ě' | 'ę' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ľ' | 'ĺ' | 'ł' => 'l',
+        'ň' | 'ń' | 'ñ' => 'n',
+        'ó' | 'ò' | 'ô' | 'ö' | 'õ' | 'ő' => 'o',
+        'ř' | 'ŕ' => 'r',
+        'š' | 'ś' => 's',
+        'ť' => 't',
+        'ú' | 'ù' | 'û' | 'ü' | 'ů' | 'ű' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ž' | 'ź' | 'ż' => 'z',
+        other => other,
+    }
+}
+
+/// Lowercases and strips diacritics so "Brně" compares as "brne".
+pub fn fold_text(value: &str) -> String {
+    value
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(fold_char)
+        .collect()
+}
+
+fn is_vowel(ch: char) -> bool {
+    matches!(ch, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+/// Drops trailing vowels so case endings still match ("brno" -> "brn").
+fn stem(word: &str) -> &str {
+    let trimmed = word.trim_end_matches(is_vowel);
+    if trimmed.chars().count() >= MIN_STEM_LEN {
+        trimmed
+    } else {
+        word
+    }
+}
+
+/// Every word of `needle` must occur in `haystack`, either as a substring or
+/// as a declined form of the word (same stem, short ending).
+pub fn text_matches(haystack: &str, needle: &str) -> bool {
+    let haystack = fold_text(haystack);
+    let words: Vec<&str> = haystack
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    fold_text(needle).split_whitespace().all(|token| {
+        if haystack.contains(token) {
+            return true;
+        }
+        let stem = stem(token);
+        stem.chars().count() >= MIN_STEM_LEN
+            && words.iter().any(|word| {
+                word.starts_with(stem)
+                    && word.chars().count() - stem.chars().count() <= MAX_SUFFIX_LEN
+            })
+    })
+}
+
+fn matches_query(entry: &ProjectIndexEntry, query: &ProjectQuery, today: &str) -> bool {
+    if let Some(band_ref) = query.band_ref.as_deref() {
+        if entry.band_ref.as_deref() != Some(band_ref) {
+            return false;
+        }
+    }
+    if let Some(purpose) = query.purpose.as_deref() {
+        if entry.purpose.as_deref() != Some(purpose) {
+            return false;
+        }
+    }
+
+    // Dates are ISO `YYYY-MM-DD`, so string order is date order.
+    let event_date = entry.event_date.as_deref();
+    let date_filtered =
+        query.when != DateScope::All || query.date_from.is_some() || query.date_to.is_some();
+    if date_filtered {
+        let Some(date) = event_date else {
+            return false;
+        };
+        match query.when {
+            DateScope::Upcoming if date < today => return false,
+            DateScope::Past if date >= today => return false,
+            _ => {}
+        }
+        if query.date_from.as_deref().is_some_and(|from| date < from) {
+            return false;
+        }
+        if query.date_to.as_deref().is_some_and(|to| date > to) {
+            return false;
+        }
+    }
+
+    if let Some(venue) = query.venue.as_deref().filter(|v| !v.trim().is_empty()) {
+        if !text_matches(entry.event_venue.as_deref().unwrap_or(""), venue) {
+            return false;
+        }
+    }
+    if let Some(text) = query.text.as_deref().filter(|v| !v.trim().is_empty()) {
+        let haystack = [
+            entry.display_name.as_deref(),
+            entry.slug.as_deref(),
+            entry.event_venue.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+        if !text_matches(&haystack, text) {
+            return false;
+        }
+    }
+    true
+}
+
+fn sort_value(entry: &ProjectIndexEntry, key: ProjectSortKey) -> Option<String> {
+    match key {
+        ProjectSortKey::UpdatedAt => entry.updated_at.clone(),
+        ProjectSortKey::CreatedAt => entry.created_at.clone(),
+        ProjectSortKey::EventDate => entry.event_date.clone(),
+        ProjectSortKey::DisplayName => entry.display_name.as_deref().map(fold_text),
+        ProjectSortKey::EventVenue => entry.event_venue.as_deref().map(fold_text),
+    }
+}
+
+/// Filters, sorts and pages `entries`. Returns the total match count and the
+/// requested page.
+pub fn query_entries(
+    entries: Vec<ProjectIndexEntry>,
+    query: &ProjectQuery,
+) -> (usize, Vec<ProjectIndexEntry>) {
+    let today = query
+        .today
+        .clone()
+        .unwrap_or_else(|| utc_date(SystemTime::now()));
+    let descending = query.descending.unwrap_or(matches!(
+        query.sort,
+        ProjectSortKey::UpdatedAt | ProjectSortKey::CreatedAt | ProjectSortKey::EventDate
+    ));
+
+    let mut matched: Vec<(Option<String>, ProjectIndexEntry)> = entries
+        .into_iter()
+        .filter(|entry| matches_query(entry, query, &today))
+        .map(|entry| (sort_value(&entry, query.sort), entry))
+        .collect();
+    // Entries without a sort value go last in either direction; id keeps the order stable.
+    matched.sort_by(|(a_key, a), (b_key, b)| {
+        let by_key = match (a_key, b_key) {
+            (Some(a_key), Some(b_key)) if descending => b_key.cmp(a_key),
+            (Some(a_key), Some(b_key)) => a_key.cmp(b_key),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        by_key.then_with(|| a.id.cmp(&b.id))
+    });
+
+    let total = matched.len();
+    let page = matched
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(DEFAULT_LIMIT))
+        .map(|(_, entry)| entry)
+        .collect();
+    (total, page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_text, query_entries, text_matches, DateScope, ProjectQuery, ProjectSortKey};
+    use crate::project_index::ProjectIndexEntry;
+
+    fn entry(id: &str, venue: &str, event_date: &str) -> ProjectIndexEntry {
+        ProjectIndexEntry {
+            id: id.into(),
+            display_name: Some(format!("Gig {}", id)),
+            event_venue: Some(venue.into()),
+            event_date: Some(event_date.into()),
+            band_ref: Some("band".into()),
+            ..ProjectIndexEntry::default()
+        }
+    }
+
+    #[test]
+    fn text_matching_ignores_diacritics_and_case_endings() {
+        assert_eq!(fold_text("Žluťoučký kůň"), "zlutoucky kun");
+        assert!(text_matches("Sono Brně", "Brno"));
+        assert!(text_matches("Lucerna Praha", "lucerně"));
+        assert!(!text_matches("Sono Brně", "Ostrava"));
+        // Endings are counted in letters, not bytes, for letters folding keeps.
+        assert!(text_matches("Klub Brnøøø", "Brno"));
+        assert!(!text_matches("Klub Brnøøøø", "Brno"));
+    }
+
+    #[test]
+    fn filters_sorts_and_pages() {
+        let entries = vec![
+            entry("a", "Sono Brno", "2025-03-01"),
+            entry("b", "Fléda, Brně", "2025-05-01"),
+            entry("c", "Rock Café Praha", "2025-06-01"),
+        ];
+        let query = ProjectQuery {
+            venue: Some("brno".into()),
+            when: DateScope::Upcoming,
+            today: Some("2025-04-01".into()),
+            ..ProjectQuery::default()
+        };
+        let (total, page) = query_entries(entries.clone(), &query);
+        assert_eq!(total, 1);
+        assert_eq!(page[0].id, "b");
+
+        let query = ProjectQuery {
+            sort: ProjectSortKey::EventDate,
+            descending: Some(false),
+            offset: 1,
+            limit: Some(1),
+            ..ProjectQuery::default()
+        };
+        let (total, page) = query_entries(entries, &query);
+        assert_eq!(total, 3);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, "b");
+    }
+}
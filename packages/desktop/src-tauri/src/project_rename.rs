@@ -0,0 +1,252 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage_journal::FileJournal;
+use crate::storage_paths::{export_files_for_slug, sanitize_id_to_filename, StorageError};
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameOutcome {
+    pub id: String,
+    pub slug: Option<String>,
+    pub updated_versions: usize,
+    pub renamed_exports: Vec<String>,
+}
+
+/// Export PDFs of `old_slug` paired with their names under `new_slug`
+/// (`old.pdf` -> `new.pdf`, `old__2.pdf` -> `new__2.pdf`).
+fn export_renames(exports_dir: &Path, old_slug: &str, new_slug: &str) -> Vec<(PathBuf, PathBuf)> {
+    export_files_for_slug(exports_dir, old_slug)
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            let rest = name.strip_prefix(old_slug)?;
+            let target = exports_dir.join(format!("{}{}", new_slug, rest));
+            Some((path, target))
+        })
+        .collect()
+}
+
+/// Rewrites the `projectId` and `paths` of every `meta.json` below `versions_dir`
+/// so snapshots keep pointing at their (moved) folder.
+fn rewrite_version_metas(
+    versions_dir: &Path,
+    old_id: &str,
+    new_id: &str,
+    journal: &mut FileJournal,
+) -> Result<usize, StorageError> {
+    let old_segment = format!("versions/{}/", sanitize_id_to_filename(old_id));
+    let new_segment = format!("versions/{}/", sanitize_id_to_filename(new_id));
+    let mut updated = 0;
+    for entry in fs::read_dir(versions_dir)? {
+        let meta_path = entry?.path().join("meta.json");
+        if !meta_path.is_file() {
+            continue;
+        }
+        let content = fs::read_to_string(&meta_path)?;
+        let mut meta: Value = serde_json::from_str(&content)
+            .map_err(|e| StorageError::Resolve(format!("Invalid {}: {e}", meta_path.display())))?;
+        meta["projectId"] = Value::String(new_id.to_string());
+        if let Some(paths) = meta.get_mut("paths").and_then(|v| v.as_object_mut()) {
+            for value in paths.values_mut() {
+                if let Some(path) = value.as_str() {
+                    let path = format!("{}/", path).replacen(&old_segment, &new_segment, 1);
+                    *value = Value::String(path.trim_end_matches('/').to_string());
+                }
+            }
+        }
+        let json = serde_json::to_vec_pretty(&meta)
+            .map_err(|e| StorageError::Resolve(format!("Failed to serialize version meta: {e}")))?;
+        journal.write(&meta_path, &json)?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+/// Moves `versions/<old id>` and the slug's export PDFs to the new identity.
+/// Fails before touching anything if a target already exists.
+pub fn carry_project_artifacts(
+    root: &Path,
+    old_id: &str,
+    new_id: &str,
+    old_slug: Option<&str>,
+    new_slug: Option<&str>,
+    journal: &mut FileJournal,
+) -> Result<(usize, Vec<String>), StorageError> {
+    let versions_from = root.join("versions").join(sanitize_id_to_filename(old_id));
+    let versions_to = root.join("versions").join(sanitize_id_to_filename(new_id));
+    let move_versions = versions_from != versions_to && versions_from.exists();
+    if move_versions && versions_to.exists() {
+        return Err(StorageError::Resolve(format!(
+            "Version history for {} already exists",
+            new_id
+        )));
+    }
+
+    let exports = match (old_slug, new_slug) {
+        (Some(old_slug), Some(new_slug)) if old_slug != new_slug => {
+            export_renames(&root.join("exports"), old_slug, new_slug)
+        }
+        _ => Vec::new(),
+    };
+    if let Some((_, target)) = exports.iter().find(|(_, target)| target.exists()) {
+        return Err(StorageError::Resolve(format!(
+            "Export {} already exists",
+            target.display()
+        )));
+    }
+
+    let mut updated_versions = 0;
+    if move_versions {
+        journal.rename(&versions_from, &versions_to)?;
+        updated_versions = rewrite_version_metas(&versions_to, old_id, new_id, journal)?;
+    }
+    let mut renamed = Vec::new();
+    for (from, to) in exports {
+        journal.rename(&from, &to)?;
+        renamed.push(
+            to.file_name()
+                .and_then(|v| v.to_str())
+                .unwrap_or_default()
+                .to_string(),
+        );
+    }
+    Ok((updated_versions, renamed))
+}
+
+/// Changes a project's id and/or slug in one transaction: the project JSON is
+/// rewritten (under the new file name if the id changed), versions and exports
+/// are carried along, and everything is rolled back if any step fails.
+pub fn rename_project(
+    root: &Path,
+    old_path: &Path,
+    new_path: &Path,
+    new_id: &str,
+    new_slug: Option<&str>,
+) -> Result<RenameOutcome, StorageError> {
+    let content = fs::read_to_string(old_path)?;
+    let mut project: Value = serde_json::from_str(&content)
+        .map_err(|e| StorageError::Resolve(format!("Invalid project JSON: {e}")))?;
+    let old_id = project
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string())
+        .or_else(|| {
+            old_path
+                .file_stem()
+                .and_then(|v| v.to_str())
+                .map(|v| v.to_string())
+        })
+        .unwrap_or_default();
+    let old_slug = project
+        .get("slug")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+    let new_slug = new_slug.map(|v| v.to_string()).or_else(|| old_slug.clone());
+    if old_path != new_path && new_path.exists() {
+        return Err(StorageError::Resolve(format!(
+            "A project with id {} already exists",
+            new_id
+        )));
+    }
+
+    project["id"] = Value::String(new_id.to_string());
+    if let Some(slug) = &new_slug {
+        project["slug"] = Value::String(slug.clone());
+    }
+    let json = serde_json::to_vec_pretty(&project)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize project: {e}")))?;
+
+    let mut journal = FileJournal::new();
+    let result = (|| {
+        let (updated_versions, renamed_exports) = carry_project_artifacts(
+            root,
+            &old_id,
+            new_id,
+            old_slug.as_deref(),
+            new_slug.as_deref(),
+            &mut journal,
+        )?;
+        journal.write(new_path, &json)?;
+        if old_path != new_path {
+            journal.remove_file(old_path)?;
+        }
+        Ok(RenameOutcome {
+            id: new_id.to_string(),
+            slug: new_slug.clone(),
+            updated_versions,
+            renamed_exports,
+        })
+    })();
+    if result.is_err() {
+        journal.rollback();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rename_project;
+    use crate::test_support::TempDir;
+    use std::fs;
+
+    /// Project `old` (slug `gig`) with a version, two PDFs, and another
+    /// project's `taken.pdf`.
+    fn storage() -> TempDir {
+        let root = TempDir::new("rename");
+        root.write("projects/old.json", r#"{"id":"old","slug":"gig"}"#);
+        root.write(
+            "versions/old/v1/meta.json",
+            r#"{"projectId":"old","paths":{"versionDir":"user_data/versions/old/v1","pdf":"user_data/versions/old/v1/gig.pdf"}}"#,
+        );
+        root.write("exports/gig.pdf", "%PDF");
+        root.write("exports/gig__2.pdf", "%PDF");
+        root.write("exports/taken.pdf", "%PDF");
+        root
+    }
+
+    #[test]
+    fn slug_conflict_rolls_back() {
+        let root = storage();
+        let (old_path, new_path) = (
+            root.join("projects/old.json"),
+            root.join("projects/new.json"),
+        );
+        assert!(rename_project(root.path(), &old_path, &new_path, "new", Some("taken")).is_err());
+        assert!(old_path.exists() && !new_path.exists());
+        assert!(root.join("versions/old/v1/meta.json").exists());
+        assert!(root.join("exports/gig.pdf").exists());
+    }
+
+    #[test]
+    fn rename_moves_project_and_exports() {
+        let root = storage();
+        let (old_path, new_path) = (
+            root.join("projects/old.json"),
+            root.join("projects/new.json"),
+        );
+        let outcome =
+            rename_project(root.path(), &old_path, &new_path, "new", Some("gig2")).expect("rename");
+        assert_eq!(outcome.renamed_exports, vec!["gig2.pdf", "gig2__2.pdf"]);
+        assert!(!old_path.exists());
+        let project = fs::read_to_string(&new_path).unwrap();
+        assert!(project.contains("\"new\"") && project.contains("\"gig2\""));
+    }
+
+    #[test]
+    fn rename_rewrites_version_meta() {
+        let root = storage();
+        let (old_path, new_path) = (
+            root.join("projects/old.json"),
+            root.join("projects/new.json"),
+        );
+        let outcome =
+            rename_project(root.path(), &old_path, &new_path, "new", Some("gig2")).expect("rename");
+        assert_eq!(outcome.updated_versions, 1);
+        let meta = fs::read_to_string(root.join("versions/new/v1/meta.json")).unwrap();
+        assert!(meta.contains("user_data/versions/new/v1/gig.pdf"));
+        assert!(meta.contains("\"projectId\": \"new\""));
+    }
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::storage_paths::sha256_hex;
+
+/// What is on disk when a save was based on an older revision. Sent with
+/// `PROJECT_CONFLICT` so the UI can merge or overwrite.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectConflict {
+    pub revision: String,
+    pub updated_at: Option<String>,
+    pub json: String,
+}
+
+/// Revision token of a project file: the SHA-256 of its bytes.
+pub fn project_revision(bytes: &[u8]) -> String {
+    sha256_hex(bytes)
+}
+
+/// Checks the revision a client last read against the current file. Clients
+/// send the content hash from `read_project_document` or the last save, which
+/// also catches outside edits. The file's `updatedAt` is still accepted from
+/// callers that only have the JSON; it misses edits that left it alone.
+pub fn check_revision(current: &[u8], expected: &str) -> Result<(), Box<ProjectConflict>> {
+    let expected = expected.trim();
+    let revision = project_revision(current);
+    if expected.eq_ignore_ascii_case(&revision) {
+        return Ok(());
+    }
+    let updated_at = serde_json::from_slice::<Value>(current)
+        .ok()
+        .and_then(|json| {
+            json.get("updatedAt")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string())
+        });
+    if updated_at.as_deref() == Some(expected) {
+        return Ok(());
+    }
+    Err(Box::new(ProjectConflict {
+        revision,
+        updated_at,
+        json: String::from_utf8_lossy(current).into_owned(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_revision, project_revision};
+
+    #[test]
+    fn accepts_hash_or_updated_at_and_reports_current_state() {
+        let current = br#"{"id":"p","updatedAt":"2025-01-02T10:00:00Z"}"#;
+        assert!(check_revision(current, &project_revision(current)).is_ok());
+        assert!(check_revision(current, "2025-01-02T10:00:00Z").is_ok());
+
+        let conflict = check_revision(current, "2025-01-01T09:00:00Z").unwrap_err();
+        assert_eq!(conflict.revision, project_revision(current));
+        assert_eq!(conflict.updated_at.as_deref(), Some("2025-01-02T10:00:00Z"));
+        assert!(conflict.json.contains("\"id\":\"p\""));
+    }
+}
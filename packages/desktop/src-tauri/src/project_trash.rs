@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::storage_journal::FileJournal;
+use crate::storage_paths::{
+    atomic_write_bytes, export_files_for_slug, now_iso, sanitize_id_to_filename, StorageError,
+};
+use crate::timestamps::{normalize_timestamp, parse_rfc3339};
+
+const ENTRY_FILE_NAME: &str = "entry.json";
+const PROJECT_FILE_NAME: &str = "project.json";
+
+/// Default age after which trashed projects are purged on startup.
+pub const TRASH_RETENTION_DAYS: u64 = 30;
+
+/// Metadata for one deleted project stored under `trash/<id>/`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    pub project_id: String,
+    pub slug: Option<String>,
+    pub display_name: Option<String>,
+    pub deleted_at: String,
+    /// Original file name in `projects/`.
+    pub project_file_name: String,
+    pub has_versions: bool,
+    pub export_file_names: Vec<String>,
+}
+
+/// Moves a project file together with its `versions/<id>` folder and its
+/// published PDFs into `trash/<entry id>/`.
+pub fn move_project_to_trash(
+    root: &Path,
+    project_path: &Path,
+    project_id: &str,
+) -> Result<TrashEntry, StorageError> {
+    let json = fs::read_to_string(project_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+    let text = |key: &str| {
+        json.as_ref()
+            .and_then(|v| v.get(key))
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    };
+    let slug = text("slug");
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let entry_id = format!("{}-{}", sanitize_id_to_filename(project_id), stamp);
+    let entry_dir = root.join("trash").join(&entry_id);
+    fs::create_dir_all(&entry_dir)?;
+
+    let mut journal = FileJournal::new();
+    let result = (|| {
+        journal.rename(project_path, &entry_dir.join(PROJECT_FILE_NAME))?;
+
+        let versions_dir = root
+            .join("versions")
+            .join(sanitize_id_to_filename(project_id));
+        let has_versions = versions_dir.exists();
+        if has_versions {
+            journal.rename(&versions_dir, &entry_dir.join("versions"))?;
+        }
+
+        let mut export_file_names = Vec::new();
+        if let Some(slug) = slug.as_deref() {
+            for path in export_files_for_slug(&root.join("exports"), slug) {
+                let Some(name) = path.file_name().and_then(|v| v.to_str()) else {
+                    continue;
+                };
+                journal.rename(&path, &entry_dir.join("exports").join(name))?;
+                export_file_names.push(name.to_string());
+            }
+        }
+
+        let entry = TrashEntry {
+            id: entry_id.clone(),
+            project_id: project_id.to_string(),
+            slug: slug.clone(),
+            display_name: text("displayName"),
+            deleted_at: now_iso(),
+            project_file_name: project_path
+                .file_name()
+                .and_then(|v| v.to_str())
+                .unwrap_or(PROJECT_FILE_NAME)
+                .to_string(),
+            has_versions,
+            export_file_names,
+        };
+        write_entry(&entry_dir, &entry)?;
+        Ok(entry)
+    })();
+
+    if result.is_err() {
+        journal.rollback();
+        let _ = fs::remove_dir_all(&entry_dir);
+    }
+    result
+}
+
+fn write_entry(entry_dir: &Path, entry: &TrashEntry) -> Result<(), StorageError> {
+    let json = serde_json::to_vec_pretty(entry)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize trash entry: {e}")))?;
+    atomic_write_bytes(&entry_dir.join(ENTRY_FILE_NAME), &json)
+}
+
+fn read_entry(entry_dir: &Path) -> Result<TrashEntry, StorageError> {
+    let content = fs::read_to_string(entry_dir.join(ENTRY_FILE_NAME))?;
+    let mut entry: TrashEntry = serde_json::from_str(&content)
+        .map_err(|e| StorageError::Resolve(format!("Invalid trash entry JSON: {e}")))?;
+    // Entries written before timestamps were RFC 3339 hold Unix seconds.
+    if let Some(deleted_at) = normalize_timestamp(&entry.deleted_at) {
+        entry.deleted_at = deleted_at;
+    }
+    Ok(entry)
+}
+
+fn entry_dir(root: &Path, entry_id: &str) -> Result<PathBuf, StorageError> {
+    let dir = root.join("trash").join(sanitize_id_to_filename(entry_id));
+    if !dir.join(ENTRY_FILE_NAME).exists() {
+        return Err(StorageError::Resolve(format!(
+            "Trash entry not found: {}",
+            entry_id
+        )));
+    }
+    Ok(dir)
+}
+
+/// Trashed projects, most recently deleted first. Unreadable entries are skipped.
+pub fn list_trash(root: &Path) -> Result<Vec<TrashEntry>, StorageError> {
+    let trash = root.join("trash");
+    if !trash.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&trash)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Ok(item) = read_entry(&path) {
+                entries.push((entry_age_anchor(&path, Some(&item.deleted_at)), item));
+            }
+        }
+    }
+    entries.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(entries.into_iter().map(|(_, item)| item).collect())
+}
+
+/// Puts a trashed project back. Fails without changes if a project file or
+/// versions folder with the same name exists again; export PDFs whose name
+/// has been taken in the meantime stay in the trash entry and are reported.
+pub fn restore_from_trash(
+    root: &Path,
+    entry_id: &str,
+) -> Result<(TrashEntry, Vec<String>), StorageError> {
+    let dir = entry_dir(root, entry_id)?;
+    let entry = read_entry(&dir)?;
+    let project_target = root.join("projects").join(&entry.project_file_name);
+    let versions_target = root
+        .join("versions")
+        .join(sanitize_id_to_filename(&entry.project_id));
+    if project_target.exists() || (entry.has_versions && versions_target.exists()) {
+        return Err(StorageError::Resolve(format!(
+            "A project with id {} already exists",
+            entry.project_id
+        )));
+    }
+
+    let mut journal = FileJournal::new();
+    let mut warnings = Vec::new();
+    let result = (|| {
+        journal.rename(&dir.join(PROJECT_FILE_NAME), &project_target)?;
+        if entry.has_versions {
+            journal.rename(&dir.join("versions"), &versions_target)?;
+        }
+        for name in &entry.export_file_names {
+            let target = root.join("exports").join(name);
+            if target.exists() {
+                warnings.push(format!(
+                    "Export {} already exists and was not restored",
+                    name
+                ));
+                continue;
+            }
+            journal.rename(&dir.join("exports").join(name), &target)?;
+        }
+        Ok(())
+    })();
+    if let Err(err) = result {
+        journal.rollback();
+        return Err(err);
+    }
+    if warnings.is_empty() {
+        fs::remove_dir_all(&dir)?;
+    }
+    Ok((entry, warnings))
+}
+
+/// Permanently removes one trash entry, or all of them when `entry_id` is `None`.
+pub fn purge_trash(root: &Path, entry_id: Option<&str>) -> Result<usize, StorageError> {
+    if let Some(entry_id) = entry_id {
+        fs::remove_dir_all(entry_dir(root, entry_id)?)?;
+        return Ok(1);
+    }
+    let trash = root.join("trash");
+    if !trash.exists() {
+        return Ok(0);
+    }
+    let mut purged = 0;
+    for entry in fs::read_dir(&trash)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+/// Purges entries deleted more than `max_age` ago.
+pub fn purge_expired_trash(root: &Path, max_age: Duration) -> Result<usize, StorageError> {
+    let trash = root.join("trash");
+    if !trash.exists() {
+        return Ok(0);
+    }
+    let now = SystemTime::now();
+    let mut purged = 0;
+    for entry in fs::read_dir(&trash)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let deleted_at = read_entry(&path).ok().map(|item| item.deleted_at);
+        let age = now
+            .duration_since(entry_age_anchor(&path, deleted_at.as_deref()))
+            .unwrap_or_default();
+        if age > max_age {
+            fs::remove_dir_all(&path)?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+/// Deletion time of an entry: its recorded `deletedAt`, or when `entry.json`
+/// was last written if that is missing or unparsable. The file time alone is
+/// not trusted because copies and restores reset it.
+fn entry_age_anchor(entry_dir: &Path, deleted_at: Option<&str>) -> SystemTime {
+    if let Some(deleted_at) = deleted_at.and_then(parse_rfc3339) {
+        return deleted_at;
+    }
+    fs::metadata(entry_dir.join(ENTRY_FILE_NAME))
+        .and_then(|meta| meta.modified())
+        .unwrap_or(UNIX_EPOCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        list_trash, move_project_to_trash, purge_expired_trash, purge_trash, restore_from_trash,
+        TrashEntry, ENTRY_FILE_NAME,
+    };
+    use crate::test_support::TempDir;
+    use std::fs;
+    use std::time::Duration;
+
+    /// Project `p1` (slug `gig`) with a version, two of its PDFs and one PDF of
+    /// a different slug sharing the prefix.
+    fn storage() -> TempDir {
+        let root = TempDir::new("trash");
+        root.write("projects/p1.json", r#"{"id":"p1","slug":"gig"}"#);
+        root.write("versions/p1/v1/meta.json", "{}");
+        root.write("exports/gig.pdf", "%PDF");
+        root.write("exports/gig__2.pdf", "%PDF");
+        root.write("exports/gig-other.pdf", "%PDF");
+        root
+    }
+
+    #[test]
+    fn trash_takes_versions_and_own_exports() {
+        let root = storage();
+        let entry = move_project_to_trash(root.path(), &root.join("projects/p1.json"), "p1")
+            .expect("trash");
+        assert!(!root.join("projects/p1.json").exists());
+        assert!(!root.join("versions/p1").exists());
+        assert_eq!(entry.export_file_names, vec!["gig.pdf", "gig__2.pdf"]);
+        assert!(root.join("exports/gig-other.pdf").exists());
+        assert_eq!(list_trash(root.path()).expect("list").len(), 1);
+    }
+
+    #[test]
+    fn restore_puts_everything_back() {
+        let root = storage();
+        let entry = move_project_to_trash(root.path(), &root.join("projects/p1.json"), "p1")
+            .expect("trash");
+        let (restored, warnings) = restore_from_trash(root.path(), &entry.id).expect("restore");
+        assert!(warnings.is_empty());
+        assert_eq!(restored.project_id, "p1");
+        assert!(root.join("projects/p1.json").exists());
+        assert!(root.join("versions/p1/v1/meta.json").exists());
+        assert!(root.join("exports/gig.pdf").exists());
+        assert!(list_trash(root.path()).expect("list").is_empty());
+    }
+
+    #[test]
+    fn purge_empties_the_bin() {
+        let root = storage();
+        move_project_to_trash(root.path(), &root.join("projects/p1.json"), "p1").expect("trash");
+        assert_eq!(purge_trash(root.path(), None).expect("purge"), 1);
+        assert!(list_trash(root.path()).expect("list").is_empty());
+    }
+
+    #[test]
+    fn expiry_follows_deleted_at_not_file_time() {
+        let root = storage();
+        let entry = move_project_to_trash(root.path(), &root.join("projects/p1.json"), "p1")
+            .expect("trash");
+        let entry_file = root.join("trash").join(&entry.id).join(ENTRY_FILE_NAME);
+        let mut old = entry.clone();
+        old.deleted_at = "2020-01-01T00:00:00Z".into();
+        fs::write(&entry_file, serde_json::to_vec(&old).expect("json")).expect("rewrite");
+        let month = Duration::from_secs(30 * 24 * 60 * 60);
+        assert_eq!(purge_expired_trash(root.path(), month).expect("purge"), 1);
+        assert!(list_trash(root.path()).expect("list").is_empty());
+    }
+
+    #[test]
+    fn expiry_falls_back_to_file_time_without_deleted_at() {
+        let root = storage();
+        let entry = move_project_to_trash(root.path(), &root.join("projects/p1.json"), "p1")
+            .expect("trash");
+        let entry_file = root.join("trash").join(&entry.id).join(ENTRY_FILE_NAME);
+        let broken = TrashEntry {
+            deleted_at: "not a date".into(),
+            ..entry
+        };
+        fs::write(&entry_file, serde_json::to_vec(&broken).expect("json")).expect("rewrite");
+        let month = Duration::from_secs(30 * 24 * 60 * 60);
+        assert_eq!(purge_expired_trash(root.path(), month).expect("purge"), 0);
+    }
+}
@@ -0,0 +1,330 @@
+//! Read side of the export snapshots in `versions/<project id>/<version id>/`
+//! (written by `src/infra/fs/versionStore.ts`), plus the restore points taken
+//! before a snapshot replaces the current project.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::export_status::SourceHashes;
+use crate::storage_lock::lock_storage_file;
+use crate::storage_paths::{atomic_write_bytes, safe_join, sanitize_id_to_filename, StorageError};
+use crate::timestamps::format_rfc3339;
+use crate::version_annotations::SentRecord;
+
+/// Snapshot kind of restore points; export snapshots have no `kind`.
+pub const RESTORE_POINT_KIND: &str = "restorePoint";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotPaths {
+    pub version_dir: String,
+    pub project_json: String,
+    pub meta_json: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdf: Option<String>,
+}
+
+/// `meta.json` of a snapshot, mirroring `ProjectSnapshotMeta` in
+/// `versionStore.ts`. Unknown fields are kept.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSnapshotMeta {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub project_id: String,
+    pub version_id: String,
+    pub generated_at: String,
+    #[serde(default)]
+    pub document_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(default)]
+    pub band_ref: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_venue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    /// `None` for restore points, which have no PDF.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdf_file_name: Option<String>,
+    #[serde(default)]
+    pub paths: SnapshotPaths,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Sent to a venue or promoter; kept by `prune_versions` regardless of age.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sent: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Distribution log, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sent_to: Vec<SentRecord>,
+    /// Inputs the PDF was rendered from; see `get_export_status`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_hashes: Option<SourceHashes>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+pub fn project_versions_dir(versions_root: &Path, project_id: &str) -> PathBuf {
+    versions_root.join(sanitize_id_to_filename(project_id))
+}
+
+/// Folder of one snapshot; fails if it does not exist.
+pub fn version_dir(
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+) -> Result<PathBuf, StorageError> {
+    let dir = safe_join(&project_versions_dir(versions_root, project_id), version_id)?;
+    if !dir.join("meta.json").is_file() {
+        return Err(StorageError::Resolve(format!(
+            "Version {} of project {} not found",
+            version_id, project_id
+        )));
+    }
+    Ok(dir)
+}
+
+fn read_meta(dir: &Path) -> Result<ProjectSnapshotMeta, StorageError> {
+    let content = fs::read_to_string(dir.join("meta.json"))?;
+    serde_json::from_str(&content)
+        .map_err(|e| StorageError::Resolve(format!("Invalid {}: {e}", dir.display())))
+}
+
+fn write_meta(dir: &Path, meta: &ProjectSnapshotMeta) -> Result<(), StorageError> {
+    let json = serde_json::to_vec_pretty(meta)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize version meta: {e}")))?;
+    atomic_write_bytes(&dir.join("meta.json"), &json)
+}
+
+/// All snapshots of a project, newest first. Unreadable `meta.json` files are
+/// skipped (the storage doctor reports them).
+pub fn list_versions(
+    versions_root: &Path,
+    project_id: &str,
+) -> Result<Vec<ProjectSnapshotMeta>, StorageError> {
+    list_versions_in(&project_versions_dir(versions_root, project_id))
+}
+
+/// Like `list_versions`, for a `versions/<sanitized id>/` folder.
+pub fn list_versions_in(dir: &Path) -> Result<Vec<ProjectSnapshotMeta>, StorageError> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut versions = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.join("meta.json").is_file() {
+            continue;
+        }
+        match read_meta(&path) {
+            Ok(meta) => versions.push(meta),
+            Err(err) => eprintln!("[versions] skipping {}: {:?}", path.display(), err),
+        }
+    }
+    versions.sort_by(|a, b| {
+        b.generated_at
+            .cmp(&a.generated_at)
+            .then_with(|| b.version_id.cmp(&a.version_id))
+    });
+    Ok(versions)
+}
+
+/// Meta and raw `project.json` of one snapshot.
+pub fn read_version(
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+) -> Result<(ProjectSnapshotMeta, String), StorageError> {
+    let dir = version_dir(versions_root, project_id, version_id)?;
+    let meta = read_meta(&dir)?;
+    let project = fs::read_to_string(dir.join("project.json"))?;
+    Ok((meta, project))
+}
+
+pub fn version_pdf_path(
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+) -> Result<PathBuf, StorageError> {
+    let dir = version_dir(versions_root, project_id, version_id)?;
+    let meta = read_meta(&dir)?;
+    let pdf = meta
+        .pdf_file_name
+        .as_deref()
+        .map(|name| safe_join(&dir, name))
+        .transpose()?
+        .filter(|path| path.is_file());
+    pdf.ok_or_else(|| StorageError::Resolve(format!("Version {} has no PDF", version_id)))
+}
+
+/// `20250307-183000-123`, the id format of `versionStore.ts`.
+fn format_version_id(time: SystemTime) -> String {
+    let iso = format_rfc3339(time);
+    format!(
+        "{}{}{}-{}{}{}-{}",
+        &iso[0..4],
+        &iso[5..7],
+        &iso[8..10],
+        &iso[11..13],
+        &iso[14..16],
+        &iso[17..19],
+        &iso[20..23]
+    )
+}
+
+fn text(project: &Value, key: &str) -> Option<String> {
+    project
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string())
+}
+
+/// Saves `project_bytes` as a PDF-less snapshot so it can be restored later.
+pub fn create_restore_point(
+    versions_root: &Path,
+    project_id: &str,
+    project_bytes: &[u8],
+    now: SystemTime,
+) -> Result<ProjectSnapshotMeta, StorageError> {
+    let project: Value = serde_json::from_slice(project_bytes).unwrap_or(Value::Null);
+    let project_dir = project_versions_dir(versions_root, project_id);
+    let base_id = format_version_id(now);
+    let mut version_id = base_id.clone();
+    let mut counter = 0;
+    while project_dir.join(&version_id).exists() {
+        counter += 1;
+        version_id = format!("{}-{}", base_id, counter);
+    }
+    let dir = project_dir.join(&version_id);
+    fs::create_dir_all(&dir)?;
+
+    let relative = format!(
+        "versions/{}/{}",
+        sanitize_id_to_filename(project_id),
+        version_id
+    );
+    let meta = ProjectSnapshotMeta {
+        schema_version: 1,
+        project_id: project_id.to_string(),
+        version_id,
+        generated_at: format_rfc3339(now),
+        document_date: text(&project, "documentDate").unwrap_or_default(),
+        purpose: text(&project, "purpose"),
+        band_ref: text(&project, "bandRef").unwrap_or_default(),
+        note: text(&project, "note"),
+        event_date: text(&project, "eventDate"),
+        event_venue: text(&project, "eventVenue"),
+        slug: text(&project, "slug"),
+        pdf_file_name: None,
+        paths: SnapshotPaths {
+            project_json: format!("{}/project.json", relative),
+            meta_json: format!("{}/meta.json", relative),
+            version_dir: relative,
+            pdf: None,
+        },
+        kind: Some(RESTORE_POINT_KIND.into()),
+        sent: false,
+        tags: Vec::new(),
+        sent_to: Vec::new(),
+        source_hashes: None,
+        extra: Map::new(),
+    };
+    atomic_write_bytes(&dir.join("project.json"), project_bytes)?;
+    write_meta(&dir, &meta)?;
+    Ok(meta)
+}
+
+/// Read-modify-write of a snapshot's `meta.json` under its lock.
+pub fn update_version_meta(
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+    update: impl FnOnce(&mut ProjectSnapshotMeta),
+) -> Result<ProjectSnapshotMeta, StorageError> {
+    let dir = version_dir(versions_root, project_id, version_id)?;
+    let _lock = lock_storage_file(&dir.join("meta.json"))?;
+    let mut meta = read_meta(&dir)?;
+    update(&mut meta);
+    write_meta(&dir, &meta)?;
+    Ok(meta)
+}
+
+/// Flags a snapshot as sent (or clears the flag). Sent snapshots are never
+/// pruned.
+pub fn set_version_sent(
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+    sent: bool,
+) -> Result<ProjectSnapshotMeta, StorageError> {
+    update_version_meta(versions_root, project_id, version_id, |meta| {
+        meta.sent = sent;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_restore_point, list_versions, read_version, version_pdf_path};
+    use crate::test_support::TempDir;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const EXPORT_ID: &str = "20250101-100000-000";
+    const RESTORE_POINT_ID: &str = "20251009-085320-123";
+
+    /// An export of `p1` written by the CLI, plus a restore point.
+    fn versions() -> TempDir {
+        let root = TempDir::new("versions");
+        root.write(format!("p1/{}/project.json", EXPORT_ID), r#"{"id":"p1"}"#);
+        root.write(format!("p1/{}/gig.pdf", EXPORT_ID), "%PDF");
+        root.write(
+            format!("p1/{}/meta.json", EXPORT_ID),
+            r#"{"schemaVersion":1,"projectId":"p1","versionId":"20250101-100000-000","generatedAt":"2025-01-01T10:00:00.000Z","documentDate":"2025-01-01","bandRef":"band","pdfFileName":"gig.pdf","paths":{"versionDir":"v","projectJson":"v/project.json","metaJson":"v/meta.json","pdf":"v/gig.pdf"},"exportedBy":"cli"}"#,
+        );
+        let now = UNIX_EPOCH + Duration::from_millis(1_760_000_000_123);
+        create_restore_point(root.path(), "p1", br#"{"id":"p1","slug":"gig"}"#, now)
+            .expect("restore point");
+        root
+    }
+
+    #[test]
+    fn restore_point_is_named_after_its_time_and_has_no_pdf() {
+        let root = TempDir::new("versions");
+        let now = UNIX_EPOCH + Duration::from_millis(1_760_000_000_123);
+        let point = create_restore_point(root.path(), "p1", b"{}", now).expect("restore point");
+        assert_eq!(point.version_id, RESTORE_POINT_ID);
+        assert!(point.pdf_file_name.is_none());
+    }
+
+    #[test]
+    fn list_is_newest_first_and_keeps_unknown_meta_keys() {
+        let root = versions();
+        let versions = list_versions(root.path(), "p1").expect("list");
+        let ids: Vec<&str> = versions.iter().map(|v| v.version_id.as_str()).collect();
+        assert_eq!(ids, vec![RESTORE_POINT_ID, EXPORT_ID]);
+        assert_eq!(versions[1].extra["exportedBy"], "cli");
+    }
+
+    #[test]
+    fn read_returns_snapshot_project() {
+        let root = versions();
+        let (_, project) = read_version(root.path(), "p1", RESTORE_POINT_ID).expect("read");
+        assert!(project.contains("gig"));
+        assert!(read_version(root.path(), "p1", "../p2").is_err());
+    }
+
+    #[test]
+    fn pdf_path_exists_only_for_exports() {
+        let root = versions();
+        assert!(version_pdf_path(root.path(), "p1", EXPORT_ID).is_ok());
+        assert!(version_pdf_path(root.path(), "p1", RESTORE_POINT_ID).is_err());
+    }
+}
@@ -0,0 +1,519 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::storage_paths::{copy_dir_recursive, now_iso, sha256_hex, StorageError};
+
+const BACKUP_FORMAT: &str = "stagepilot-backup";
+const BACKUP_FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const STORAGE_PREFIX: &str = "storage";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub app_version: String,
+    pub schema_version: u32,
+    pub created_at: String,
+    pub include_exports: bool,
+    pub files: Vec<BackupFileEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFileEntry {
+    /// Path relative to the storage root, always `/`-separated.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl BackupManifest {
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct RestoreOutcome {
+    pub manifest: BackupManifest,
+    /// Where the replaced storage tree was moved, when restoring in place.
+    pub previous_root: Option<PathBuf>,
+    /// Top-level entries the backup does not contain (e.g. `assets/`, or
+    /// `exports/` when they were left out) copied over from the replaced tree.
+    pub carried_over: Vec<String>,
+}
+
+/// Packs the storage tree at `root` into a gzip-compressed tar archive with a
+/// leading `manifest.json` carrying per-file SHA-256 checksums.
+pub fn create_backup(
+    root: &Path,
+    archive_path: &Path,
+    schema_version: u32,
+    include_exports: bool,
+) -> Result<BackupManifest, StorageError> {
+    let mut relative_paths = Vec::new();
+    if root.join("storage.json").is_file() {
+        relative_paths.push("storage.json".to_string());
+    }
+    collect_files(root, "projects", true, &mut relative_paths)?;
+    collect_files(root, "library", false, &mut relative_paths)?;
+    collect_files(root, "versions", true, &mut relative_paths)?;
+    collect_files(root, "trash", true, &mut relative_paths)?;
+    collect_files(root, "quarantine", true, &mut relative_paths)?;
+    if include_exports {
+        collect_files(root, "exports", true, &mut relative_paths)?;
+    }
+    relative_paths.retain(|path| !path.starts_with("library/") || path.ends_with(".json"));
+
+    let mut files = Vec::with_capacity(relative_paths.len());
+    for relative in &relative_paths {
+        let bytes = fs::read(root.join(relative))?;
+        files.push(BackupFileEntry {
+            path: relative.clone(),
+            size: bytes.len() as u64,
+            sha256: sha256_hex(&bytes),
+        });
+    }
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT.into(),
+        format_version: BACKUP_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").into(),
+        schema_version,
+        created_at: now_iso(),
+        include_exports,
+        files,
+    };
+
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let partial_path = archive_path.with_extension("partial");
+    let result = write_archive(root, &partial_path, &manifest);
+    if let Err(err) = result {
+        let _ = fs::remove_file(&partial_path);
+        return Err(err);
+    }
+    fs::rename(&partial_path, archive_path)?;
+    Ok(manifest)
+}
+
+fn write_archive(
+    root: &Path,
+    archive_path: &Path,
+    manifest: &BackupManifest,
+) -> Result<(), StorageError> {
+    let file = File::create(archive_path)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let manifest_json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize backup manifest: {e}")))?;
+    append_bytes(&mut builder, MANIFEST_ENTRY, &manifest_json, mtime)?;
+    for entry in &manifest.files {
+        let bytes = fs::read(root.join(&entry.path))?;
+        if sha256_hex(&bytes) != entry.sha256 {
+            return Err(StorageError::Resolve(format!(
+                "{} changed while the backup was being written",
+                entry.path
+            )));
+        }
+        append_bytes(
+            &mut builder,
+            &format!("{}/{}", STORAGE_PREFIX, entry.path),
+            &bytes,
+            mtime,
+        )?;
+    }
+    let encoder = builder.into_inner()?;
+    encoder.finish()?.sync_all()?;
+    Ok(())
+}
+
+pub(crate) fn append_bytes<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    bytes: &[u8],
+    mtime: u64,
+) -> Result<(), StorageError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    builder.append_data(&mut header, path, bytes)?;
+    Ok(())
+}
+
+/// Collects files below `root/folder` as `/`-separated relative paths,
+/// skipping hidden entries such as lock and in-flight temp files.
+fn collect_files(
+    root: &Path,
+    folder: &str,
+    recursive: bool,
+    out: &mut Vec<String>,
+) -> Result<(), StorageError> {
+    let dir = root.join(folder);
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(&dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || name.ends_with(".tmp") {
+            continue;
+        }
+        let relative = format!("{}/{}", folder, name);
+        if entry.file_type()?.is_dir() {
+            if recursive {
+                collect_files(root, &relative, true, out)?;
+            }
+        } else {
+            out.push(relative);
+        }
+    }
+    Ok(())
+}
+
+/// Reads only the manifest, so callers can check compatibility before
+/// extracting anything.
+pub fn read_backup_manifest(archive_path: &Path) -> Result<BackupManifest, StorageError> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_ref() == Path::new(MANIFEST_ENTRY) {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            return parse_manifest(&content);
+        }
+    }
+    Err(StorageError::Resolve(
+        "Backup archive has no manifest.json".into(),
+    ))
+}
+
+fn parse_manifest(content: &str) -> Result<BackupManifest, StorageError> {
+    let manifest: BackupManifest = serde_json::from_str(content)
+        .map_err(|e| StorageError::Resolve(format!("Invalid backup manifest JSON: {e}")))?;
+    if manifest.format != BACKUP_FORMAT || manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(StorageError::Resolve(format!(
+            "Unsupported backup format {} v{}",
+            manifest.format, manifest.format_version
+        )));
+    }
+    Ok(manifest)
+}
+
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Extracts the archive into `staging` (which becomes a storage root) and
+/// verifies it against the manifest: every listed file must be present with
+/// matching size and checksum, and nothing unlisted may be present.
+fn extract_backup(archive_path: &Path, staging: &Path) -> Result<BackupManifest, StorageError> {
+    fs::create_dir_all(staging)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+    let mut manifest: Option<BackupManifest> = None;
+    let mut extracted: HashMap<String, (u64, String)> = HashMap::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        if entry_path == Path::new(MANIFEST_ENTRY) {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            manifest = Some(parse_manifest(&content)?);
+            continue;
+        }
+        let relative = entry_path
+            .strip_prefix(STORAGE_PREFIX)
+            .map_err(|_| {
+                StorageError::Resolve(format!("Unexpected backup entry {}", entry_path.display()))
+            })?
+            .to_path_buf();
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(StorageError::Resolve(format!(
+                "Unsafe backup entry {}",
+                entry_path.display()
+            )));
+        }
+        let target = staging.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = HashingWriter {
+            inner: File::create(&target)?,
+            hasher: Sha256::new(),
+            size: 0,
+        };
+        io::copy(&mut entry, &mut writer)?;
+        writer.inner.sync_all()?;
+        let key = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        extracted.insert(
+            key,
+            (writer.size, format!("{:x}", writer.hasher.finalize())),
+        );
+    }
+
+    let manifest = manifest.ok_or(StorageError::Resolve(
+        "Backup archive has no manifest.json".into(),
+    ))?;
+    for file in &manifest.files {
+        match extracted.remove(&file.path) {
+            Some((size, sha)) if size == file.size && sha == file.sha256 => {}
+            Some(_) => {
+                return Err(StorageError::Resolve(format!(
+                    "Checksum mismatch for {} in backup",
+                    file.path
+                )))
+            }
+            None => {
+                return Err(StorageError::Resolve(format!(
+                    "{} is listed in the backup manifest but missing",
+                    file.path
+                )))
+            }
+        }
+    }
+    if let Some(extra) = extracted.keys().next() {
+        return Err(StorageError::Resolve(format!(
+            "{} is in the backup but not in its manifest",
+            extra
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Restores a verified backup into `target_root`.
+///
+/// Extraction happens in a sibling staging folder, which is then renamed into
+/// place. An existing non-empty root is only replaced when `replace_existing`
+/// is set; it is kept next to the new root as `<name>.pre-restore-<stamp>`.
+pub fn restore_backup(
+    archive_path: &Path,
+    target_root: &Path,
+    replace_existing: bool,
+) -> Result<RestoreOutcome, StorageError> {
+    let parent = target_root.parent().ok_or(StorageError::Resolve(
+        "Restore target has no parent folder".into(),
+    ))?;
+    let name = target_root
+        .file_name()
+        .and_then(|v| v.to_str())
+        .unwrap_or("stagepilot")
+        .to_string();
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    fs::create_dir_all(parent)?;
+    let staging = parent.join(format!(".{}.restore-{}", name, stamp));
+
+    let manifest = match extract_backup(archive_path, &staging) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(err);
+        }
+    };
+
+    let target_has_data = target_root.exists() && fs::read_dir(target_root)?.next().is_some();
+    if target_has_data && !replace_existing {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(StorageError::Resolve(format!(
+            "Restore target {} is not empty",
+            target_root.display()
+        )));
+    }
+
+    let mut previous_root = None;
+    let mut carried_over = Vec::new();
+    if target_has_data {
+        carried_over = match carry_over_missing(target_root, &staging) {
+            Ok(names) => names,
+            Err(err) => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(err);
+            }
+        };
+        let previous = parent.join(format!("{}.pre-restore-{}", name, stamp));
+        fs::rename(target_root, &previous)?;
+        if let Err(err) = fs::rename(&staging, target_root) {
+            let _ = fs::rename(&previous, target_root);
+            let _ = fs::remove_dir_all(&staging);
+            return Err(err.into());
+        }
+        previous_root = Some(previous);
+    } else {
+        if target_root.exists() {
+            fs::remove_dir(target_root)?;
+        }
+        fs::rename(&staging, target_root)?;
+    }
+
+    Ok(RestoreOutcome {
+        manifest,
+        previous_root,
+        carried_over,
+    })
+}
+
+/// Copies top-level entries of `current` that are absent from `staging`, so an
+/// in-place restore does not drop what the backup never covered. The replaced
+/// tree keeps its own copy.
+fn carry_over_missing(current: &Path, staging: &Path) -> Result<Vec<String>, StorageError> {
+    let mut entries = fs::read_dir(current)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    let mut carried = Vec::new();
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || staging.join(&name).exists() {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &staging.join(&name))?;
+        } else {
+            fs::copy(entry.path(), staging.join(&name))?;
+        }
+        carried.push(name);
+    }
+    Ok(carried)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_backup, read_backup_manifest, restore_backup, BackupManifest};
+    use crate::test_support::TempDir;
+
+    /// A storage root at `stagepilot/` with one project, a version, a library
+    /// file, a trashed project, a quarantined file, an export and a lock file.
+    fn storage() -> TempDir {
+        let base = TempDir::new("backup");
+        base.write("stagepilot/storage.json", "{\"schemaVersion\":1}");
+        base.write("stagepilot/projects/p1.json", "{\"id\":\"p1\"}");
+        base.write("stagepilot/projects/.p1.json.lock", "");
+        base.write("stagepilot/library/bands.json", "[]");
+        base.write("stagepilot/versions/p1/v1/meta.json", "{}");
+        base.write("stagepilot/trash/t1/entry.json", "{}");
+        base.write("stagepilot/quarantine/q1/p2.json", "{");
+        base.write("stagepilot/exports/p1.pdf", "%PDF");
+        base
+    }
+
+    fn backup(base: &TempDir) -> BackupManifest {
+        create_backup(
+            &base.join("stagepilot"),
+            &base.join("backup.tar.gz"),
+            1,
+            false,
+        )
+        .expect("backup")
+    }
+
+    #[test]
+    fn backup_skips_hidden_files_and_exports() {
+        let base = storage();
+        let manifest = backup(&base);
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "storage.json",
+                "projects/p1.json",
+                "library/bands.json",
+                "versions/p1/v1/meta.json",
+                "trash/t1/entry.json",
+                "quarantine/q1/p2.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn manifest_is_readable_without_extracting() {
+        let base = storage();
+        backup(&base);
+        let manifest = read_backup_manifest(&base.join("backup.tar.gz")).expect("manifest");
+        assert_eq!(manifest.schema_version, 1);
+    }
+
+    #[test]
+    fn restore_into_fresh_root() {
+        let base = storage();
+        backup(&base);
+        let fresh = base.join("restored");
+        let outcome = restore_backup(&base.join("backup.tar.gz"), &fresh, false).expect("restore");
+        assert!(outcome.previous_root.is_none());
+        assert!(fresh.join("projects/p1.json").exists());
+    }
+
+    #[test]
+    fn restore_refuses_non_empty_root_unless_replacing() {
+        let base = storage();
+        backup(&base);
+        let archive = base.join("backup.tar.gz");
+        assert!(restore_backup(&archive, &base.join("stagepilot"), false).is_err());
+    }
+
+    #[test]
+    fn in_place_restore_keeps_previous_root() {
+        let base = storage();
+        backup(&base);
+        let root = base.join("stagepilot");
+        let outcome =
+            restore_backup(&base.join("backup.tar.gz"), &root, true).expect("restore in place");
+        let previous = outcome.previous_root.expect("previous root kept");
+        assert!(previous.join("exports/p1.pdf").exists());
+        assert!(root.join("projects/p1.json").exists());
+    }
+
+    #[test]
+    fn in_place_restore_carries_over_folders_outside_the_backup() {
+        let base = storage();
+        base.write("stagepilot/assets/logo.png", "png");
+        backup(&base);
+        base.write("stagepilot/projects/p2.json", "{\"id\":\"p2\"}");
+        let root = base.join("stagepilot");
+        let outcome =
+            restore_backup(&base.join("backup.tar.gz"), &root, true).expect("restore in place");
+        assert_eq!(outcome.carried_over, vec!["assets", "exports"]);
+        assert!(root.join("exports/p1.pdf").exists());
+        assert!(root.join("assets/logo.png").exists());
+        assert!(!root.join("projects/p2.json").exists());
+    }
+}
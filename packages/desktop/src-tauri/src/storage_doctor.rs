@@ -0,0 +1,577 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::storage_lock::lock_storage_file;
+use crate::storage_paths::{atomic_write_bytes, safe_join, sanitize_id_to_filename, StorageError};
+
+/// Folder that receives files moved aside by repairs or from the hub.
+const QUARANTINE_DIR_NAME: &str = "quarantine";
+const MUSICIAN_DEFAULTS_FILE_NAME: &str = "musician_defaults.json";
+/// Temp files younger than this may still belong to a write in progress.
+const STALE_TEMP_AGE: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FindingKind {
+    OrphanVersions,
+    OrphanExport,
+    LeftoverTempFile,
+    InvalidProjectJson,
+    InvalidLibraryJson,
+    DuplicateProjectId,
+    DanglingMusicianDefault,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageFinding {
+    pub kind: FindingKind,
+    /// Path relative to the storage root.
+    pub path: String,
+    pub message: String,
+    /// Set for findings `repair_storage` knows how to fix.
+    pub repairable: bool,
+    /// Map key for `DanglingMusicianDefault` findings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageScanReport {
+    pub projects_scanned: usize,
+    pub findings: Vec<StorageFinding>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairAction {
+    pub finding: StorageFinding,
+    pub action: String,
+    pub applied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageRepairReport {
+    pub dry_run: bool,
+    pub actions: Vec<RepairAction>,
+    /// Findings that need a human decision (e.g. duplicate project ids).
+    pub skipped: Vec<StorageFinding>,
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn finding(
+    root: &Path,
+    kind: FindingKind,
+    path: &Path,
+    message: String,
+    repairable: bool,
+) -> StorageFinding {
+    StorageFinding {
+        kind,
+        path: relative(root, path),
+        message,
+        repairable,
+        key: None,
+    }
+}
+
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().and_then(|v| v.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|v| v.to_str())
+        .map(|name| name.ends_with(".tmp"))
+        .unwrap_or(false)
+}
+
+fn collect_stale_temp_files(dir: &Path, now: SystemTime, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_stale_temp_files(&path, now, out);
+            continue;
+        }
+        if !is_temp_file(&path) {
+            continue;
+        }
+        let age = fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if age >= STALE_TEMP_AGE {
+            out.push(path);
+        }
+    }
+}
+
+/// Walks the storage root and reports problems without changing anything.
+///
+/// `shipped_musician_ids` are the musicians bundled under `data/musicians`;
+/// together with `library/musicians.json` they decide which
+/// `musician_defaults.json` keys are still valid.
+pub fn scan_storage(
+    root: &Path,
+    shipped_musician_ids: &HashSet<String>,
+) -> Result<StorageScanReport, StorageError> {
+    let mut report = StorageScanReport::default();
+    let mut ids: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut slugs: HashSet<String> = HashSet::new();
+
+    for path in json_files(&root.join("projects")) {
+        report.projects_scanned += 1;
+        let parsed = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<Value>(&content).map_err(|err| err.to_string())
+            });
+        let json = match parsed {
+            Ok(json) if json.is_object() => json,
+            Ok(_) => {
+                report.findings.push(finding(
+                    root,
+                    FindingKind::InvalidProjectJson,
+                    &path,
+                    "Project file is not a JSON object".into(),
+                    true,
+                ));
+                continue;
+            }
+            Err(err) => {
+                report.findings.push(finding(
+                    root,
+                    FindingKind::InvalidProjectJson,
+                    &path,
+                    format!("Project file cannot be parsed ({})", err),
+                    true,
+                ));
+                continue;
+            }
+        };
+        let id = json
+            .get("id")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+            .or_else(|| {
+                path.file_stem()
+                    .and_then(|v| v.to_str())
+                    .map(|v| v.to_string())
+            })
+            .unwrap_or_default();
+        ids.entry(id).or_default().push(path.clone());
+        if let Some(slug) = json.get("slug").and_then(|v| v.as_str()) {
+            slugs.insert(slug.to_string());
+        }
+    }
+
+    let mut duplicates: Vec<_> = ids.iter().filter(|(_, paths)| paths.len() > 1).collect();
+    duplicates.sort_by_key(|(id, _)| id.as_str());
+    for (id, paths) in duplicates {
+        let files: Vec<String> = paths.iter().map(|path| relative(root, path)).collect();
+        for path in paths {
+            report.findings.push(finding(
+                root,
+                FindingKind::DuplicateProjectId,
+                path,
+                format!("Project id '{}' is used by {}", id, files.join(", ")),
+                false,
+            ));
+        }
+    }
+
+    let version_folders: HashSet<String> =
+        ids.keys().map(|id| sanitize_id_to_filename(id)).collect();
+    if let Ok(entries) = fs::read_dir(root.join("versions")) {
+        let mut orphans: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|v| v.to_str())
+                    .map(|name| !version_folders.contains(name))
+                    .unwrap_or(false)
+            })
+            .collect();
+        orphans.sort();
+        for path in orphans {
+            report.findings.push(finding(
+                root,
+                FindingKind::OrphanVersions,
+                &path,
+                "Version history has no matching project".into(),
+                true,
+            ));
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(root.join("exports")) {
+        let mut orphans: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file() && path.extension().and_then(|v| v.to_str()) == Some("pdf")
+            })
+            .filter(|path| {
+                let name = path.file_name().and_then(|v| v.to_str()).unwrap_or("");
+                !slugs.iter().any(|slug| {
+                    name == format!("{}.pdf", slug) || name.starts_with(&format!("{}__", slug))
+                })
+            })
+            .collect();
+        orphans.sort();
+        for path in orphans {
+            report.findings.push(finding(
+                root,
+                FindingKind::OrphanExport,
+                &path,
+                "Exported PDF does not belong to any project slug".into(),
+                true,
+            ));
+        }
+    }
+
+    let mut temp_files = Vec::new();
+    let now = SystemTime::now();
+    for folder in ["projects", "library", "versions", "exports"] {
+        collect_stale_temp_files(&root.join(folder), now, &mut temp_files);
+    }
+    if let Ok(entries) = fs::read_dir(root) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && is_temp_file(&path) {
+                temp_files.push(path);
+            }
+        }
+    }
+    temp_files.sort();
+    for path in temp_files {
+        report.findings.push(finding(
+            root,
+            FindingKind::LeftoverTempFile,
+            &path,
+            "Leftover temp file from an interrupted write".into(),
+            true,
+        ));
+    }
+
+    let library = root.join("library");
+    let mut musicians_valid = true;
+    let mut known_musicians = shipped_musician_ids.clone();
+    let mut defaults: Option<(PathBuf, serde_json::Map<String, Value>)> = None;
+    for path in json_files(&library) {
+        let name = path.file_name().and_then(|v| v.to_str()).unwrap_or("");
+        let expects_map = name == MUSICIAN_DEFAULTS_FILE_NAME;
+        let parsed = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<Value>(&content).map_err(|err| err.to_string())
+            });
+        let problem = match &parsed {
+            Err(err) => Some(format!("Library file cannot be parsed ({})", err)),
+            Ok(Value::Object(_)) if expects_map => None,
+            Ok(Value::Array(_)) if !expects_map => None,
+            Ok(_) if expects_map => Some("Library file is not a JSON object".into()),
+            Ok(_) => Some("Library file is not a JSON array".into()),
+        };
+        if let Some(message) = problem {
+            if name == "musicians.json" {
+                musicians_valid = false;
+            }
+            report.findings.push(finding(
+                root,
+                FindingKind::InvalidLibraryJson,
+                &path,
+                message,
+                true,
+            ));
+            continue;
+        }
+        match parsed {
+            Ok(Value::Array(items)) if name == "musicians.json" => {
+                known_musicians.extend(
+                    items
+                        .iter()
+                        .filter_map(|item| item.get("id").and_then(|v| v.as_str()))
+                        .map(|id| id.to_string()),
+                );
+            }
+            Ok(Value::Object(map)) if expects_map => defaults = Some((path.clone(), map)),
+            _ => {}
+        }
+    }
+
+    // Without a readable musicians list every key would look dangling.
+    if let (true, Some((path, map))) = (musicians_valid, defaults) {
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        for key in keys {
+            let musician_id = key.split(':').next().unwrap_or("");
+            if known_musicians.contains(musician_id) {
+                continue;
+            }
+            let mut item = finding(
+                root,
+                FindingKind::DanglingMusicianDefault,
+                &path,
+                format!(
+                    "Defaults '{}' point to missing musician '{}'",
+                    key, musician_id
+                ),
+                true,
+            );
+            item.key = Some(key.clone());
+            report.findings.push(item);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Fixes repairable findings. Broken or orphaned files are moved to
+/// `quarantine/<timestamp>/` rather than deleted; stale temp files and dangling
+/// defaults keys are removed. With `dry_run` only the planned actions are returned.
+pub fn repair_storage(
+    root: &Path,
+    shipped_musician_ids: &HashSet<String>,
+    dry_run: bool,
+) -> Result<StorageRepairReport, StorageError> {
+    let scan = scan_storage(root, shipped_musician_ids)?;
+    let quarantine = new_quarantine_dir(root);
+
+    let mut report = StorageRepairReport {
+        dry_run,
+        actions: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut dangling_keys: Vec<StorageFinding> = Vec::new();
+    for item in scan.findings {
+        if !item.repairable {
+            report.skipped.push(item);
+            continue;
+        }
+        if item.kind == FindingKind::DanglingMusicianDefault {
+            dangling_keys.push(item);
+            continue;
+        }
+        let source = root.join(&item.path);
+        let target = quarantine.join(&item.path);
+        let action = match item.kind {
+            FindingKind::LeftoverTempFile => "delete".to_string(),
+            _ => format!("move to {}", relative(root, &target)),
+        };
+        let error = if dry_run {
+            None
+        } else {
+            let result = match item.kind {
+                FindingKind::LeftoverTempFile => {
+                    fs::remove_file(&source).map_err(StorageError::from)
+                }
+                _ => move_aside(&source, &target),
+            };
+            result.err().map(|err| format!("{:?}", err))
+        };
+        report.actions.push(RepairAction {
+            finding: item,
+            action,
+            applied: !dry_run && error.is_none(),
+            error,
+        });
+    }
+
+    if !dangling_keys.is_empty() {
+        let path = root.join("library").join(MUSICIAN_DEFAULTS_FILE_NAME);
+        let error = if dry_run {
+            None
+        } else {
+            remove_defaults_keys(&path, &dangling_keys)
+                .err()
+                .map(|err| format!("{:?}", err))
+        };
+        for item in dangling_keys {
+            report.actions.push(RepairAction {
+                action: format!("remove key {}", item.key.as_deref().unwrap_or("")),
+                finding: item,
+                applied: !dry_run && error.is_none(),
+                error: error.clone(),
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Fresh `quarantine/<millis>/` folder (not created yet) for one batch of moves.
+pub fn new_quarantine_dir(root: &Path) -> PathBuf {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    root.join(QUARANTINE_DIR_NAME).join(stamp.to_string())
+}
+
+/// Moves a broken file out of `projects/` into `quarantine/<millis>/projects/`
+/// and returns its new location. `file_name` must name a file directly in
+/// `projects/`.
+pub fn quarantine_project_file(root: &Path, file_name: &str) -> Result<PathBuf, StorageError> {
+    let projects_dir = root.join("projects");
+    let source = safe_join(&projects_dir, file_name)?;
+    if source.parent() != Some(projects_dir.as_path()) || !source.is_file() {
+        return Err(StorageError::Resolve(format!(
+            "Project file not found: {}",
+            file_name
+        )));
+    }
+    let target = new_quarantine_dir(root).join("projects").join(file_name);
+    move_aside(&source, &target)?;
+    Ok(target)
+}
+
+pub fn move_aside(source: &Path, target: &Path) -> Result<(), StorageError> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(source, target)?;
+    Ok(())
+}
+
+fn remove_defaults_keys(path: &Path, findings: &[StorageFinding]) -> Result<(), StorageError> {
+    let _lock = lock_storage_file(path)?;
+    let content = fs::read_to_string(path)?;
+    let mut map: serde_json::Map<String, Value> = serde_json::from_str(&content)
+        .map_err(|e| StorageError::Resolve(format!("Invalid musician defaults JSON: {e}")))?;
+    for item in findings {
+        if let Some(key) = &item.key {
+            map.remove(key);
+        }
+    }
+    let json = serde_json::to_vec_pretty(&map).map_err(|e| {
+        StorageError::Resolve(format!("Failed to serialize musician defaults: {e}"))
+    })?;
+    atomic_write_bytes(path, &json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quarantine_project_file, repair_storage, scan_storage, FindingKind};
+    use crate::project_index::refresh_project_index;
+    use crate::test_support::TempDir;
+    use std::collections::HashSet;
+    use std::fs;
+
+    /// A broken project, a duplicate id, orphaned versions and export, and a
+    /// default for a musician that no longer exists.
+    fn damaged() -> TempDir {
+        let root = TempDir::new("doctor");
+        fs::create_dir_all(root.join("versions/gone/v1")).expect("orphan versions");
+        fs::create_dir_all(root.join("versions/p1")).expect("versions");
+        root.write("projects/p1.json", r#"{"id":"p1","slug":"gig"}"#);
+        root.write("projects/copy.json", r#"{"id":"p1","slug":"gig"}"#);
+        root.write("projects/broken.json", r#"{"id":"#);
+        root.write("exports/gig__2.pdf", "%PDF");
+        root.write("exports/old.pdf", "%PDF");
+        root.write("library/musicians.json", r#"[{"id":"m1"}]"#);
+        root.write(
+            "library/musician_defaults.json",
+            r#"{"m1:bass":{},"shipped:drums":{},"gone:keys":{}}"#,
+        );
+        root
+    }
+
+    fn known_musicians() -> HashSet<String> {
+        ["shipped".to_string()].into_iter().collect()
+    }
+
+    #[test]
+    fn scan_reports_each_problem() {
+        let root = damaged();
+        let scan = scan_storage(root.path(), &known_musicians()).expect("scan");
+        let kinds: Vec<FindingKind> = scan.findings.iter().map(|item| item.kind).collect();
+        assert_eq!(scan.projects_scanned, 3);
+        assert_eq!(
+            kinds,
+            vec![
+                FindingKind::InvalidProjectJson,
+                FindingKind::DuplicateProjectId,
+                FindingKind::DuplicateProjectId,
+                FindingKind::OrphanVersions,
+                FindingKind::OrphanExport,
+                FindingKind::DanglingMusicianDefault,
+            ]
+        );
+    }
+
+    #[test]
+    fn dry_run_changes_nothing() {
+        let root = damaged();
+        let dry = repair_storage(root.path(), &known_musicians(), true).expect("dry run");
+        assert!(dry.actions.iter().all(|action| !action.applied));
+        assert!(root.join("versions/gone").exists());
+    }
+
+    #[test]
+    fn repair_fixes_repairable_findings() {
+        let root = damaged();
+        let repaired = repair_storage(root.path(), &known_musicians(), false).expect("repair");
+        assert_eq!(repaired.actions.len(), 4);
+        assert_eq!(repaired.skipped.len(), 2);
+        assert!(!root.join("versions/gone").exists());
+        assert!(!root.join("projects/broken.json").exists());
+        let defaults = fs::read_to_string(root.join("library/musician_defaults.json")).unwrap();
+        assert!(!defaults.contains("gone:keys") && defaults.contains("shipped:drums"));
+        let rescan = scan_storage(root.path(), &known_musicians()).expect("rescan");
+        assert!(rescan.findings.iter().all(|item| !item.repairable));
+    }
+
+    #[test]
+    fn quarantined_file_leaves_the_listing_problems() {
+        let root = damaged();
+        let index = refresh_project_index(root.path(), true).expect("index");
+        assert!(index.entries["broken.json"].problem.is_some());
+
+        let target = quarantine_project_file(root.path(), "broken.json").expect("quarantine");
+        assert!(target.ends_with("projects/broken.json"));
+        assert!(target.starts_with(root.join("quarantine")));
+        assert_eq!(fs::read_to_string(&target).expect("moved"), r#"{"id":"#);
+
+        let index = refresh_project_index(root.path(), true).expect("index again");
+        assert!(!index.entries.contains_key("broken.json"));
+        assert!(index.entries.values().all(|entry| entry.problem.is_none()));
+    }
+
+    #[test]
+    fn quarantine_only_takes_files_in_projects() {
+        let root = damaged();
+        assert!(quarantine_project_file(root.path(), "missing.json").is_err());
+        assert!(quarantine_project_file(root.path(), "../library/musicians.json").is_err());
+        assert!(root.join("library/musicians.json").exists());
+    }
+}
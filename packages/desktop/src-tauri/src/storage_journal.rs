@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage_paths::{atomic_write_bytes, StorageError};
+
+enum JournalStep {
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// `previous` is `None` when the file did not exist before.
+    Wrote {
+        path: PathBuf,
+        previous: Option<Vec<u8>>,
+    },
+    Removed {
+        path: PathBuf,
+        previous: Vec<u8>,
+    },
+}
+
+/// File operations performed so far by a multi-step storage change, undone in
+/// reverse order if a later step fails.
+#[derive(Default)]
+pub struct FileJournal {
+    steps: Vec<JournalStep>,
+}
+
+impl FileJournal {
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    pub fn rename(&mut self, from: &Path, to: &Path) -> Result<(), StorageError> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(from, to)?;
+        self.steps.push(JournalStep::Renamed {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    pub fn write(&mut self, path: &Path, bytes: &[u8]) -> Result<(), StorageError> {
+        let previous = if path.exists() {
+            Some(fs::read(path)?)
+        } else {
+            None
+        };
+        atomic_write_bytes(path, bytes)?;
+        self.steps.push(JournalStep::Wrote {
+            path: path.to_path_buf(),
+            previous,
+        });
+        Ok(())
+    }
+
+    pub fn remove_file(&mut self, path: &Path) -> Result<(), StorageError> {
+        let previous = fs::read(path)?;
+        fs::remove_file(path)?;
+        self.steps.push(JournalStep::Removed {
+            path: path.to_path_buf(),
+            previous,
+        });
+        Ok(())
+    }
+
+    /// Best effort: every step is attempted even if an earlier undo fails.
+    pub fn rollback(self) {
+        for step in self.steps.into_iter().rev() {
+            let result = match step {
+                JournalStep::Renamed { from, to } => fs::rename(&to, &from).map_err(Into::into),
+                JournalStep::Wrote {
+                    path,
+                    previous: Some(bytes),
+                }
+                | JournalStep::Removed {
+                    path,
+                    previous: bytes,
+                } => atomic_write_bytes(&path, &bytes),
+                JournalStep::Wrote {
+                    path,
+                    previous: None,
+                } => fs::remove_file(&path).map_err(Into::into),
+            };
+            if let Err(err) = result {
+                eprintln!("[storage] rollback step failed: {:?}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileJournal;
+    use crate::test_support::TempDir;
+    use std::fs;
+
+    fn files() -> TempDir {
+        let dir = TempDir::new("journal");
+        for name in ["a", "b", "c"] {
+            dir.write(format!("{}.txt", name), name);
+        }
+        dir
+    }
+
+    #[test]
+    fn rollback_undoes_rename() {
+        let dir = files();
+        let mut journal = FileJournal::new();
+        journal
+            .rename(&dir.join("a.txt"), &dir.join("moved/a.txt"))
+            .expect("rename");
+        journal.rollback();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "a");
+        assert!(!dir.join("moved/a.txt").exists());
+    }
+
+    #[test]
+    fn rollback_restores_overwritten_and_removes_created_files() {
+        let dir = files();
+        let mut journal = FileJournal::new();
+        journal
+            .write(&dir.join("b.txt"), b"changed")
+            .expect("write");
+        journal.write(&dir.join("new.txt"), b"new").expect("create");
+        journal.rollback();
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "b");
+        assert!(!dir.join("new.txt").exists());
+    }
+
+    #[test]
+    fn rollback_restores_removed_file() {
+        let dir = files();
+        let mut journal = FileJournal::new();
+        journal.remove_file(&dir.join("c.txt")).expect("remove");
+        journal.rollback();
+        assert_eq!(fs::read_to_string(dir.join("c.txt")).unwrap(), "c");
+    }
+}
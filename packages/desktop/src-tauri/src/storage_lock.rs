@@ -0,0 +1,86 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::storage_paths::StorageError;
+
+const LOCK_WAIT: Duration = Duration::from_secs(3);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Advisory, cross-process lock guarding one storage file.
+///
+/// The OS lock lives on a sidecar `.<file name>.lock` next to the target and is
+/// released when the guard is dropped (or the process dies).
+#[derive(Debug)]
+pub struct StorageLock {
+    _file: File,
+}
+
+pub fn lock_path_for(target: &Path) -> PathBuf {
+    let file_name = target
+        .file_name()
+        .and_then(|v| v.to_str())
+        .unwrap_or("storage");
+    target.with_file_name(format!(".{}.lock", file_name))
+}
+
+/// Acquires an exclusive lock for `target`, waiting briefly for other holders
+/// before giving up with `StorageError::Locked`.
+pub fn lock_storage_file(target: &Path) -> Result<StorageLock, StorageError> {
+    lock_with_wait(target, LOCK_WAIT)
+}
+
+fn lock_with_wait(target: &Path, wait: Duration) -> Result<StorageLock, StorageError> {
+    let lock_path = lock_path_for(target);
+    if let Some(parent) = lock_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(StorageLock { _file: file }),
+            Err(TryLockError::WouldBlock) => {
+                if started.elapsed() >= wait {
+                    return Err(StorageError::Locked(target.display().to_string()));
+                }
+                thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(TryLockError::Error(err)) => return Err(StorageError::Io(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lock_path_for, lock_storage_file, lock_with_wait};
+    use crate::storage_paths::StorageError;
+    use crate::test_support::TempDir;
+    use std::path::Path;
+    use std::time::Duration;
+
+    #[test]
+    fn lock_path_is_hidden_sidecar() {
+        let path = lock_path_for(Path::new("/tmp/root/library/bands.json"));
+        assert_eq!(path, Path::new("/tmp/root/library/.bands.json.lock"));
+    }
+
+    #[test]
+    fn second_holder_is_reported_as_locked() {
+        let dir = TempDir::new("lock");
+        let target = dir.join("bands.json");
+
+        let held = lock_storage_file(&target).expect("first lock");
+        let second = lock_with_wait(&target, Duration::from_millis(100));
+        assert!(matches!(second, Err(StorageError::Locked(path)) if path.ends_with("bands.json")));
+
+        drop(held);
+        assert!(lock_storage_file(&target).is_ok());
+    }
+}
@@ -0,0 +1,506 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::storage_paths::{
+    atomic_write_bytes, copy_dir_recursive, now_iso, read_storage_meta, write_storage_meta,
+    StorageError, UserStorageMeta,
+};
+use crate::timestamps::{format_rfc3339, normalize_json_timestamps};
+use crate::version_blobs::{dedupe_versions, relink_versions};
+
+/// Folders (relative to the storage root) that migrations may rewrite and that
+/// are therefore captured in the pre-migration snapshot. `blobs` goes with
+/// `versions`, whose snapshot files are hard links into it.
+const MIGRATED_FOLDERS: [&str; 5] = ["projects", "library", "versions", "blobs", "exports"];
+const SNAPSHOTS_DIR_NAME: &str = "migration_backups";
+/// Pre-migration snapshots kept after a successful upgrade, newest first.
+const KEPT_SNAPSHOTS: usize = 3;
+
+/// Recorded in `storage.json` when an upgrade fails and is rolled back. The
+/// storage stays read-only and the upgrade is not retried (nor the tree
+/// snapshotted again) until a different StagePilot build starts.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedMigration {
+    pub from_version: u32,
+    pub target_version: u32,
+    pub app_version: String,
+    pub failed_at: String,
+    pub reason: String,
+}
+
+/// The recorded failure was produced by this build, so retrying would fail
+/// the same way.
+pub fn retry_blocked(meta: &UserStorageMeta) -> bool {
+    meta.failed_migration.as_ref().is_some_and(|failed| {
+        failed.from_version == meta.schema_version
+            && failed.app_version == env!("CARGO_PKG_VERSION")
+    })
+}
+
+/// One step of the storage upgrade pipeline, taking the tree from
+/// `from_version` to `from_version + 1`.
+pub struct StorageMigration {
+    pub from_version: u32,
+    pub description: &'static str,
+    pub run: fn(&Path) -> Result<(), StorageError>,
+}
+
+/// Ordered list of storage migrations. Append new steps here and bump
+/// `STORAGE_SCHEMA_VERSION` together.
+pub const STORAGE_MIGRATIONS: &[StorageMigration] = &[
+    StorageMigration {
+        from_version: 1,
+        description: "normalize timestamps to RFC 3339 UTC",
+        run: normalize_timestamps,
+    },
+    StorageMigration {
+        from_version: 2,
+        description: "store version files as content-addressed blobs",
+        run: store_version_blobs,
+    },
+];
+
+const PROJECT_TIMESTAMP_KEYS: [&str; 5] = [
+    "createdAt",
+    "updatedAt",
+    "archivedAt",
+    "trashedAt",
+    "purgeAt",
+];
+
+/// Rewrites `path` with `update` applied; the file is only touched when
+/// `update` reports a change. Unreadable JSON is left for the storage doctor.
+fn rewrite_json(path: &Path, update: impl FnOnce(&mut Value) -> bool) -> Result<(), StorageError> {
+    let Ok(mut json) = serde_json::from_str::<Value>(&fs::read_to_string(path)?) else {
+        return Ok(());
+    };
+    if update(&mut json) {
+        let bytes = serde_json::to_vec_pretty(&json).map_err(|e| {
+            StorageError::Resolve(format!("Failed to serialize {}: {e}", path.display()))
+        })?;
+        atomic_write_bytes(path, &bytes)?;
+    }
+    Ok(())
+}
+
+/// v1 -> v2: older builds wrote Unix seconds with a trailing `Z`. Projects
+/// without a usable `createdAt` get their file time, and a missing
+/// `updatedAt` falls back to `createdAt`.
+fn normalize_timestamps(root: &Path) -> Result<(), StorageError> {
+    let meta_path = root.join("storage.json");
+    if meta_path.exists() {
+        rewrite_json(&meta_path, |meta| {
+            normalize_json_timestamps(meta, &["createdAt", "lastMigratedAt"])
+        })?;
+    }
+
+    let projects_dir = root.join("projects");
+    if projects_dir.exists() {
+        for entry in fs::read_dir(&projects_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let modified = fs::metadata(&path)?.modified()?;
+            rewrite_json(&path, |project| {
+                if !project.is_object() {
+                    return false;
+                }
+                let mut changed = normalize_json_timestamps(project, &PROJECT_TIMESTAMP_KEYS);
+                if project.get("createdAt").is_none() {
+                    project["createdAt"] = Value::String(format_rfc3339(modified));
+                    changed = true;
+                }
+                if project.get("updatedAt").is_none() {
+                    project["updatedAt"] = project["createdAt"].clone();
+                    changed = true;
+                }
+                changed
+            })?;
+        }
+    }
+
+    let versions_dir = root.join("versions");
+    if versions_dir.exists() {
+        for project_dir in fs::read_dir(&versions_dir)? {
+            let project_dir = project_dir?.path();
+            if !project_dir.is_dir() {
+                continue;
+            }
+            for version_dir in fs::read_dir(&project_dir)? {
+                let meta_path = version_dir?.path().join("meta.json");
+                if meta_path.is_file() {
+                    rewrite_json(&meta_path, |meta| {
+                        normalize_json_timestamps(meta, &["generatedAt"])
+                    })?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// v2 -> v3: snapshot files become hard links into `blobs/`.
+fn store_version_blobs(root: &Path) -> Result<(), StorageError> {
+    let ingested = dedupe_versions(root)?;
+    eprintln!("[storage] stored {} versions as blobs", ingested);
+    Ok(())
+}
+
+/// Brings the storage at `root` from `meta.schema_version` up to `target`.
+///
+/// The migrated folders are snapshotted first; if any step fails the snapshot
+/// is copied back so the tree is left exactly as it was found.
+pub fn migrate_storage(
+    root: &Path,
+    mut meta: UserStorageMeta,
+    target: u32,
+    migrations: &[StorageMigration],
+) -> Result<UserStorageMeta, StorageError> {
+    if meta.schema_version >= target {
+        return Ok(meta);
+    }
+
+    let from = meta.schema_version;
+    let snapshot_dir = snapshot_storage(root, from)?;
+    eprintln!(
+        "[storage] migrating schema v{} -> v{} (snapshot {})",
+        from,
+        target,
+        snapshot_dir.display()
+    );
+
+    if let Err(err) = run_migrations(root, from, target, migrations) {
+        eprintln!("[storage] migration failed, restoring snapshot: {:?}", err);
+        restore_snapshot(root, &snapshot_dir)?;
+        meta.failed_migration = Some(FailedMigration {
+            from_version: from,
+            target_version: target,
+            app_version: env!("CARGO_PKG_VERSION").into(),
+            failed_at: now_iso(),
+            reason: format!("{:?}", err),
+        });
+        write_storage_meta(root, &meta)?;
+        return Err(err);
+    }
+
+    // Steps may rewrite storage.json themselves; keep what they wrote.
+    if let Ok(migrated) = read_storage_meta(root) {
+        meta = migrated;
+    }
+    meta.schema_version = target;
+    meta.last_migrated_at = Some(now_iso());
+    meta.failed_migration = None;
+    write_storage_meta(root, &meta)?;
+    match prune_snapshots(root) {
+        Ok(0) => {}
+        Ok(count) => eprintln!("[storage] removed {} old migration snapshots", count),
+        Err(err) => eprintln!("[storage] migration snapshot cleanup failed: {:?}", err),
+    }
+    Ok(meta)
+}
+
+fn run_migrations(
+    root: &Path,
+    from: u32,
+    target: u32,
+    migrations: &[StorageMigration],
+) -> Result<(), StorageError> {
+    let mut version = from;
+    while version < target {
+        let step = migrations
+            .iter()
+            .find(|migration| migration.from_version == version)
+            .ok_or(StorageError::InvalidSchema(version))?;
+        eprintln!(
+            "[storage] migration v{} -> v{}: {}",
+            version,
+            version + 1,
+            step.description
+        );
+        (step.run)(root)?;
+        version += 1;
+    }
+    Ok(())
+}
+
+fn snapshot_storage(root: &Path, from_version: u32) -> Result<PathBuf, StorageError> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let snapshot_dir = root
+        .join(SNAPSHOTS_DIR_NAME)
+        .join(format!("v{}-{}", from_version, stamp));
+    fs::create_dir_all(&snapshot_dir)?;
+    for folder in MIGRATED_FOLDERS {
+        let source = root.join(folder);
+        if source.exists() {
+            copy_dir_recursive(&source, &snapshot_dir.join(folder))?;
+        }
+    }
+    let meta_path = root.join("storage.json");
+    if meta_path.exists() {
+        fs::copy(&meta_path, snapshot_dir.join("storage.json"))?;
+    }
+    Ok(snapshot_dir)
+}
+
+fn restore_snapshot(root: &Path, snapshot_dir: &Path) -> Result<(), StorageError> {
+    for folder in MIGRATED_FOLDERS {
+        let target = root.join(folder);
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        let source = snapshot_dir.join(folder);
+        if source.exists() {
+            copy_dir_recursive(&source, &target)?;
+        } else {
+            fs::create_dir_all(&target)?;
+        }
+    }
+    let meta_snapshot = snapshot_dir.join("storage.json");
+    if meta_snapshot.exists() {
+        fs::copy(&meta_snapshot, root.join("storage.json"))?;
+    }
+    // The copies above are separate files; share them with `blobs/` again.
+    if let Err(err) = relink_versions(root) {
+        eprintln!("[storage] could not relink restored versions: {:?}", err);
+    }
+    Ok(())
+}
+
+/// Removes all but the newest `KEPT_SNAPSHOTS` snapshots, ordered by the
+/// timestamp in their `v<from>-<millis>` name. Returns how many were removed.
+fn prune_snapshots(root: &Path) -> Result<usize, StorageError> {
+    let Ok(entries) = fs::read_dir(root.join(SNAPSHOTS_DIR_NAME)) else {
+        return Ok(0);
+    };
+    let mut snapshots: Vec<(u128, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            let stamp = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.rsplit_once('-'))
+                .and_then(|(_, stamp)| stamp.parse().ok())
+                .unwrap_or_default();
+            (stamp, path)
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.cmp(a));
+    let mut removed = 0;
+    for (_, path) in snapshots.into_iter().skip(KEPT_SNAPSHOTS) {
+        fs::remove_dir_all(&path)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        migrate_storage, retry_blocked, StorageMigration, KEPT_SNAPSHOTS, SNAPSHOTS_DIR_NAME,
+        STORAGE_MIGRATIONS,
+    };
+    use crate::storage_paths::{
+        ensure_storage_at, is_read_only, read_storage_meta, write_storage_meta, StorageError,
+        UserStorageMeta,
+    };
+    use crate::test_support::TempDir;
+    use std::fs;
+    use std::path::Path;
+
+    fn temp_root(name: &str) -> TempDir {
+        let root = TempDir::new(&format!("migrations-{}", name));
+        root.write("projects/p.json", "{}");
+        root
+    }
+
+    fn meta(schema_version: u32) -> UserStorageMeta {
+        UserStorageMeta {
+            schema_version,
+            created_at: "2025-01-01T00:00:00Z".into(),
+            last_migrated_at: None,
+            version_retention: None,
+            failed_migration: None,
+        }
+    }
+
+    fn add_marker(root: &Path) -> Result<(), StorageError> {
+        fs::write(root.join("projects").join("marker.txt"), "v2")?;
+        Ok(())
+    }
+
+    fn rename_marker(root: &Path) -> Result<(), StorageError> {
+        fs::rename(
+            root.join("projects").join("marker.txt"),
+            root.join("projects").join("marker_v3.txt"),
+        )?;
+        Ok(())
+    }
+
+    fn fail(_root: &Path) -> Result<(), StorageError> {
+        Err(StorageError::Resolve("boom".into()))
+    }
+
+    #[test]
+    fn runs_migrations_in_order_and_records_timestamp() {
+        let root = temp_root("ordered");
+        let migrations = [
+            StorageMigration {
+                from_version: 2,
+                description: "rename marker",
+                run: rename_marker,
+            },
+            StorageMigration {
+                from_version: 1,
+                description: "add marker",
+                run: add_marker,
+            },
+        ];
+        let migrated = migrate_storage(root.path(), meta(1), 3, &migrations).expect("migrate");
+        assert_eq!(migrated.schema_version, 3);
+        assert!(migrated.last_migrated_at.is_some());
+        assert!(root.join("projects").join("marker_v3.txt").exists());
+        assert!(root.join(SNAPSHOTS_DIR_NAME).read_dir().unwrap().count() == 1);
+    }
+
+    #[test]
+    fn failed_migration_restores_snapshot() {
+        let root = temp_root("rollback");
+        let migrations = [
+            StorageMigration {
+                from_version: 1,
+                description: "add marker",
+                run: add_marker,
+            },
+            StorageMigration {
+                from_version: 2,
+                description: "fail",
+                run: fail,
+            },
+        ];
+        assert!(migrate_storage(root.path(), meta(1), 3, &migrations).is_err());
+        assert!(!root.join("projects").join("marker.txt").exists());
+        assert!(root.join("projects").join("p.json").exists());
+    }
+
+    #[test]
+    fn snapshot_covers_blobs_and_rollback_restores_them() {
+        let root = temp_root("blobs");
+        root.write("blobs/ab/abcd", "pdf");
+        let migrations = [
+            StorageMigration {
+                from_version: 1,
+                description: "drop blobs",
+                run: |root| Ok(fs::remove_dir_all(root.join("blobs"))?),
+            },
+            StorageMigration {
+                from_version: 2,
+                description: "fail",
+                run: fail,
+            },
+        ];
+        assert!(migrate_storage(root.path(), meta(1), 3, &migrations).is_err());
+        assert_eq!(
+            fs::read_to_string(root.join("blobs/ab/abcd")).expect("restored blob"),
+            "pdf"
+        );
+    }
+
+    #[test]
+    fn successful_migration_keeps_only_recent_snapshots() {
+        let root = temp_root("prune");
+        for stamp in 1..=4 {
+            fs::create_dir_all(root.join(SNAPSHOTS_DIR_NAME).join(format!("v1-{}", stamp)))
+                .expect("old snapshot");
+        }
+        let migrations = [StorageMigration {
+            from_version: 1,
+            description: "add marker",
+            run: add_marker,
+        }];
+        migrate_storage(root.path(), meta(1), 2, &migrations).expect("migrate");
+        let kept: Vec<String> = fs::read_dir(root.join(SNAPSHOTS_DIR_NAME))
+            .expect("snapshots")
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        // The snapshot of this run and the two newest older ones.
+        assert_eq!(kept.len(), KEPT_SNAPSHOTS);
+        assert!(kept.contains(&"v1-3".to_string()));
+        assert!(kept.contains(&"v1-4".to_string()));
+    }
+
+    #[test]
+    fn failed_migration_is_recorded_and_not_retried() {
+        let root = temp_root("recorded");
+        write_storage_meta(root.path(), &meta(1)).expect("write meta");
+        let migrations = [StorageMigration {
+            from_version: 1,
+            description: "fail",
+            run: fail,
+        }];
+        assert!(migrate_storage(root.path(), meta(1), 2, &migrations).is_err());
+        let recorded = read_storage_meta(root.path()).expect("read meta");
+        assert_eq!(recorded.schema_version, 1);
+        assert!(recorded
+            .failed_migration
+            .as_ref()
+            .unwrap()
+            .reason
+            .contains("boom"));
+        assert!(retry_blocked(&recorded));
+
+        fs::remove_dir_all(root.join(SNAPSHOTS_DIR_NAME)).expect("clear snapshots");
+        let reopened = ensure_storage_at(root.path()).expect("ensure");
+        assert!(is_read_only(&reopened));
+        assert!(!root.join(SNAPSHOTS_DIR_NAME).exists());
+    }
+
+    #[test]
+    fn v1_timestamps_are_normalized() {
+        let root = temp_root("timestamps");
+        fs::create_dir_all(root.join("versions").join("p").join("v1")).expect("create version");
+        fs::write(
+            root.join("projects").join("p.json"),
+            r#"{"id":"p","createdAt":"1700000000Z"}"#,
+        )
+        .expect("write project");
+        fs::write(
+            root.join("versions").join("p").join("v1").join("meta.json"),
+            r#"{"generatedAt":"2025-01-01T10:00:00+01:00"}"#,
+        )
+        .expect("write meta");
+        fs::write(
+            root.join("storage.json"),
+            r#"{"schemaVersion":1,"createdAt":"1700000000Z"}"#,
+        )
+        .expect("write storage meta");
+
+        let migrated =
+            migrate_storage(root.path(), meta(1), 2, STORAGE_MIGRATIONS).expect("migrate");
+        assert_eq!(migrated.created_at, "2023-11-14T22:13:20.000Z");
+        let project = fs::read_to_string(root.join("projects").join("p.json")).unwrap();
+        assert!(project.contains(r#""createdAt": "2023-11-14T22:13:20.000Z""#));
+        assert!(project.contains(r#""updatedAt": "2023-11-14T22:13:20.000Z""#));
+        let version =
+            fs::read_to_string(root.join("versions").join("p").join("v1").join("meta.json"))
+                .unwrap();
+        assert!(version.contains("2025-01-01T09:00:00.000Z"));
+    }
+
+    #[test]
+    fn missing_step_is_reported_as_invalid_schema() {
+        let root = temp_root("missing");
+        let result = migrate_storage(root.path(), meta(1), 2, &[]);
+        assert!(matches!(result, Err(StorageError::InvalidSchema(1))));
+    }
+}
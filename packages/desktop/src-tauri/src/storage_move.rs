@@ -0,0 +1,191 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::storage_paths::{copy_dir_recursive, StorageError};
+
+/// Storage entries carried over when the root moves. `temp/` is rebuilt on
+/// demand and intentionally left behind. Copying turns the hard links between
+/// `versions/` and `blobs/` into separate files; `relink_versions` restores
+/// them at the new root.
+const MOVED_ENTRIES: [&str; 9] = [
+    "storage.json",
+    "projects",
+    "versions",
+    "exports",
+    "library",
+    "assets",
+    "trash",
+    "quarantine",
+    "blobs",
+];
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMoveReport {
+    pub files_copied: usize,
+    pub bytes_copied: u64,
+    pub warnings: Vec<String>,
+}
+
+/// Copies the storage tree from `source` into the empty `target` and verifies
+/// every file byte-for-byte. The source is left untouched; on any failure the
+/// partial copy is removed again.
+pub fn copy_storage_tree(source: &Path, target: &Path) -> Result<StorageMoveReport, StorageError> {
+    if !target.is_absolute() {
+        return Err(StorageError::Resolve(
+            "Target storage root must be an absolute path".into(),
+        ));
+    }
+    if target == source || target.starts_with(source) {
+        return Err(StorageError::Resolve(
+            "Target storage root must be outside the current storage root".into(),
+        ));
+    }
+    if target.exists() && fs::read_dir(target)?.next().is_some() {
+        return Err(StorageError::Resolve(format!(
+            "Target folder {} is not empty",
+            target.display()
+        )));
+    }
+    fs::create_dir_all(target)?;
+
+    let result = copy_and_verify(source, target);
+    if result.is_err() {
+        for entry in MOVED_ENTRIES {
+            remove_entry(&target.join(entry));
+        }
+    }
+    result
+}
+
+fn copy_and_verify(source: &Path, target: &Path) -> Result<StorageMoveReport, StorageError> {
+    let mut report = StorageMoveReport::default();
+    for entry in MOVED_ENTRIES {
+        let from = source.join(entry);
+        if !from.exists() {
+            continue;
+        }
+        let to = target.join(entry);
+        if from.is_dir() {
+            copy_dir_recursive(&from, &to)?;
+        } else {
+            fs::copy(&from, &to)?;
+        }
+        verify_copy(&from, &to, &mut report)?;
+    }
+    Ok(report)
+}
+
+fn verify_copy(
+    source: &Path,
+    target: &Path,
+    report: &mut StorageMoveReport,
+) -> Result<(), StorageError> {
+    if source.is_dir() {
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            verify_copy(&entry.path(), &target.join(entry.file_name()), report)?;
+        }
+        return Ok(());
+    }
+    let expected = fs::read(source)?;
+    let actual = fs::read(target).map_err(|err| {
+        StorageError::Resolve(format!("Missing copy of {} ({})", source.display(), err))
+    })?;
+    if expected != actual {
+        return Err(StorageError::Resolve(format!(
+            "Copy verification failed for {}",
+            source.display()
+        )));
+    }
+    report.files_copied += 1;
+    report.bytes_copied += expected.len() as u64;
+    Ok(())
+}
+
+/// Deletes the moved entries from the old root. Failures are returned as
+/// warnings because the new root is already active at this point.
+pub fn remove_moved_entries(source: &Path) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for entry in MOVED_ENTRIES {
+        let path = source.join(entry);
+        if !path.exists() {
+            continue;
+        }
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(err) = result {
+            warnings.push(format!("Failed to remove {} ({})", path.display(), err));
+        }
+    }
+    warnings
+}
+
+fn remove_entry(path: &Path) {
+    if path.is_dir() {
+        let _ = fs::remove_dir_all(path);
+    } else if path.exists() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy_storage_tree, remove_moved_entries};
+    use crate::test_support::TempDir;
+
+    fn old_root() -> TempDir {
+        let base = TempDir::new("move");
+        base.write("old/storage.json", "{}");
+        base.write("old/projects/a.json", "{\"id\":\"a\"}");
+        base.write("old/temp/preview.pdf", "%PDF");
+        base
+    }
+
+    #[test]
+    fn copy_skips_temp_files() {
+        let base = old_root();
+        let report = copy_storage_tree(&base.join("old"), &base.join("new")).expect("copy");
+        assert_eq!(report.files_copied, 2);
+        assert!(base.join("new/projects/a.json").exists());
+        assert!(!base.join("new/temp").exists());
+    }
+
+    #[test]
+    fn copy_carries_trash_quarantine_and_blobs() {
+        let base = old_root();
+        base.write("old/trash/t1/entry.json", "{}");
+        base.write("old/quarantine/q1/b.json", "{");
+        base.write("old/blobs/ab/abcd", "%PDF");
+        copy_storage_tree(&base.join("old"), &base.join("new")).expect("copy");
+        assert!(base.join("new/trash/t1/entry.json").exists());
+        assert!(base.join("new/quarantine/q1/b.json").exists());
+        assert!(base.join("new/blobs/ab/abcd").exists());
+        assert!(remove_moved_entries(&base.join("old")).is_empty());
+        assert!(!base.join("old/trash").exists());
+        assert!(!base.join("old/quarantine").exists());
+    }
+
+    #[test]
+    fn copy_refuses_non_empty_or_nested_target() {
+        let base = old_root();
+        copy_storage_tree(&base.join("old"), &base.join("new")).expect("copy");
+        assert!(
+            copy_storage_tree(&base.join("old"), &base.join("new")).is_err(),
+            "target not empty"
+        );
+        assert!(copy_storage_tree(&base.join("old"), &base.join("old/nested")).is_err());
+    }
+
+    #[test]
+    fn moved_entries_are_removed_from_old_root() {
+        let base = old_root();
+        copy_storage_tree(&base.join("old"), &base.join("new")).expect("copy");
+        assert!(remove_moved_entries(&base.join("old")).is_empty());
+        assert!(!base.join("old/projects").exists());
+    }
+}
@@ -1,19 +1,39 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Component, Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicU64, Ordering};
 use tauri::Manager;
 
+use crate::storage_lock::lock_storage_file;
+use crate::storage_migrations::{
+    migrate_storage, retry_blocked, FailedMigration, STORAGE_MIGRATIONS,
+};
+use crate::timestamps::{format_rfc3339, Clock, SystemClock};
+use crate::version_retention::VersionRetention;
+
 const STORAGE_DIR_NAME: &str = "stagepilot";
-const STORAGE_SCHEMA_VERSION: u32 = 1;
+const STORAGE_LOCATION_FILE_NAME: &str = "storage_location.json";
+const DATA_DIR_ENV: &str = "STAGEPILOT_DATA_DIR";
+const PORTABLE_ENV: &str = "STAGEPILOT_PORTABLE";
+const PORTABLE_MARKER_FILE_NAME: &str = "stagepilot.portable";
+pub const STORAGE_SCHEMA_VERSION: u32 = 3;
 const MAX_ID_LEN: usize = 120;
 
 #[derive(Debug)]
 pub enum StorageError {
     Io(std::io::Error),
     Resolve(String),
+    /// Stored schema cannot be brought up to date (no migration path).
     InvalidSchema(u32),
+    /// Storage was written by a newer StagePilot; writes are refused.
+    ReadOnly(u32),
+    /// Another process holds the advisory lock for this path.
+    Locked(String),
+    /// An earlier upgrade of this storage failed; writes are refused until a
+    /// StagePilot update retries it.
+    MigrationFailed(String),
 }
 
 impl From<std::io::Error> for StorageError {
@@ -29,28 +49,121 @@ pub struct UserStorageMeta {
     pub created_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_migrated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_retention: Option<VersionRetention>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_migration: Option<FailedMigration>,
 }
 
-fn now_iso() -> String {
-    let secs = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or_default();
-    format!("{}Z", secs)
+/// Current time as RFC 3339 UTC.
+pub(crate) fn now_iso() -> String {
+    format_rfc3339(SystemClock.now())
 }
 
 fn storage_meta_path(root: &Path) -> PathBuf {
     root.join("storage.json")
 }
 
-pub fn user_storage_root(app: &tauri::AppHandle) -> Result<PathBuf, StorageError> {
+/// Where the active storage root came from, in order of precedence.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageRootSource {
+    /// `STAGEPILOT_DATA_DIR` environment override.
+    Env,
+    /// Portable mode: storage next to the executable.
+    Portable,
+    /// Root chosen with `move_storage_root`, persisted in app data.
+    Configured,
+    /// `app_data_dir()/stagepilot`.
+    Default,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedStorageRoot {
+    pub path: PathBuf,
+    pub source: StorageRootSource,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageLocationConfig {
+    root: PathBuf,
+}
+
+fn app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, StorageError> {
     // StagePilot desktop currently targets Tauri v2 (`tauri = "2"`), where
     // app-data paths are resolved via `app.path().app_data_dir()`.
-    let app_data_dir = app
-        .path()
+    app.path()
         .app_data_dir()
-        .map_err(|e| StorageError::Resolve(format!("Failed to resolve app data dir: {e}")))?;
-    Ok(app_data_dir.join(STORAGE_DIR_NAME))
+        .map_err(|e| StorageError::Resolve(format!("Failed to resolve app data dir: {e}")))
+}
+
+fn storage_location_path(app: &tauri::AppHandle) -> Result<PathBuf, StorageError> {
+    Ok(app_data_dir(app)?.join(STORAGE_LOCATION_FILE_NAME))
+}
+
+/// Portable mode is enabled by `STAGEPILOT_PORTABLE=1` or by a
+/// `stagepilot.portable` marker file placed next to the executable.
+fn portable_storage_root() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    let forced = std::env::var(PORTABLE_ENV)
+        .map(|value| value == "1")
+        .unwrap_or(false);
+    if forced || exe_dir.join(PORTABLE_MARKER_FILE_NAME).exists() {
+        return Some(exe_dir.join(STORAGE_DIR_NAME));
+    }
+    None
+}
+
+pub fn resolve_storage_root(app: &tauri::AppHandle) -> Result<ResolvedStorageRoot, StorageError> {
+    if let Ok(value) = std::env::var(DATA_DIR_ENV) {
+        if !value.trim().is_empty() {
+            return Ok(ResolvedStorageRoot {
+                path: PathBuf::from(value.trim()),
+                source: StorageRootSource::Env,
+            });
+        }
+    }
+    if let Some(path) = portable_storage_root() {
+        return Ok(ResolvedStorageRoot {
+            path,
+            source: StorageRootSource::Portable,
+        });
+    }
+    let location_path = storage_location_path(app)?;
+    if location_path.exists() {
+        let content = fs::read_to_string(&location_path)?;
+        let config: StorageLocationConfig = serde_json::from_str(&content)
+            .map_err(|e| StorageError::Resolve(format!("Invalid storage location JSON: {e}")))?;
+        return Ok(ResolvedStorageRoot {
+            path: config.root,
+            source: StorageRootSource::Configured,
+        });
+    }
+    Ok(ResolvedStorageRoot {
+        path: app_data_dir(app)?.join(STORAGE_DIR_NAME),
+        source: StorageRootSource::Default,
+    })
+}
+
+pub fn user_storage_root(app: &tauri::AppHandle) -> Result<PathBuf, StorageError> {
+    Ok(resolve_storage_root(app)?.path)
+}
+
+/// Persists `root` as the configured storage root for future launches.
+pub fn set_configured_storage_root(
+    app: &tauri::AppHandle,
+    root: &Path,
+) -> Result<(), StorageError> {
+    let location_path = storage_location_path(app)?;
+    if let Some(parent) = location_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_vec_pretty(&StorageLocationConfig {
+        root: root.to_path_buf(),
+    })
+    .map_err(|e| StorageError::Resolve(format!("Failed to serialize storage location: {e}")))?;
+    atomic_write_bytes(&location_path, &json)
 }
 
 pub fn maybe_wipe_storage_for_dev(app: &tauri::AppHandle) -> Result<(), StorageError> {
@@ -75,33 +188,83 @@ pub fn maybe_wipe_storage_for_dev(app: &tauri::AppHandle) -> Result<(), StorageE
 
 pub fn ensure_user_storage(app: &tauri::AppHandle) -> Result<UserStorageMeta, StorageError> {
     let root = user_storage_root(app)?;
-    fs::create_dir_all(&root)?;
+    ensure_storage_at(&root)
+}
+
+/// Like `ensure_user_storage`, but refuses storage that is in read-only mode
+/// because it was written by a newer StagePilot version.
+pub fn ensure_writable_storage(app: &tauri::AppHandle) -> Result<UserStorageMeta, StorageError> {
+    let meta = ensure_user_storage(app)?;
+    if let Some(failed) = &meta.failed_migration {
+        return Err(StorageError::MigrationFailed(failed.reason.clone()));
+    }
+    if is_read_only(&meta) {
+        return Err(StorageError::ReadOnly(meta.schema_version));
+    }
+    Ok(meta)
+}
+
+pub fn is_read_only(meta: &UserStorageMeta) -> bool {
+    meta.schema_version > STORAGE_SCHEMA_VERSION || meta.failed_migration.is_some()
+}
+
+pub fn ensure_storage_at(root: &Path) -> Result<UserStorageMeta, StorageError> {
+    fs::create_dir_all(root)?;
     for folder in [
-        "projects", "exports", "temp", "versions", "assets", "library",
+        "projects", "exports", "temp", "versions", "blobs", "assets", "library", "trash",
     ] {
         fs::create_dir_all(root.join(folder))?;
     }
 
-    let meta_path = storage_meta_path(&root);
+    let meta_path = storage_meta_path(root);
     if !meta_path.exists() {
         let meta = UserStorageMeta {
             schema_version: STORAGE_SCHEMA_VERSION,
             created_at: now_iso(),
             last_migrated_at: None,
+            version_retention: None,
+            failed_migration: None,
         };
-        let json = serde_json::to_vec_pretty(&meta).map_err(|e| {
-            StorageError::Resolve(format!("Failed to serialize storage metadata: {e}"))
-        })?;
-        atomic_write_bytes(&meta_path, &json)?;
+        write_storage_meta(root, &meta)?;
         return Ok(meta);
     }
 
-    let content = fs::read_to_string(&meta_path)?;
-    let meta: UserStorageMeta = serde_json::from_str(&content)
-        .map_err(|e| StorageError::Resolve(format!("Invalid storage metadata JSON: {e}")))?;
-    if meta.schema_version != STORAGE_SCHEMA_VERSION {
-        return Err(StorageError::InvalidSchema(meta.schema_version));
+    let meta = read_storage_meta(root)?;
+    if meta.schema_version < STORAGE_SCHEMA_VERSION && !retry_blocked(&meta) {
+        // Another window may be migrating the same tree; re-read under the lock.
+        let _lock = lock_storage_file(&meta_path)?;
+        let meta = read_storage_meta(root)?;
+        if retry_blocked(&meta) {
+            return Ok(meta);
+        }
+        return migrate_storage(root, meta, STORAGE_SCHEMA_VERSION, STORAGE_MIGRATIONS);
     }
+    // Newer schemas and failed upgrades are left untouched; callers check
+    // `is_read_only`.
+    Ok(meta)
+}
+
+pub fn read_storage_meta(root: &Path) -> Result<UserStorageMeta, StorageError> {
+    let content = fs::read_to_string(storage_meta_path(root))?;
+    serde_json::from_str(&content)
+        .map_err(|e| StorageError::Resolve(format!("Invalid storage metadata JSON: {e}")))
+}
+
+pub fn write_storage_meta(root: &Path, meta: &UserStorageMeta) -> Result<(), StorageError> {
+    let json = serde_json::to_vec_pretty(meta)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize storage metadata: {e}")))?;
+    atomic_write_bytes(&storage_meta_path(root), &json)
+}
+
+/// Read-modify-write of `storage.json` under its lock.
+pub fn update_storage_meta(
+    root: &Path,
+    update: impl FnOnce(&mut UserStorageMeta),
+) -> Result<UserStorageMeta, StorageError> {
+    let _lock = lock_storage_file(&storage_meta_path(root))?;
+    let mut meta = read_storage_meta(root)?;
+    update(&mut meta);
+    write_storage_meta(root, &meta)?;
     Ok(meta)
 }
 
@@ -130,6 +293,28 @@ pub fn library_dir(app: &tauri::AppHandle) -> Result<PathBuf, StorageError> {
     Ok(user_storage_root(app)?.join("library"))
 }
 
+/// Published PDFs belonging to `slug`: `<slug>.pdf` plus the `<slug>__N.pdf`
+/// fallbacks written while the main file was locked.
+pub fn export_files_for_slug(exports_dir: &Path, slug: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(exports_dir) else {
+        return Vec::new();
+    };
+    let exact = format!("{}.pdf", slug);
+    let prefix = format!("{}__", slug);
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|v| v.to_str())
+                .map(|name| name == exact || (name.starts_with(&prefix) && name.ends_with(".pdf")))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
 pub fn sanitize_id_to_filename(project_id: &str) -> String {
     let mut out = String::with_capacity(project_id.len().min(MAX_ID_LEN));
     for ch in project_id.chars() {
@@ -177,12 +362,68 @@ pub fn safe_join(base: &Path, child: &str) -> Result<PathBuf, StorageError> {
     Ok(base.join(child_path))
 }
 
+pub fn copy_dir_recursive(source: &Path, target: &Path) -> Result<(), StorageError> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let path = entry.path();
+        let dest = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&path, &dest)?;
+        } else {
+            fs::copy(&path, &dest)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Unique sibling temp path (`.<name>.<pid>-<n>.tmp`) so concurrent writers in
+/// different processes or threads never share a temp file.
+pub(crate) fn unique_temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().and_then(|v| v.to_str()).unwrap_or("file");
+    let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name,
+        std::process::id(),
+        counter
+    ))
+}
+
 pub fn atomic_write_bytes(path: &Path, bytes: &[u8]) -> Result<(), StorageError> {
-    let temp_path = path.with_extension("tmp");
-    let mut file = File::create(&temp_path)?;
-    file.write_all(bytes)?;
-    file.sync_all()?;
-    fs::rename(temp_path, path)?;
+    let temp_path = unique_temp_path(path);
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err.into());
+    }
+    sync_parent_dir(path)?;
+    Ok(())
+}
+
+/// Persists the rename itself; without this a crash can lose the new entry.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), StorageError> {
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), StorageError> {
+    // Directory handles cannot be fsynced on Windows; NTFS journals the rename.
     Ok(())
 }
 
@@ -218,4 +459,3 @@ mod tests {
         assert!(!rendered.contains(".."));
     }
 }
-
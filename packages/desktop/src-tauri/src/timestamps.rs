@@ -0,0 +1,276 @@
+//! RFC 3339 UTC timestamps (`2025-03-07T18:30:00.000Z`, the format of JS
+//! `toISOString`) and the clock that produces them.
+
+use serde_json::Value;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of the current time. Production code uses `SystemClock`; tests pass
+/// a fixed clock.
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Unix epoch values above this are taken as milliseconds, below as seconds.
+const MILLIS_THRESHOLD: u64 = 100_000_000_000;
+
+/// Civil date for a day count since 1970-01-01 (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn epoch_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let millis = epoch_millis(time);
+    let (days, day_millis) = (millis.div_euclid(86_400_000), millis.rem_euclid(86_400_000));
+    let (year, month, day) = civil_from_days(days);
+    let secs = day_millis / 1_000;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+        day_millis % 1_000
+    )
+}
+
+/// UTC calendar date as `YYYY-MM-DD`.
+pub fn utc_date(time: SystemTime) -> String {
+    format_rfc3339(time)[..10].to_string()
+}
+
+fn number(value: &str, range: std::ops::RangeInclusive<i64>) -> Option<i64> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok().filter(|n| range.contains(n))
+}
+
+fn two_digits(value: &str, range: std::ops::RangeInclusive<i64>) -> Option<i64> {
+    number(value, range).filter(|_| value.len() == 2)
+}
+
+/// Parses `YYYY-MM-DD` into days since the epoch. Impossible dates such as
+/// `2025-02-30` are rejected.
+fn parse_date(value: &str) -> Option<i64> {
+    let mut parts = value.split('-');
+    let year = parts.next().filter(|v| v.len() == 4)?;
+    let month = parts.next()?;
+    let day = parts.next()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let date = (
+        number(year, 0..=9999)?,
+        two_digits(month, 1..=12)?,
+        two_digits(day, 1..=31)?,
+    );
+    let days = days_from_civil(date.0, date.1, date.2);
+    (civil_from_days(days) == date).then_some(days)
+}
+
+/// Whether `value` is a valid calendar date in `YYYY-MM-DD` form.
+pub fn is_iso_date(value: &str) -> bool {
+    parse_date(value).is_some()
+}
+
+/// Parses an RFC 3339 timestamp (`T` separator, optional fraction, `Z` or a
+/// `±HH:MM` offset).
+pub fn parse_rfc3339(value: &str) -> Option<SystemTime> {
+    let (date, rest) = value.split_once(['T', 't'])?;
+    let days = parse_date(date)?;
+    if rest.len() < 9 || !rest.is_char_boundary(8) {
+        return None;
+    }
+    let (clock, mut zone) = rest.split_at(8);
+    let mut fields = clock.split(':');
+    let hour = two_digits(fields.next()?, 0..=23)?;
+    let minute = two_digits(fields.next()?, 0..=59)?;
+    let second = two_digits(fields.next()?, 0..=60)?;
+
+    let mut millis = 0;
+    if let Some(fraction) = zone.strip_prefix('.') {
+        let digits = fraction
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        if digits == 0 {
+            return None;
+        }
+        millis = format!("{:0<3}", &fraction[..digits.min(3)]).parse().ok()?;
+        zone = &fraction[digits..];
+    }
+    let offset_minutes = match zone {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match zone.chars().next()? {
+                '+' => 1,
+                '-' => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = zone[1..].split_once(':')?;
+            sign * (two_digits(hours, 0..=23)? * 60 + two_digits(minutes, 0..=59)?)
+        }
+    };
+
+    let total = ((days * 86_400 + hour * 3_600 + minute * 60 + second - offset_minutes * 60)
+        * 1_000)
+        + millis;
+    u64::try_from(total)
+        .ok()
+        .map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+/// Rewrites a stored timestamp as RFC 3339 UTC. Understands RFC 3339 with any
+/// offset, Unix seconds or milliseconds (with or without the stray `Z` older
+/// builds wrote) and bare `YYYY-MM-DD` dates. `None` if unparseable.
+pub fn normalize_timestamp(value: &str) -> Option<String> {
+    let value = value.trim();
+    if let Some(time) = parse_rfc3339(value) {
+        return Some(format_rfc3339(time));
+    }
+    let digits = value.strip_suffix(['Z', 'z']).unwrap_or(value);
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        let number: u64 = digits.parse().ok()?;
+        let millis = if number >= MILLIS_THRESHOLD {
+            number
+        } else {
+            number.checked_mul(1_000)?
+        };
+        return Some(format_rfc3339(UNIX_EPOCH + Duration::from_millis(millis)));
+    }
+    parse_date(value)
+        .and_then(|days| u64::try_from(days * 86_400_000).ok())
+        .map(|millis| format_rfc3339(UNIX_EPOCH + Duration::from_millis(millis)))
+}
+
+/// Normalizes the string timestamps under `keys`; unparseable values are
+/// removed. Returns whether anything changed.
+pub fn normalize_json_timestamps(object: &mut Value, keys: &[&str]) -> bool {
+    let Some(fields) = object.as_object_mut() else {
+        return false;
+    };
+    let mut changed = false;
+    for key in keys {
+        let Some(current) = fields.get(*key).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        match normalize_timestamp(current) {
+            Some(normalized) if normalized == current => {}
+            Some(normalized) => {
+                fields.insert((*key).into(), Value::String(normalized));
+                changed = true;
+            }
+            None => {
+                fields.remove(*key);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Backend-owned project stamps: `createdAt` is kept from the file being
+/// replaced (or set now for a new project) and `updatedAt` is always now.
+pub fn stamp_project(project: &mut Value, previous: Option<&Value>, clock: &dyn Clock) {
+    let now = format_rfc3339(clock.now());
+    let created_at = previous
+        .and_then(|previous| previous.get("createdAt"))
+        .and_then(|v| v.as_str())
+        .and_then(normalize_timestamp)
+        .unwrap_or_else(|| now.clone());
+    if let Some(fields) = project.as_object_mut() {
+        fields.insert("createdAt".into(), Value::String(created_at));
+        fields.insert("updatedAt".into(), Value::String(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_rfc3339, normalize_json_timestamps, normalize_timestamp, stamp_project, Clock,
+    };
+    use serde_json::json;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    struct FixedClock(SystemTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            self.0
+        }
+    }
+
+    #[test]
+    fn formats_normalizes_and_stamps() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_760_000_000_123);
+        assert_eq!(format_rfc3339(time), "2025-10-09T08:53:20.123Z");
+
+        for (input, expected) in [
+            ("1760000000Z", Some("2025-10-09T08:53:20.000Z")),
+            ("1760000000123", Some("2025-10-09T08:53:20.123Z")),
+            (
+                "2025-10-09T10:53:20+02:00",
+                Some("2025-10-09T08:53:20.000Z"),
+            ),
+            ("2025-10-09T08:53:20.5Z", Some("2025-10-09T08:53:20.500Z")),
+            ("2025-10-09", Some("2025-10-09T00:00:00.000Z")),
+            ("2024-02-29T23:59:59Z", Some("2024-02-29T23:59:59.000Z")),
+            ("yesterday", None),
+            ("2025-13-01T00:00:00Z", None),
+            ("2025-02-29", None),
+        ] {
+            assert_eq!(normalize_timestamp(input).as_deref(), expected, "{}", input);
+        }
+
+        let mut project = json!({"createdAt": "1700000000Z", "archivedAt": "soon"});
+        assert!(normalize_json_timestamps(
+            &mut project,
+            &["createdAt", "archivedAt"]
+        ));
+        assert_eq!(project["createdAt"], "2023-11-14T22:13:20.000Z");
+        assert!(project.get("archivedAt").is_none());
+
+        let clock = FixedClock(time);
+        let mut saved = json!({"createdAt": "client value"});
+        stamp_project(&mut saved, Some(&project), &clock);
+        assert_eq!(saved["createdAt"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(saved["updatedAt"], "2025-10-09T08:53:20.123Z");
+        let mut fresh = json!({});
+        stamp_project(&mut fresh, None, &clock);
+        assert_eq!(fresh["createdAt"], fresh["updatedAt"]);
+    }
+}
@@ -0,0 +1,245 @@
+//! Notes, tags and the distribution log of exported snapshots: who received
+//! which version, how and when.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::project_revision::project_revision;
+use crate::project_versions::{
+    list_versions, update_version_meta, version_dir, ProjectSnapshotMeta,
+};
+use crate::storage_paths::StorageError;
+use crate::timestamps::{format_rfc3339, normalize_timestamp};
+
+/// One delivery of a snapshot, stored in `sentTo` of its `meta.json`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SentRecord {
+    pub contact: String,
+    /// How it was delivered, e.g. `email` or `print`.
+    pub channel: String,
+    /// RFC 3339 UTC.
+    pub sent_at: String,
+}
+
+/// A `SentRecord` together with the snapshot it belongs to.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributionEntry {
+    pub version_id: String,
+    pub generated_at: String,
+    /// Content hash of the snapshot's `project.json`, comparable with the
+    /// revision returned by `save_project`.
+    pub project_revision: Option<String>,
+    pub contact: String,
+    pub channel: String,
+    pub sent_at: String,
+}
+
+fn trimmed(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Replaces the note and/or tags of a snapshot; `None` leaves a field as it is
+/// and an empty note clears it. Tags are trimmed and deduplicated.
+pub fn annotate_version(
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+    note: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<ProjectSnapshotMeta, StorageError> {
+    update_version_meta(versions_root, project_id, version_id, |meta| {
+        if let Some(note) = note {
+            meta.note = trimmed(&note);
+        }
+        if let Some(tags) = tags {
+            meta.tags.clear();
+            for tag in tags.iter().filter_map(|tag| trimmed(tag)) {
+                if !meta.tags.contains(&tag) {
+                    meta.tags.push(tag);
+                }
+            }
+        }
+    })
+}
+
+/// Appends to a snapshot's distribution log and flags it as sent. `sent_at`
+/// defaults to `now`.
+pub fn record_sent(
+    versions_root: &Path,
+    project_id: &str,
+    version_id: &str,
+    contact: &str,
+    channel: &str,
+    sent_at: Option<&str>,
+    now: SystemTime,
+) -> Result<ProjectSnapshotMeta, StorageError> {
+    let contact = trimmed(contact)
+        .ok_or_else(|| StorageError::Resolve("Contact must not be empty".into()))?;
+    let channel = trimmed(channel)
+        .ok_or_else(|| StorageError::Resolve("Channel must not be empty".into()))?;
+    let sent_at = match sent_at {
+        Some(value) => normalize_timestamp(value)
+            .ok_or_else(|| StorageError::Resolve(format!("Invalid sentAt: {}", value)))?,
+        None => format_rfc3339(now),
+    };
+    update_version_meta(versions_root, project_id, version_id, |meta| {
+        meta.sent = true;
+        meta.sent_to.push(SentRecord {
+            contact,
+            channel,
+            sent_at,
+        });
+    })
+}
+
+/// Every delivery of a project's snapshots, newest first. `contact` keeps only
+/// records whose contact contains it, ignoring case.
+pub fn distribution_log(
+    versions_root: &Path,
+    project_id: &str,
+    contact: Option<&str>,
+) -> Result<Vec<DistributionEntry>, StorageError> {
+    let contact = contact.and_then(trimmed).map(|c| c.to_lowercase());
+    let mut entries = Vec::new();
+    for meta in list_versions(versions_root, project_id)? {
+        let records: Vec<&SentRecord> = meta
+            .sent_to
+            .iter()
+            .filter(|record| {
+                contact
+                    .as_ref()
+                    .is_none_or(|c| record.contact.to_lowercase().contains(c.as_str()))
+            })
+            .collect();
+        if records.is_empty() {
+            continue;
+        }
+        let revision = version_dir(versions_root, project_id, &meta.version_id)
+            .and_then(|dir| Ok(fs::read(dir.join("project.json"))?))
+            .ok()
+            .map(|bytes| project_revision(&bytes));
+        for record in records {
+            entries.push(DistributionEntry {
+                version_id: meta.version_id.clone(),
+                generated_at: meta.generated_at.clone(),
+                project_revision: revision.clone(),
+                contact: record.contact.clone(),
+                channel: record.channel.clone(),
+                sent_at: record.sent_at.clone(),
+            });
+        }
+    }
+    entries.sort_by(|a, b| b.sent_at.cmp(&a.sent_at));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{annotate_version, distribution_log, record_sent};
+    use crate::test_support::TempDir;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(1_760_000_000_000)
+    }
+
+    fn versions() -> TempDir {
+        let root = TempDir::new("sent");
+        for version_id in ["v1", "v2"] {
+            root.write(format!("p1/{}/project.json", version_id), version_id);
+            root.write(
+                format!("p1/{}/meta.json", version_id),
+                format!(
+                    r#"{{"projectId":"p1","versionId":"{}","generatedAt":"2025-01-01T00:00:00.000Z"}}"#,
+                    version_id
+                ),
+            );
+        }
+        root
+    }
+
+    /// v1 went to the venue by email, v2 to the FOH engineer in print.
+    fn delivered() -> TempDir {
+        let root = versions();
+        record_sent(
+            root.path(),
+            "p1",
+            "v1",
+            "Lucerna venue",
+            "email",
+            Some("2025-02-01T10:00:00+01:00"),
+            now(),
+        )
+        .expect("send v1");
+        record_sent(
+            root.path(),
+            "p1",
+            "v2",
+            "FOH engineer",
+            "print",
+            None,
+            now(),
+        )
+        .expect("send v2");
+        root
+    }
+
+    #[test]
+    fn annotation_trims_note_and_dedupes_tags() {
+        let root = versions();
+        let meta = annotate_version(
+            root.path(),
+            "p1",
+            "v1",
+            Some(" stage left fixed ".into()),
+            Some(vec!["final".into(), " final ".into(), "".into()]),
+        )
+        .expect("annotate");
+        assert_eq!(meta.note.as_deref(), Some("stage left fixed"));
+        assert_eq!(meta.tags, vec!["final"]);
+    }
+
+    #[test]
+    fn recording_a_delivery_marks_version_sent() {
+        let root = versions();
+        let meta = record_sent(
+            root.path(),
+            "p1",
+            "v2",
+            "FOH engineer",
+            "print",
+            None,
+            now(),
+        )
+        .expect("send");
+        assert!(meta.sent);
+    }
+
+    #[test]
+    fn delivery_needs_a_recipient() {
+        let root = versions();
+        assert!(record_sent(root.path(), "p1", "v2", " ", "email", None, now()).is_err());
+    }
+
+    #[test]
+    fn log_lists_newest_version_first() {
+        let root = delivered();
+        let log = distribution_log(root.path(), "p1", None).expect("log");
+        let versions: Vec<&str> = log.iter().map(|e| e.version_id.as_str()).collect();
+        assert_eq!(versions, vec!["v2", "v1"]);
+    }
+
+    #[test]
+    fn log_filters_by_recipient() {
+        let root = delivered();
+        let venue = distribution_log(root.path(), "p1", Some("lucerna")).expect("venue log");
+        assert_eq!(venue.len(), 1);
+        assert_eq!(venue[0].sent_at, "2025-02-01T09:00:00.000Z");
+        assert!(venue[0].project_revision.is_some());
+    }
+}
@@ -0,0 +1,341 @@
+//! Content-addressed storage for snapshot files. Each `project.json` and PDF
+//! in `versions/` is stored once as `blobs/<aa>/<sha256>`, and the snapshot's
+//! file becomes a hard link to that blob, so repeated identical exports cost
+//! no extra space. Snapshots keep their plain layout and readers need no
+//! changes; `manifest.json` in each snapshot records the blob of every file.
+//! Where hard links are not supported (FAT) the snapshot keeps its own files
+//! and no blob is stored, so nothing is kept twice.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage_paths::{atomic_write_bytes, sha256_hex, unique_temp_path, StorageError};
+
+pub const BLOBS_DIR_NAME: &str = "blobs";
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobRef {
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// `manifest.json` of a snapshot: file name -> blob.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionManifest {
+    pub manifest_version: u32,
+    pub files: BTreeMap<String, BlobRef>,
+    /// `false` when the files could not be hard-linked to blobs; the snapshot's
+    /// own files are then the only copy and its blobs are not kept.
+    #[serde(default = "default_shared")]
+    pub shared: bool,
+}
+
+fn default_shared() -> bool {
+    true
+}
+
+impl VersionManifest {
+    fn hashes(&self) -> Vec<&str> {
+        let mut hashes: Vec<&str> = self
+            .files
+            .values()
+            .map(|blob| blob.sha256.as_str())
+            .collect();
+        hashes.sort_unstable();
+        hashes
+    }
+
+    /// Same bytes, regardless of file names (the PDF is named after the slug).
+    pub fn same_content(&self, other: &VersionManifest) -> bool {
+        self.hashes() == other.hashes()
+    }
+}
+
+pub fn blob_path(root: &Path, sha256: &str) -> PathBuf {
+    root.join(BLOBS_DIR_NAME).join(&sha256[..2]).join(sha256)
+}
+
+/// `None` for snapshots that were not ingested yet.
+pub fn read_manifest(version_dir: &Path) -> Option<VersionManifest> {
+    let content = fs::read_to_string(version_dir.join(MANIFEST_FILE_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Whether both paths are links to one file: device and inode on unix,
+/// volume serial and file index on Windows.
+fn same_file(a: &Path, b: &Path) -> bool {
+    ::same_file::is_same_file(a, b).unwrap_or(false)
+}
+
+/// Makes `path` share storage with `blob`, creating the blob as a link to
+/// `path` when it does not exist yet. Returns `false`, without copying, when
+/// the file system has no hard links.
+fn link_to_blob(path: &Path, blob: &Path) -> Result<bool, StorageError> {
+    if !blob.exists() {
+        if let Some(parent) = blob.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return Ok(fs::hard_link(path, blob).is_ok());
+    }
+    if same_file(path, blob) {
+        return Ok(true);
+    }
+    let temp = unique_temp_path(path);
+    if fs::hard_link(blob, &temp).is_err() {
+        return Ok(false);
+    }
+    if let Err(err) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(err.into());
+    }
+    Ok(true)
+}
+
+fn write_manifest(version_dir: &Path, manifest: &VersionManifest) -> Result<(), StorageError> {
+    let json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| StorageError::Resolve(format!("Failed to serialize manifest: {e}")))?;
+    atomic_write_bytes(&version_dir.join(MANIFEST_FILE_NAME), &json)
+}
+
+/// Stores the files of one snapshot in the blob store and writes its
+/// manifest. `meta.json` stays a plain file because it is edited in place.
+pub fn ingest_version(root: &Path, version_dir: &Path) -> Result<VersionManifest, StorageError> {
+    let mut files = BTreeMap::new();
+    let mut shared = true;
+    for entry in fs::read_dir(version_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.file_type()?.is_file()
+            || name.starts_with('.')
+            || name == "meta.json"
+            || name == MANIFEST_FILE_NAME
+        {
+            continue;
+        }
+        let path = entry.path();
+        let bytes = fs::read(&path)?;
+        let sha256 = sha256_hex(&bytes);
+        shared &= link_to_blob(&path, &blob_path(root, &sha256))?;
+        files.insert(
+            name,
+            BlobRef {
+                sha256,
+                size: bytes.len() as u64,
+            },
+        );
+    }
+    let manifest = VersionManifest {
+        manifest_version: MANIFEST_VERSION,
+        files,
+        shared,
+    };
+    write_manifest(version_dir, &manifest)?;
+    Ok(manifest)
+}
+
+fn version_dirs(root: &Path) -> Result<Vec<PathBuf>, StorageError> {
+    let versions = root.join("versions");
+    let mut dirs = Vec::new();
+    if !versions.exists() {
+        return Ok(dirs);
+    }
+    for project_dir in fs::read_dir(&versions)? {
+        let project_dir = project_dir?.path();
+        if !project_dir.is_dir() {
+            continue;
+        }
+        for version_dir in fs::read_dir(&project_dir)? {
+            let version_dir = version_dir?.path();
+            if version_dir.join("meta.json").is_file() {
+                dirs.push(version_dir);
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+/// Ingests every snapshot without a manifest, or whose blobs went missing
+/// (after a backup restore or a trash restore). Returns the number of
+/// snapshots ingested.
+pub fn dedupe_versions(root: &Path) -> Result<usize, StorageError> {
+    let mut ingested = 0;
+    for dir in version_dirs(root)? {
+        let current = read_manifest(&dir).is_some_and(|manifest| {
+            !manifest.shared
+                || manifest
+                    .files
+                    .values()
+                    .all(|blob| blob_path(root, &blob.sha256).is_file())
+        });
+        if !current {
+            ingest_version(root, &dir)?;
+            ingested += 1;
+        }
+    }
+    Ok(ingested)
+}
+
+/// Links snapshot files to their blobs again after a copy (a storage move)
+/// turned them into separate files. Snapshots whose files cannot be linked are
+/// marked unshared so `collect_garbage` drops the now redundant blobs.
+/// Returns the number of files linked.
+pub fn relink_versions(root: &Path) -> Result<usize, StorageError> {
+    let mut relinked = 0;
+    for dir in version_dirs(root)? {
+        let Some(mut manifest) = read_manifest(&dir).filter(|manifest| manifest.shared) else {
+            continue;
+        };
+        for (name, blob) in &manifest.files {
+            let path = dir.join(name);
+            let blob = blob_path(root, &blob.sha256);
+            if !path.is_file() || !blob.is_file() || same_file(&path, &blob) {
+                continue;
+            }
+            if !link_to_blob(&path, &blob)? {
+                manifest.shared = false;
+                break;
+            }
+            relinked += 1;
+        }
+        if !manifest.shared {
+            write_manifest(&dir, &manifest)?;
+        }
+    }
+    Ok(relinked)
+}
+
+/// Removes blobs no shared snapshot in `versions/` refers to. Snapshot files
+/// are links to their blobs or standalone copies, so this never loses
+/// snapshot data.
+pub fn collect_garbage(root: &Path) -> Result<usize, StorageError> {
+    let blobs = root.join(BLOBS_DIR_NAME);
+    if !blobs.exists() {
+        return Ok(0);
+    }
+    let referenced: HashSet<String> = version_dirs(root)?
+        .iter()
+        .filter_map(|dir| read_manifest(dir))
+        .filter(|manifest| manifest.shared)
+        .flat_map(|manifest| manifest.files.into_values().map(|blob| blob.sha256))
+        .collect();
+    let mut removed = 0;
+    for prefix in fs::read_dir(&blobs)? {
+        let prefix = prefix?.path();
+        if !prefix.is_dir() {
+            continue;
+        }
+        for blob in fs::read_dir(&prefix)? {
+            let blob = blob?.path();
+            let name = blob.file_name().and_then(|v| v.to_str()).unwrap_or("");
+            if !referenced.contains(name) {
+                fs::remove_file(&blob)?;
+                removed += 1;
+            }
+        }
+        if fs::read_dir(&prefix)?.next().is_none() {
+            fs::remove_dir(&prefix)?;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        blob_path, collect_garbage, dedupe_versions, read_manifest, relink_versions, same_file,
+        write_manifest,
+    };
+    use crate::storage_paths::copy_dir_recursive;
+    use crate::test_support::TempDir;
+    use std::fs;
+
+    /// Two snapshots of `p1` with the same bytes under different PDF names.
+    fn identical_versions() -> TempDir {
+        let root = TempDir::new("blobs");
+        for (version_id, pdf_name) in [("v1", "gig.pdf"), ("v2", "renamed-gig.pdf")] {
+            let dir = format!("versions/p1/{}", version_id);
+            root.write(format!("{}/project.json", dir), r#"{"id":"p1"}"#);
+            root.write(format!("{}/{}", dir, pdf_name), "%PDF-1.7");
+            root.write(format!("{}/meta.json", dir), "{}");
+        }
+        root
+    }
+
+    #[test]
+    fn identical_snapshots_share_blobs() {
+        let root = identical_versions();
+        assert_eq!(dedupe_versions(root.path()).expect("dedupe"), 2);
+        let v1 = read_manifest(&root.join("versions/p1/v1")).expect("manifest v1");
+        let v2 = read_manifest(&root.join("versions/p1/v2")).expect("manifest v2");
+        assert!(v1.same_content(&v2));
+        let pdf_blob = blob_path(root.path(), &v1.files["gig.pdf"].sha256);
+        assert_eq!(fs::read_to_string(&pdf_blob).expect("blob"), "%PDF-1.7");
+        assert_eq!(
+            fs::read_to_string(root.join("versions/p1/v2/renamed-gig.pdf")).expect("read"),
+            "%PDF-1.7"
+        );
+    }
+
+    #[test]
+    fn dedupe_skips_versions_already_stored() {
+        let root = identical_versions();
+        dedupe_versions(root.path()).expect("dedupe");
+        assert_eq!(dedupe_versions(root.path()).expect("second pass"), 0);
+    }
+
+    #[test]
+    fn garbage_collection_removes_unreferenced_blobs() {
+        let root = identical_versions();
+        dedupe_versions(root.path()).expect("dedupe");
+        let v1 = read_manifest(&root.join("versions/p1/v1")).expect("manifest");
+        let pdf_blob = blob_path(root.path(), &v1.files["gig.pdf"].sha256);
+
+        fs::remove_dir_all(root.join("versions/p1/v2")).expect("drop v2");
+        assert_eq!(collect_garbage(root.path()).expect("gc"), 0);
+        fs::remove_dir_all(root.join("versions/p1/v1")).expect("drop v1");
+        assert_eq!(collect_garbage(root.path()).expect("gc"), 2);
+        assert!(!pdf_blob.exists());
+    }
+
+    #[test]
+    fn relink_restores_sharing_after_a_copy() {
+        let root = identical_versions();
+        dedupe_versions(root.path()).expect("dedupe");
+        let moved = TempDir::new("blobs-moved");
+        for entry in ["versions", "blobs"] {
+            copy_dir_recursive(&root.join(entry), &moved.join(entry)).expect("copy");
+        }
+        let v1 = read_manifest(&moved.join("versions/p1/v1")).expect("manifest");
+        let pdf_blob = blob_path(moved.path(), &v1.files["gig.pdf"].sha256);
+        let pdf = moved.join("versions/p1/v2/renamed-gig.pdf");
+        assert!(!same_file(&pdf, &pdf_blob));
+
+        assert_eq!(relink_versions(moved.path()).expect("relink"), 4);
+        assert!(same_file(&pdf, &pdf_blob));
+        assert_eq!(relink_versions(moved.path()).expect("second pass"), 0);
+    }
+
+    #[test]
+    fn unshared_snapshots_keep_no_blobs() {
+        let root = identical_versions();
+        dedupe_versions(root.path()).expect("dedupe");
+        for version_id in ["v1", "v2"] {
+            let dir = root.join(format!("versions/p1/{}", version_id));
+            let mut manifest = read_manifest(&dir).expect("manifest");
+            manifest.shared = false;
+            write_manifest(&dir, &manifest).expect("write manifest");
+        }
+        assert_eq!(collect_garbage(root.path()).expect("gc"), 2);
+        assert_eq!(dedupe_versions(root.path()).expect("no re-ingest"), 0);
+        assert_eq!(
+            fs::read_to_string(root.join("versions/p1/v1/gig.pdf")).expect("read"),
+            "%PDF-1.7"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Retention policy for the snapshots in `versions/`. A snapshot is kept when
+//! any rule keeps it: it is among the newest `keep_last` of its project, it is
+//! younger than `keep_days`, or it is flagged as sent.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::project_versions::{list_versions_in, project_versions_dir, ProjectSnapshotMeta};
+use crate::storage_paths::{safe_join, StorageError};
+use crate::timestamps::parse_rfc3339;
+use crate::version_blobs::read_manifest;
+
+/// Stored as `versionRetention` in `storage.json`. Without `keep_last` and
+/// `keep_days` nothing is pruned.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionRetention {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_last: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_days: Option<u64>,
+    /// Prune the exported project's snapshots after each export.
+    #[serde(default)]
+    pub auto_prune: bool,
+}
+
+impl VersionRetention {
+    /// `index` is the snapshot's position in its project, newest first.
+    fn keeps(&self, index: usize, meta: &ProjectSnapshotMeta, now: SystemTime) -> bool {
+        if meta.sent || (self.keep_last.is_none() && self.keep_days.is_none()) {
+            return true;
+        }
+        if self.keep_last.is_some_and(|count| index < count) {
+            return true;
+        }
+        let Some(days) = self.keep_days else {
+            return false;
+        };
+        // Snapshots with an unreadable date are never pruned by age.
+        let Some(generated_at) = parse_rfc3339(&meta.generated_at) else {
+            return true;
+        };
+        now.duration_since(generated_at)
+            .map(|age| age < Duration::from_secs(days * 24 * 60 * 60))
+            .unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunedVersion {
+    pub project_id: String,
+    pub version_id: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneReport {
+    /// Nothing was deleted; `pruned` lists what would be.
+    pub dry_run: bool,
+    pub pruned: Vec<PrunedVersion>,
+    pub kept: usize,
+    /// Bytes freed, or freeable on a dry run. Blobs still used by a kept
+    /// snapshot are not counted.
+    pub reclaimable_bytes: u64,
+}
+
+/// Bytes deleting a snapshot frees. Files stored as blobs count once, and not
+/// at all while a kept snapshot still shares them (`shared`).
+fn reclaimable_size(dir: &Path, shared: &mut HashSet<String>) -> Result<u64, StorageError> {
+    let manifest = read_manifest(dir);
+    let mut total = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            total += reclaimable_size(&entry.path(), shared)?;
+            continue;
+        }
+        if !file_type.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let blob = manifest.as_ref().and_then(|m| m.files.get(&name));
+        if blob.is_none_or(|blob| shared.insert(blob.sha256.clone())) {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+fn blob_hashes(dir: &Path) -> Vec<String> {
+    read_manifest(dir)
+        .map(|manifest| {
+            manifest
+                .files
+                .into_values()
+                .map(|blob| blob.sha256)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn prune_project_dir(
+    project_dir: &Path,
+    retention: &VersionRetention,
+    now: SystemTime,
+    report: &mut PruneReport,
+) -> Result<(), StorageError> {
+    let mut shared = HashSet::new();
+    let mut prunable = Vec::new();
+    for (index, meta) in list_versions_in(project_dir)?.into_iter().enumerate() {
+        let dir = safe_join(project_dir, &meta.version_id)?;
+        if retention.keeps(index, &meta, now) || !dir.join("meta.json").is_file() {
+            report.kept += 1;
+            shared.extend(blob_hashes(&dir));
+        } else {
+            prunable.push((meta, dir));
+        }
+    }
+    for (meta, dir) in prunable {
+        let bytes = reclaimable_size(&dir, &mut shared)?;
+        if !report.dry_run {
+            fs::remove_dir_all(&dir)?;
+        }
+        report.reclaimable_bytes += bytes;
+        report.pruned.push(PrunedVersion {
+            project_id: meta.project_id,
+            version_id: meta.version_id,
+            bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Applies `retention` to one project, or to every project when `project_id`
+/// is `None`.
+pub fn prune_versions(
+    versions_root: &Path,
+    project_id: Option<&str>,
+    retention: &VersionRetention,
+    now: SystemTime,
+    dry_run: bool,
+) -> Result<PruneReport, StorageError> {
+    let mut report = PruneReport {
+        dry_run,
+        ..PruneReport::default()
+    };
+    match project_id {
+        Some(project_id) => prune_project_dir(
+            &project_versions_dir(versions_root, project_id),
+            retention,
+            now,
+            &mut report,
+        )?,
+        None if versions_root.exists() => {
+            for entry in fs::read_dir(versions_root)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    prune_project_dir(&path, retention, now, &mut report)?;
+                }
+            }
+        }
+        None => {}
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prune_versions, VersionRetention};
+    use crate::test_support::TempDir;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(1_760_000_000_000)
+    }
+
+    fn keep_last_one_for_30_days() -> VersionRetention {
+        VersionRetention {
+            keep_last: Some(1),
+            keep_days: Some(30),
+            auto_prune: false,
+        }
+    }
+
+    /// `p1` with v4 (recent), v3 and v1 (old, unsent) and v2 (old, sent).
+    fn versions() -> TempDir {
+        let root = TempDir::new("retention");
+        for (version_id, generated_at, sent) in [
+            ("v4", "2025-10-08T00:00:00.000Z", false),
+            ("v3", "2025-06-01T00:00:00.000Z", false),
+            ("v2", "2025-05-01T00:00:00.000Z", true),
+            ("v1", "2025-04-01T00:00:00.000Z", false),
+        ] {
+            root.write(format!("p1/{}/gig.pdf", version_id), "%PDF-1.7");
+            root.write(
+                format!("p1/{}/meta.json", version_id),
+                format!(
+                    r#"{{"projectId":"p1","versionId":"{}","generatedAt":"{}","sent":{}}}"#,
+                    version_id, generated_at, sent
+                ),
+            );
+        }
+        root
+    }
+
+    #[test]
+    fn dry_run_reports_old_unsent_versions_without_deleting() {
+        let root = versions();
+        let dry = prune_versions(root.path(), None, &keep_last_one_for_30_days(), now(), true)
+            .expect("dry run");
+        let pruned: Vec<&str> = dry.pruned.iter().map(|v| v.version_id.as_str()).collect();
+        assert_eq!(pruned, vec!["v3", "v1"]);
+        assert_eq!(dry.kept, 2);
+        assert!(dry.reclaimable_bytes > 16);
+        assert!(root.join("p1/v1").exists());
+    }
+
+    #[test]
+    fn prune_deletes_old_unsent_versions_and_keeps_sent_ones() {
+        let root = versions();
+        let retention = keep_last_one_for_30_days();
+        let dry = prune_versions(root.path(), None, &retention, now(), true).expect("dry run");
+        let report =
+            prune_versions(root.path(), Some("p1"), &retention, now(), false).expect("prune");
+        assert_eq!(report.reclaimable_bytes, dry.reclaimable_bytes);
+        assert!(!root.join("p1/v1").exists());
+        assert!(root.join("p1/v2").exists());
+    }
+
+    #[test]
+    fn default_retention_keeps_everything() {
+        let root = versions();
+        let report = prune_versions(
+            root.path(),
+            None,
+            &VersionRetention::default(),
+            now(),
+            false,
+        )
+        .expect("noop");
+        assert!(report.pruned.is_empty());
+        assert!(root.join("p1/v1").exists());
+    }
+}
@@ -13,8 +13,13 @@ export function useProjectsHubData(navigateImmediate: NavigateImmediateFn) {
   const [status, setStatus] = useState("");
 
   const refreshProjects = useCallback(async () => {
-    const { projects: maintainedProjects, migratedIds } = await refreshProjectsAndMigrate();
+    const { projects: maintainedProjects, migratedIds, problems } = await refreshProjectsAndMigrate();
     setProjects(maintainedProjects);
+    if (problems.length > 0) {
+      setStatus(
+        `${problems.length} project file(s) could not be read: ${problems.map((problem) => problem.fileName).join(", ")}`,
+      );
+    }
     const activePath = window.location.pathname;
     const match = activePath.match(/^\/projects\/([^/]+)/);
     if (match) {
@@ -39,14 +44,24 @@ export function useProjectsHubData(navigateImmediate: NavigateImmediateFn) {
       projectId: string,
       updater: (project: NewProjectPayload, now: Date) => NewProjectPayload,
     ) => {
-      const raw = await projectsApi.readProject(projectId);
-      const project = JSON.parse(raw) as NewProjectPayload;
-      const now = new Date();
-      const updatedProject = updater(project, now);
-      await projectsApi.saveProject({
-        projectId,
-        json: JSON.stringify(toPersistableProject(updatedProject), null, 2),
-      });
+      const save = async (raw: string, revision: string) => {
+        const project = JSON.parse(raw) as NewProjectPayload;
+        const updatedProject = updater(project, new Date());
+        await projectsApi.saveProject({
+          projectId,
+          json: JSON.stringify(toPersistableProject(updatedProject), null, 2),
+          expectedRevision: revision,
+        });
+      };
+      try {
+        const document = await projectsApi.readProjectDocument(projectId);
+        await save(document.json, document.revision);
+      } catch (error) {
+        // Lifecycle changes are a few fields, so reapply them on top of the newer file.
+        const conflict = projectsApi.getProjectConflict(error);
+        if (!conflict) throw error;
+        await save(conflict.json, conflict.revision);
+      }
       await refreshProjects();
     },
     [refreshProjects],
@@ -3,7 +3,7 @@ import { refreshProjectsAndMigrate } from "./projectMaintenance";
 
 vi.mock("./projectsApi", () => ({
   listBands: vi.fn(),
-  listProjects: vi.fn(),
+  listProjectsWithDiagnostics: vi.fn(),
   readProject: vi.fn(),
   saveProject: vi.fn(),
   deleteProjectPermanently: vi.fn(),
@@ -21,7 +21,7 @@ describe("refreshProjectsAndMigrate", () => {
 
   it("migrates legacy id and saves with legacyProjectId", async () => {
     mocked.listBands.mockResolvedValue([{ id: "band-a", name: "Band A", code: "ba" }]);
-    mocked.listProjects.mockResolvedValue([{ id: "legacy-id", bandRef: "band-a", purpose: "generic", documentDate: "2024-01-01", createdAt: "2024-01-01" }]);
+    mocked.listProjectsWithDiagnostics.mockResolvedValue({ projects: [{ id: "legacy-id", bandRef: "band-a", purpose: "generic", documentDate: "2024-01-01", createdAt: "2024-01-01" }], problems: [] });
     mocked.readProject.mockResolvedValue(JSON.stringify({
       id: "legacy-id",
       purpose: "generic",
@@ -39,9 +39,31 @@ describe("refreshProjectsAndMigrate", () => {
     expect(args.projectId).not.toBe("legacy-id");
   });
 
+  it("keeps numbered slug and display name of duplicated projects", async () => {
+    mocked.listBands.mockResolvedValue([{ id: "band-a", name: "Band A", code: "ba" }]);
+    const id = "01890f3c-7c4e-7a2b-8c3d-123456789abc";
+    mocked.listProjectsWithDiagnostics.mockResolvedValue({ projects: [{ id, bandRef: "band-a", purpose: "generic", documentDate: "2024-01-01", createdAt: "2024-01-01" }], problems: [] });
+    mocked.readProject.mockResolvedValue(JSON.stringify({
+      id,
+      purpose: "generic",
+      bandRef: "band-a",
+      documentDate: "2024-01-01",
+      createdAt: "2024-01-01",
+      templateType: "generic",
+      status: "active",
+      slug: "ba_Inputlist_Stageplan_2024_2",
+      displayName: "Band A – 2024 (2)",
+    }));
+
+    const { projects } = await refreshProjectsAndMigrate();
+
+    expect(mocked.saveProject).not.toHaveBeenCalled();
+    expect(projects[0].slug).toBe("ba_Inputlist_Stageplan_2024_2");
+  });
+
   it("purges trashed project when purgeAt is in the past", async () => {
     mocked.listBands.mockResolvedValue([{ id: "band-a", name: "Band A", code: "ba" }]);
-    mocked.listProjects.mockResolvedValue([{ id: "x", bandRef: "band-a", purpose: "generic", documentDate: "2024-01-01", createdAt: "2024-01-01" }]);
+    mocked.listProjectsWithDiagnostics.mockResolvedValue({ projects: [{ id: "x", bandRef: "band-a", purpose: "generic", documentDate: "2024-01-01", createdAt: "2024-01-01" }], problems: [] });
     mocked.readProject.mockResolvedValue(JSON.stringify({
       id: "x",
       purpose: "generic",
@@ -56,4 +78,16 @@ describe("refreshProjectsAndMigrate", () => {
 
     expect(mocked.deleteProjectPermanently).toHaveBeenCalledWith("x");
   });
+
+  it("lists projects once and passes unreadable files through", async () => {
+    const problem = { path: "/storage/projects/bad.json", fileName: "bad.json", kind: "parse" as const, message: "Invalid project JSON" };
+    mocked.listBands.mockResolvedValue([{ id: "band-a", name: "Band A", code: "ba" }]);
+    mocked.listProjectsWithDiagnostics.mockResolvedValue({ projects: [], problems: [problem] });
+
+    const { projects, problems } = await refreshProjectsAndMigrate();
+
+    expect(mocked.listProjectsWithDiagnostics).toHaveBeenCalledTimes(1);
+    expect(projects).toEqual([]);
+    expect(problems).toEqual([problem]);
+  });
 });
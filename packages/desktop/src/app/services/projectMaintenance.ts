@@ -3,16 +3,21 @@ import { generateUuidV7, isUuidV7 } from "../../../../../src/domain/projectNamin
 import type { NewProjectPayload, ProjectSummary } from "../shell/types";
 import { toPersistableProject } from "../shell/types";
 import * as projectsApi from "./projectsApi";
+import type { ProjectFileProblem } from "./projectsApi";
 import { migrateProjectLineupVocsToLeadBack } from "../domain/project/migrateProjectLineup";
 import { migrateProjectTalkbackOwner } from "../domain/project/migrateProjectTalkbackOwner";
 
-export async function refreshProjectsAndMigrate(): Promise<{ projects: ProjectSummary[]; migratedIds: Map<string, string> }> {
+export async function refreshProjectsAndMigrate(): Promise<{
+  projects: ProjectSummary[];
+  migratedIds: Map<string, string>;
+  problems: ProjectFileProblem[];
+}> {
   const availableBands = await projectsApi.listBands();
   const bandsById = new Map(availableBands.map((band) => [band.id, band]));
   const bandsByCode = new Map(
     availableBands.filter((band) => Boolean(band.code?.trim())).map((band) => [band.code?.trim().toLowerCase() ?? "", band]),
   );
-  const listed = await projectsApi.listProjects();
+  const { projects: listed, problems } = await projectsApi.listProjectsWithDiagnostics();
   const migratedIds = new Map<string, string>();
   const maintainedProjects: ProjectSummary[] = [];
   const now = new Date();
@@ -52,7 +57,10 @@ export async function refreshProjectsAndMigrate(): Promise<{ projects: ProjectSu
     };
     const slug = formatProjectSlug(namingSource, band);
     const displayName = formatProjectDisplayName(namingSource, band);
-    const needsNameMigration = project.slug !== slug || project.displayName !== displayName;
+    // Duplicates get `<slug>_N` / `<name> (N)` from the backend to avoid clashes; keep those.
+    const copySuffix = project.slug?.startsWith(`${slug}_`) ? project.slug.slice(slug.length + 1) : "";
+    const keepsCopySuffix = /^\d+$/.test(copySuffix) && project.displayName === `${displayName} (${copySuffix})`;
+    const needsNameMigration = !keepsCopySuffix && (project.slug !== slug || project.displayName !== displayName);
     const hasLegacyId = Object.prototype.hasOwnProperty.call(parsedRaw, "legacyId");
     const currentStatus = project.status ?? "active";
     const eventDate = project.eventDate;
@@ -76,8 +84,8 @@ export async function refreshProjectsAndMigrate(): Promise<{ projects: ProjectSu
     const migrated: NewProjectPayload = {
       ...(project as Omit<NewProjectPayload, "id" | "slug" | "displayName" | "bandRef">),
       id: nextId,
-      slug,
-      displayName,
+      slug: keepsCopySuffix ? (project.slug ?? slug) : slug,
+      displayName: keepsCopySuffix ? (project.displayName ?? displayName) : displayName,
       bandRef: canonicalBandRef,
       templateType: templateType ?? "generic",
       status: shouldAutoArchive ? "archived" : currentStatus,
@@ -111,5 +119,5 @@ export async function refreshProjectsAndMigrate(): Promise<{ projects: ProjectSu
     });
   }
 
-  return { projects: maintainedProjects, migratedIds };
+  return { projects: maintainedProjects, migratedIds, problems };
 }
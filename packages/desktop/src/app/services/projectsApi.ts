@@ -10,12 +10,368 @@ export function listProjects() {
   return invoke<ProjectSummary[]>(TAURI_COMMANDS.LIST_PROJECTS);
 }
 
+export type ProjectFileProblem = {
+  path: string;
+  fileName: string;
+  kind: "read" | "parse" | "notObject";
+  message: string;
+};
+
+export function listProjectsWithDiagnostics() {
+  return invoke<{ projects: ProjectSummary[]; problems: ProjectFileProblem[] }>(
+    TAURI_COMMANDS.LIST_PROJECTS_WITH_DIAGNOSTICS,
+  );
+}
+
+export type ProjectQuery = {
+  bandRef?: string;
+  purpose?: "event" | "generic";
+  when?: "all" | "upcoming" | "past";
+  dateFrom?: string;
+  dateTo?: string;
+  today?: string;
+  venue?: string;
+  text?: string;
+  sort?: "updatedAt" | "createdAt" | "eventDate" | "displayName" | "eventVenue";
+  descending?: boolean;
+  offset?: number;
+  limit?: number;
+};
+
+export function queryProjects(query: ProjectQuery) {
+  return invoke<{ total: number; offset: number; items: ProjectSummary[] }>(TAURI_COMMANDS.QUERY_PROJECTS, {
+    query,
+  });
+}
+
+export function quarantineProjectFile(fileName: string) {
+  return invoke<string>(TAURI_COMMANDS.QUARANTINE_PROJECT_FILE, { fileName });
+}
+
 export function readProject(projectId: string) {
   return invoke<string>(TAURI_COMMANDS.READ_PROJECT, { projectId });
 }
 
-export function saveProject(args: { projectId: string; legacyProjectId?: string; json: string }) {
-  return invoke<void>(TAURI_COMMANDS.SAVE_PROJECT, args);
+export type ProjectMigrationReport = {
+  fromVersion: number;
+  toVersion: number;
+  changes: string[];
+};
+
+export function readProjectDocument(projectId: string) {
+  return invoke<{ json: string; revision: string; migration?: ProjectMigrationReport | null }>(
+    TAURI_COMMANDS.READ_PROJECT_DOCUMENT,
+    { projectId },
+  );
+}
+
+/** `meta.json` of an export snapshot or of a restore point (`kind: "restorePoint"`, no PDF). */
+export type ProjectVersionMeta = {
+  schemaVersion: number;
+  projectId: string;
+  versionId: string;
+  generatedAt: string;
+  documentDate: string;
+  purpose?: "event" | "generic";
+  bandRef: string;
+  note?: string | null;
+  eventDate?: string;
+  eventVenue?: string;
+  slug?: string;
+  pdfFileName?: string;
+  paths: { versionDir: string; projectJson: string; metaJson: string; pdf?: string };
+  kind?: "restorePoint";
+  /** Sent versions are never pruned. */
+  sent?: boolean;
+  tags?: string[];
+  sentTo?: VersionSentRecord[];
+  /** Input hashes recorded at export time. */
+  sourceHashes?: { project: string; library: string };
+};
+
+export type VersionSentRecord = { contact: string; channel: string; sentAt: string };
+
+export function listProjectVersions(projectId: string) {
+  return invoke<ProjectVersionMeta[]>(TAURI_COMMANDS.LIST_PROJECT_VERSIONS, { projectId });
+}
+
+/** `manifest.json` of a snapshot: file name -> content blob. */
+export type VersionManifest = {
+  manifestVersion: number;
+  files: Record<string, { sha256: string; size: number }>;
+  /** `false` when the snapshot keeps its own files because hard links are unavailable. */
+  shared: boolean;
+};
+
+export function readProjectVersion(projectId: string, versionId: string) {
+  return invoke<{ meta: ProjectVersionMeta; projectJson: string; manifest?: VersionManifest | null }>(
+    TAURI_COMMANDS.READ_PROJECT_VERSION,
+    { projectId, versionId },
+  );
+}
+
+export function openVersionPdf(projectId: string, versionId: string) {
+  return invoke<void>(TAURI_COMMANDS.OPEN_VERSION_PDF, { projectId, versionId });
+}
+
+export function restoreProjectVersion(projectId: string, versionId: string) {
+  return invoke<{ restored: ProjectVersionMeta; restorePoint?: ProjectVersionMeta | null }>(
+    TAURI_COMMANDS.RESTORE_PROJECT_VERSION,
+    { projectId, versionId },
+  );
+}
+
+export function createProjectFromVersion(args: {
+  projectId: string;
+  versionId: string;
+  eventDate?: string;
+  eventVenue?: string;
+}) {
+  return invoke<{ id: string; slug: string; displayName: string }>(
+    TAURI_COMMANDS.CREATE_PROJECT_FROM_VERSION,
+    args,
+  );
+}
+
+type ValueChange<T> = { before?: T | null; after?: T | null };
+
+export type ProjectDiff = {
+  metadata: Array<{ field: string } & ValueChange<unknown>>;
+  lineup: Array<{
+    role: string;
+    before: string[];
+    after: string[];
+    added: string[];
+    removed: string[];
+    presetChanged: string[];
+  }>;
+  musiciansAdded: string[];
+  musiciansRemoved: string[];
+  backVocalsAdded: string[];
+  backVocalsRemoved: string[];
+  bandLeader?: ValueChange<string> | null;
+  talkbackOwner?: ValueChange<string> | null;
+  powerOverrides: Array<{ musicianId: string } & ValueChange<{ voltage: number; sockets: number }>>;
+};
+
+/** `from` / `to` are version ids, or `"current"` for the project file. */
+export function diffProjectVersions(projectId: string, from: string, to: string) {
+  return invoke<ProjectDiff>(TAURI_COMMANDS.DIFF_PROJECT_VERSIONS, { projectId, from, to });
+}
+
+export function setVersionSent(projectId: string, versionId: string, sent: boolean) {
+  return invoke<ProjectVersionMeta>(TAURI_COMMANDS.SET_VERSION_SENT, { projectId, versionId, sent });
+}
+
+/** Omitted fields are left unchanged; an empty note clears it. */
+export function annotateVersion(args: { projectId: string; versionId: string; note?: string; tags?: string[] }) {
+  return invoke<ProjectVersionMeta>(TAURI_COMMANDS.ANNOTATE_VERSION, args);
+}
+
+export function recordVersionSent(args: {
+  projectId: string;
+  versionId: string;
+  contact: string;
+  channel: string;
+  sentAt?: string;
+}) {
+  return invoke<ProjectVersionMeta>(TAURI_COMMANDS.RECORD_VERSION_SENT, args);
+}
+
+export type DistributionEntry = VersionSentRecord & {
+  versionId: string;
+  generatedAt: string;
+  projectRevision?: string | null;
+};
+
+/** Newest delivery first; `contact` matches case-insensitively as a substring. */
+export function listVersionDistribution(projectId: string, contact?: string) {
+  return invoke<DistributionEntry[]>(TAURI_COMMANDS.LIST_VERSION_DISTRIBUTION, { projectId, contact });
+}
+
+export function lastSentVersion(projectId: string, contact: string) {
+  return invoke<DistributionEntry | null>(TAURI_COMMANDS.LAST_SENT_VERSION, { projectId, contact });
+}
+
+/** Without `keepLast` and `keepDays` nothing is pruned. */
+export type VersionRetention = {
+  keepLast?: number | null;
+  keepDays?: number | null;
+  autoPrune: boolean;
+};
+
+export type PruneReport = {
+  dryRun: boolean;
+  pruned: Array<{ projectId: string; versionId: string; bytes: number }>;
+  kept: number;
+  reclaimableBytes: number;
+};
+
+export function getVersionRetention() {
+  return invoke<VersionRetention>(TAURI_COMMANDS.GET_VERSION_RETENTION);
+}
+
+export function setVersionRetention(retention: VersionRetention) {
+  return invoke<VersionRetention>(TAURI_COMMANDS.SET_VERSION_RETENTION, { retention });
+}
+
+/** Omit `projectId` to prune every project; `retention` overrides the stored policy. */
+export function pruneVersions(args: { projectId?: string; dryRun: boolean; retention?: VersionRetention }) {
+  return invoke<PruneReport>(TAURI_COMMANDS.PRUNE_VERSIONS, args);
+}
+
+export type ExportState =
+  | "neverExported"
+  | "upToDate"
+  | "projectChanged"
+  | "libraryChanged"
+  | "exportFileOutdated";
+
+export type ExportStatus = {
+  state: ExportState;
+  versionId?: string | null;
+  generatedAt?: string | null;
+  projectChanged: boolean;
+  /** `null` when the latest export predates recorded library hashes. */
+  libraryChanged?: boolean | null;
+  exportFileOutdated: boolean;
+};
+
+export function getExportStatus(projectId: string) {
+  return invoke<ExportStatus>(TAURI_COMMANDS.GET_EXPORT_STATUS, { projectId });
+}
+
+export type BundleRecordKind = "band" | "musician" | "contact" | "preset";
+export type BundleRecordSource = "shipped" | "library";
+export type BundleRecordRef = { kind: BundleRecordKind; id: string };
+
+export type BundleManifest = {
+  format: string;
+  formatVersion: number;
+  appVersion: string;
+  createdAt: string;
+  projectId: string;
+  records: Array<BundleRecordRef & { source: BundleRecordSource; file: string }>;
+  /** References the sender's machine could not resolve. */
+  missing: BundleRecordRef[];
+  pdf?: { fileName: string } | null;
+};
+
+export type BundlePreview = {
+  projectId: string;
+  displayName?: string | null;
+  createdAt: string;
+  hasPdf: boolean;
+  records: Array<BundleRecordRef & { source: BundleRecordSource; status: "identical" | "new" | "collision" }>;
+  missing: BundleRecordRef[];
+};
+
+export type BundleRecordResolution = BundleRecordRef &
+  ({ action: "map"; target: string } | { action: "rename"; newId?: string } | { action: "skip" });
+
+export type BundleImportResult = {
+  id: string;
+  slug: string;
+  displayName: string;
+  records: Array<
+    BundleRecordRef & { outcome: "imported" | "unchanged" | "mapped" | "renamed" | "skipped"; localId: string }
+  >;
+  pdfPath?: string | null;
+};
+
+export function exportProjectBundle(projectId: string, archivePath: string, includePdf = false) {
+  return invoke<BundleManifest>(TAURI_COMMANDS.EXPORT_PROJECT_BUNDLE, { projectId, archivePath, includePdf });
+}
+
+export function inspectProjectBundle(archivePath: string) {
+  return invoke<BundlePreview>(TAURI_COMMANDS.INSPECT_PROJECT_BUNDLE, { archivePath });
+}
+
+/** Every `collision` from `inspectProjectBundle` needs a resolution. */
+export function importProjectBundle(archivePath: string, resolutions: BundleRecordResolution[] = []) {
+  return invoke<BundleImportResult>(TAURI_COMMANDS.IMPORT_PROJECT_BUNDLE, { archivePath, resolutions });
+}
+
+export type ProjectConflict = {
+  revision: string;
+  updatedAt?: string | null;
+  json: string;
+};
+
+/**
+ * `expectedRevision` is the content hash from `readProjectDocument` or the last save. The `updatedAt`
+ * last read is still accepted for older callers but misses outside edits that left it alone.
+ * The backend stamps `createdAt`/`updatedAt`; the values sent in `json` are ignored.
+ */
+export function saveProject(args: {
+  projectId: string;
+  legacyProjectId?: string;
+  json: string;
+  expectedRevision?: string;
+}) {
+  return invoke<{ revision: string; createdAt: string; updatedAt: string }>(TAURI_COMMANDS.SAVE_PROJECT, args);
+}
+
+export type ProjectFieldError = {
+  /** Dotted path, e.g. `eventDate` or `lineup.drums`. */
+  field: string;
+  message: string;
+};
+
+export function getProjectFieldErrors(error: unknown): ProjectFieldError[] {
+  if (!error || typeof error !== "object") return [];
+  const { code, fieldErrors } = error as { code?: string; fieldErrors?: ProjectFieldError[] | null };
+  return code === "PROJECT_INVALID" && fieldErrors ? fieldErrors : [];
+}
+
+export function getProjectConflict(error: unknown): ProjectConflict | null {
+  if (!error || typeof error !== "object") return null;
+  const { code, conflict } = error as { code?: string; conflict?: ProjectConflict | null };
+  return code === "PROJECT_CONFLICT" && conflict ? conflict : null;
+}
+
+export function duplicateProject(args: { projectId: string; eventDate?: string; eventVenue?: string }) {
+  return invoke<{ id: string; slug: string; displayName: string }>(TAURI_COMMANDS.DUPLICATE_PROJECT, args);
+}
+
+export type RenameOutcome = {
+  id: string;
+  slug?: string | null;
+  updatedVersions: number;
+  renamedExports: string[];
+};
+
+export function renameProject(args: { projectId: string; newId?: string; newSlug?: string }) {
+  return invoke<RenameOutcome>(TAURI_COMMANDS.RENAME_PROJECT, args);
+}
+
+export type TrashEntry = {
+  id: string;
+  projectId: string;
+  slug?: string | null;
+  displayName?: string | null;
+  deletedAt: string;
+  projectFileName: string;
+  hasVersions: boolean;
+  exportFileNames: string[];
+};
+
+export function deleteProject(projectId: string) {
+  return invoke<TrashEntry>(TAURI_COMMANDS.DELETE_PROJECT, { projectId });
+}
+
+export function listTrash() {
+  return invoke<TrashEntry[]>(TAURI_COMMANDS.LIST_TRASH);
+}
+
+export function restoreTrashedProject(entryId: string) {
+  return invoke<{ entry: TrashEntry; warnings: string[] }>(TAURI_COMMANDS.RESTORE_TRASHED_PROJECT, {
+    entryId,
+  });
+}
+
+export function purgeTrash(entryId?: string) {
+  return invoke<number>(TAURI_COMMANDS.PURGE_TRASH, { entryId: entryId ?? null });
 }
 
 export function deleteProjectPermanently(projectId: string) {
@@ -1,8 +1,36 @@
 export const TAURI_COMMANDS = {
   LIST_BANDS: "list_bands",
   LIST_PROJECTS: "list_projects",
+  LIST_PROJECTS_WITH_DIAGNOSTICS: "list_projects_with_diagnostics",
+  QUERY_PROJECTS: "query_projects",
+  QUARANTINE_PROJECT_FILE: "quarantine_project_file",
   READ_PROJECT: "read_project",
+  READ_PROJECT_DOCUMENT: "read_project_document",
+  LIST_PROJECT_VERSIONS: "list_project_versions",
+  READ_PROJECT_VERSION: "read_project_version",
+  OPEN_VERSION_PDF: "open_version_pdf",
+  RESTORE_PROJECT_VERSION: "restore_project_version",
+  CREATE_PROJECT_FROM_VERSION: "create_project_from_version",
+  DIFF_PROJECT_VERSIONS: "diff_project_versions",
+  SET_VERSION_SENT: "set_version_sent",
+  ANNOTATE_VERSION: "annotate_version",
+  RECORD_VERSION_SENT: "record_version_sent",
+  LIST_VERSION_DISTRIBUTION: "list_version_distribution",
+  LAST_SENT_VERSION: "last_sent_version",
+  GET_VERSION_RETENTION: "get_version_retention",
+  SET_VERSION_RETENTION: "set_version_retention",
+  PRUNE_VERSIONS: "prune_versions",
+  GET_EXPORT_STATUS: "get_export_status",
+  EXPORT_PROJECT_BUNDLE: "export_project_bundle",
+  INSPECT_PROJECT_BUNDLE: "inspect_project_bundle",
+  IMPORT_PROJECT_BUNDLE: "import_project_bundle",
   SAVE_PROJECT: "save_project",
+  DUPLICATE_PROJECT: "duplicate_project",
+  RENAME_PROJECT: "rename_project",
+  DELETE_PROJECT: "delete_project",
   DELETE_PROJECT_PERMANENTLY: "delete_project_permanently",
+  LIST_TRASH: "list_trash",
+  RESTORE_TRASHED_PROJECT: "restore_trashed_project",
+  PURGE_TRASH: "purge_trash",
   GET_BAND_SETUP_DATA: "get_band_setup_data",
 } as const;
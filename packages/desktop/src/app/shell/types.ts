@@ -25,7 +25,9 @@ export type ProjectSummary = {
   purpose?: "event" | "generic" | null;
 };
 
-export type BandOption = { id: string; name: string; code?: string | null };
+/** Where a band or musician resolved from; user library entries override shipped ones by id. */
+export type RecordOrigin = "shipped" | "user";
+export type BandOption = { id: string; name: string; code?: string | null; origin?: RecordOrigin };
 export type MemberOption = { id: string; name: string };
 export type LibraryBandMember = {
   musicianId: string;
@@ -47,12 +49,16 @@ export type LibraryBand = {
   name: string;
   code: string;
   description?: string;
+  bandLeader?: string | null;
+  defaultContactId?: string | null;
   constraints: Record<string, RoleConstraint>;
   roleConstraints?: RoleLabelConstraints;
   defaultLineup?: LineupMap | null;
   members: LibraryBandMember[];
   contacts: LibraryContact[];
   messages: LibraryMessage[];
+  origin?: RecordOrigin;
+  overridesShipped?: boolean;
 };
 export type LibraryMusician = {
   id: string;
@@ -60,6 +66,8 @@ export type LibraryMusician = {
   gender?: string;
   defaultRoles: string[];
   notes?: string;
+  origin?: RecordOrigin;
+  overridesShipped?: boolean;
 };
 export type BandSetupData = {
   id: string;
@@ -0,0 +1,67 @@
+// Builds the export engine the desktop app ships under `engine/`: the export and
+// preview scripts bundled into plain ESM (no tsx, no sources), the PDF fonts the
+// renderer loads from `src/infra/pdf/fonts`, and a production-only node_modules
+// holding just the runtime dependencies. `data/` is added by tauri.conf.json.
+import fs from "node:fs/promises";
+import path from "node:path";
+import { spawnSync } from "node:child_process";
+import { build } from "esbuild";
+
+import { PROJECT_ROOT } from "../src/infra/fs/dataRoot.js";
+
+const OUT_DIR = path.join(PROJECT_ROOT, "build", "engine");
+const SCRIPTS = ["desktop_export", "desktop_preview"];
+// Packages with native parts or downloaded browsers stay real dependencies.
+const RUNTIME_DEPENDENCIES = ["puppeteer"];
+
+async function main(): Promise<void> {
+  // Pin the versions the repo lockfile resolved, so the app ships what was tested.
+  const lock = JSON.parse(await fs.readFile(path.join(PROJECT_ROOT, "package-lock.json"), "utf8")) as {
+    packages: Record<string, { version?: string }>;
+  };
+
+  await fs.rm(OUT_DIR, { recursive: true, force: true });
+  await fs.mkdir(OUT_DIR, { recursive: true });
+
+  await build({
+    entryPoints: SCRIPTS.map((name) => path.join(PROJECT_ROOT, "scripts", `${name}.ts`)),
+    outdir: path.join(OUT_DIR, "scripts"),
+    outExtension: { ".js": ".mjs" },
+    bundle: true,
+    platform: "node",
+    format: "esm",
+    target: "node20",
+    external: RUNTIME_DEPENDENCIES,
+    logLevel: "info",
+  });
+
+  await fs.cp(path.join(PROJECT_ROOT, "src", "infra", "pdf", "fonts"), path.join(OUT_DIR, "src", "infra", "pdf", "fonts"), {
+    recursive: true,
+  });
+
+  const dependencies = Object.fromEntries(
+    RUNTIME_DEPENDENCIES.map((name) => {
+      const version = lock.packages[`node_modules/${name}`]?.version;
+      if (!version) throw new Error(`Runtime dependency ${name} is missing from package-lock.json`);
+      return [name, version];
+    }),
+  );
+  await fs.writeFile(
+    path.join(OUT_DIR, "package.json"),
+    `${JSON.stringify({ name: "stagepilot-engine", private: true, type: "module", dependencies }, null, 2)}\n`,
+  );
+
+  const install = spawnSync("npm", ["install", "--omit=dev", "--no-package-lock", "--no-audit", "--no-fund"], {
+    cwd: OUT_DIR,
+    stdio: "inherit",
+    shell: process.platform === "win32",
+  });
+  if (install.status !== 0) {
+    throw new Error(`npm install for the engine failed with status ${install.status}`);
+  }
+}
+
+main().catch((err) => {
+  console.error("[build-engine] failed", err);
+  process.exit(1);
+});
@@ -1,6 +1,7 @@
 import path from "node:path";
 import { argv, exit } from "node:process";
 import { readdir } from "node:fs/promises";
+import { parseLibraryDir } from "../src/infra/fs/dataRoot.js";
 import { loadJsonFile } from "../src/infra/fs/loadJson.js";
 import type { ProjectJson } from "../src/domain/model/types.js";
 import { exportProjectPdf } from "../src/app/usecases/exportPdf.js";
@@ -9,6 +10,7 @@ import { ExportLockedError } from "../src/app/usecases/publishExportPdf.js";
 type Args = {
   projectId: string;
   userDataDir: string;
+  libraryDir: string;
 };
 
 type ExportResponse =
@@ -34,16 +36,16 @@ function parseArgs(args: string[]): Args {
     throw new Error("Invalid args: project-id or user-data-dir missing");
   }
 
-  return { projectId, userDataDir };
+  return { projectId, userDataDir, libraryDir: parseLibraryDir(args, userDataDir) };
 }
 
 async function run(): Promise<ExportResponse> {
-  const { projectId, userDataDir } = parseArgs(argv.slice(2));
+  const { projectId, userDataDir, libraryDir } = parseArgs(argv.slice(2));
   const projectPath = await resolveProjectPathById(path.join(userDataDir, "projects"), projectId);
   const project = await loadJsonFile<ProjectJson>(projectPath);
 
   try {
-    const result = await exportProjectPdf({ userDataDir, project });
+    const result = await exportProjectPdf({ userDataDir, libraryDir, project });
     return { ok: true, result };
   } catch (err) {
     if (err instanceof ExportLockedError) {
@@ -8,11 +8,12 @@ import type { ProjectJson } from "../src/domain/model/types.js";
 import { formatProjectSlug } from "../src/domain/projectNaming.js";
 import { buildDocument } from "../src/domain/pipeline/buildDocument.js";
 import { validateDocument } from "../src/domain/rules/validateDocument.js";
+import { parseLibraryDir } from "../src/infra/fs/dataRoot.js";
 import { loadJsonFile } from "../src/infra/fs/loadJson.js";
 import { loadRepository } from "../src/infra/fs/repo.js";
 import { renderPdf } from "../src/infra/pdf/pdf.js";
 
-type Args = { projectId: string; userDataDir: string };
+type Args = { projectId: string; userDataDir: string; libraryDir: string };
 type Response =
   | { ok: true; result: { previewPdfPath: string } }
   | { ok: false; code: string; message: string };
@@ -26,16 +27,16 @@ function parseArgs(args: string[]): Args {
   const userDataDir = args[userDataIndex + 1];
   if (!projectId || !userDataDir)
     throw new Error("Invalid args: project-id or user-data-dir missing");
-  return { projectId, userDataDir };
+  return { projectId, userDataDir, libraryDir: parseLibraryDir(args, userDataDir) };
 }
 
 async function run(): Promise<Response> {
-  const { projectId, userDataDir } = parseArgs(argv.slice(2));
+  const { projectId, userDataDir, libraryDir } = parseArgs(argv.slice(2));
   const projectsDir = path.join(userDataDir, "projects");
   const projectPath = await resolveProjectPathById(projectsDir, projectId);
   const rawProject = await loadJsonFile<ProjectJson>(projectPath);
   const project = normalizeProject(rawProject);
-  const repo = await loadRepository({ userDataRoot: userDataDir });
+  const repo = await loadRepository({ userDataRoot: userDataDir, libraryDir });
   const band = repo.getBand(project.bandRef);
   const vm = buildDocument(project, repo);
   validateDocument(vm);
@@ -1,13 +1,13 @@
 import { access, mkdir } from "node:fs/promises";
 import path from "node:path";
 import { isBandLeader } from "../../domain/model/bandLeader.js";
-import type { Band, Project, ProjectJson } from "../../domain/model/types.js";
+import type { Band, Contact, Project, ProjectJson } from "../../domain/model/types.js";
 import {
   formatProjectSlug,
 } from "../../domain/projectNaming.js";
 import { buildDocument } from "../../domain/pipeline/buildDocument.js";
 import { validateDocument } from "../../domain/rules/validateDocument.js";
-import { DATA_ROOT, USER_DATA_ROOT } from "../../infra/fs/dataRoot.js";
+import { USER_DATA_ROOT } from "../../infra/fs/dataRoot.js";
 import { loadJsonFile } from "../../infra/fs/loadJson.js";
 import { loadRepository } from "../../infra/fs/repo.js";
 import type { DataRepository } from "../../infra/fs/repo.js";
@@ -28,14 +28,6 @@ export interface ExportPdfResult {
   versionPath: string;
 }
 
-type ContactEntity = {
-  id: string;
-  firstName: string;
-  lastName: string;
-  phone?: string;
-  email?: string;
-};
-
 function formatCzPhone(phoneRaw: string): string {
   const s = phoneRaw.trim();
 
@@ -51,7 +43,7 @@ function formatCzPhone(phoneRaw: string): string {
 }
 
 export function formatContactLine(args: {
-  contact: ContactEntity;
+  contact: Contact;
   band: Band;
   contactMusicianId?: string;
 }): string {
@@ -104,12 +96,7 @@ export async function loadDefaultContactLine(
 ): Promise<string | undefined> {
   if (!defaultContactId) return undefined;
 
-  const contactPath = path.resolve(
-    DATA_ROOT,
-    "contacts",
-    `${defaultContactId}.json`,
-  );
-  const contact = await loadJsonFile<ContactEntity>(contactPath);
+  const contact = repo.getContact(defaultContactId);
   const contactMusicianId = resolveContactMusicianId(defaultContactId, repo);
 
   return formatContactLine({ contact, band, contactMusicianId });
@@ -131,20 +118,22 @@ export async function exportPdfFromProjectFile(
 
 export async function exportProjectPdf(args: {
   userDataDir: string;
+  libraryDir?: string;
   project: ProjectJson;
 }): Promise<ExportPdfResult> {
   const project = normalizeProject(args.project);
-  return exportPdfFromProject(project.id, project, args.userDataDir);
+  return exportPdfFromProject(project.id, project, args.userDataDir, args.libraryDir);
 }
 async function exportPdfFromProject(
   projectId: string,
   project: Project,
   outDir: string,
+  libraryDir?: string,
 ): Promise<ExportPdfResult> {
   if (project.id !== projectId) {
     throw new Error(`Project id mismatch: ${projectId} vs ${project.id}`);
   }
-  const repo = await loadRepository({ userDataRoot: outDir });
+  const repo = await loadRepository({ userDataRoot: outDir, libraryDir });
   const band = repo.getBand(project.bandRef);
 
   const vm = buildDocument(project, repo);
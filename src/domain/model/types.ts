@@ -140,6 +140,15 @@ export interface Band {
   logoFile?: string;
 }
 
+/** Kontaktní osoba uváděná v PDF (data/contacts nebo knihovna). */
+export interface Contact {
+  id: string;
+  firstName: string;
+  lastName: string;
+  phone?: string;
+  email?: string;
+}
+
 /** Muzikant: profil osoby a reference na presety, které používá. */
 export interface Musician {
   id: string;
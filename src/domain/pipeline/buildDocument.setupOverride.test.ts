@@ -54,6 +54,9 @@ describe("buildDocument setup overrides", () => {
     const repo: DataRepository = {
       getBand: () => band,
       getMusician: () => musician,
+      getContact: (id: string) => {
+        throw new Error(`unknown contact ${id}`);
+      },
       getProject: () => project,
       getPreset: (id: string) => {
         if (id === "el_bass_xlr_pedalboard") return bassPreset;
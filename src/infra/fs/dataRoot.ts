@@ -16,8 +16,22 @@ function resolveAppDataBaseDir(): string {
   return process.env.XDG_DATA_HOME || path.join(os.homedir(), ".local", "share");
 }
 
-export const USER_DATA_ROOT = path.join(
-  resolveAppDataBaseDir(),
-  TAURI_APP_ID,
-  "stagepilot",
-);
+// Mirrors the desktop `STAGEPILOT_DATA_DIR` override so CLI runs see the same storage.
+export const USER_DATA_ROOT = process.env.STAGEPILOT_DATA_DIR
+  ? path.resolve(process.env.STAGEPILOT_DATA_DIR)
+  : path.join(resolveAppDataBaseDir(), TAURI_APP_ID, "stagepilot");
+
+// Bands, musicians, contacts and presets layer the user library over `data/`.
+export function defaultLibraryDir(userDataDir: string): string {
+  return path.join(userDataDir, "library");
+}
+
+// The desktop app passes `--library-dir` to the export and preview scripts;
+// without it they read the default library of `--user-data-dir`.
+export function parseLibraryDir(args: string[], userDataDir: string): string {
+  const libraryIndex = args.indexOf("--library-dir");
+  if (libraryIndex === -1) return defaultLibraryDir(userDataDir);
+  const libraryDir = args[libraryIndex + 1];
+  if (!libraryDir) throw new Error("Invalid args: library-dir missing");
+  return libraryDir;
+}
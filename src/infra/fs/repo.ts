@@ -3,13 +3,14 @@
 // Proč? Odděluje doménovou logiku od filesystemu (domain si jen říká o entity podle id).
 
 import path from "node:path";
-import { DATA_ROOT, USER_DATA_ROOT } from "./dataRoot.js";
+import { DATA_ROOT, USER_DATA_ROOT, defaultLibraryDir } from "./dataRoot.js";
 import { listJsonFiles } from "./loadTree.js";
 import { loadJsonFile } from "./loadJson.js";
 import { getAllGroupPresetsDir, getMonitorPresetsDir, getNotesTemplatesDir } from "./assetsPaths.js";
 
 import type {
   Band,
+  Contact,
   Musician,
   Project,
   PresetEntity,
@@ -21,20 +22,48 @@ import { resolvePresetIdAlias } from "../../domain/model/presetAliases.js";
 export interface DataRepository {
   getBand(id: string): Band;
   getMusician(id: string): Musician;
+  getContact(id: string): Contact;
   getProject(id: string): Project;
   getPreset(id: string): PresetEntity;
   getNotesTemplate(id: string): NotesTemplate;
 }
 
+// Snake_case keys written by older library versions, with their current name.
+const LEGACY_KEYS: [string, string][] = [
+  ["role_constraints", "roleConstraints"],
+  ["default_lineup", "defaultLineup"],
+  ["band_leader", "bandLeader"],
+  ["default_contact_id", "defaultContactId"],
+  ["default_roles", "defaultRoles"],
+];
+const LEGACY_MEMBER_KEYS: [string, string][] = [
+  ["musician_id", "musicianId"],
+  ["is_default", "isDefault"],
+];
+
+type JsonRecord = Record<string, unknown>;
+
+// Bands, musicians, contacts and presets come from two layers, as in the desktop
+// app (src-tauri/src/data_repository.rs): shipped `data/`, then the user library
+// (`<libraryDir>/bands.json`, `musicians.json`, `contacts.json`, `presets.json`).
+// A library record with a shipped id overrides it field by field; `null` keeps
+// the shipped value.
 export async function loadRepository(options?: {
   userDataRoot?: string;
   dataRoot?: string;
+  libraryDir?: string;
 }): Promise<DataRepository> {
   const userDataRoot = options?.userDataRoot ?? USER_DATA_ROOT;
   const dataRoot = options?.dataRoot ?? DATA_ROOT;
+  const libraryDir = options?.libraryDir ?? defaultLibraryDir(userDataRoot);
 
   const projects = await loadMap<Project>(path.join(userDataRoot, "projects"));
-  const bands = await loadBandsMap(path.join(dataRoot, "bands"));
+  const bands = normalizeBands(
+    layerLibrary(
+      await loadMap<Band>(path.join(dataRoot, "bands")),
+      await loadLibraryList(path.join(libraryDir, "bands.json")),
+    ),
+  );
   const bandRefs = new Map<string, Band>();
   for (const band of bands.values()) {
     bandRefs.set(band.id, band);
@@ -42,12 +71,24 @@ export async function loadRepository(options?: {
       bandRefs.set((band as { code: string }).code.trim().toLowerCase(), band);
     }
   }
-  const musicians = await loadMap<Musician>(path.join(dataRoot, "musicians"));
+  const musicians = normalizeMusicians(
+    layerLibrary(
+      await loadMap<Musician>(path.join(dataRoot, "musicians")),
+      await loadLibraryList(path.join(libraryDir, "musicians.json")),
+    ),
+  );
+  const contacts = layerLibrary(
+    await loadMap<Contact>(path.join(dataRoot, "contacts")),
+    await loadLibraryList(path.join(libraryDir, "contacts.json")),
+  );
 
   // preset entity = preset | vocal_type | talkback_type | monitor
   const groupPresets = await loadMap<PresetEntity>(getAllGroupPresetsDir(dataRoot));
   const monitorPresets = await loadMap<PresetEntity>(getMonitorPresetsDir(dataRoot));
-  const presets = new Map<string, PresetEntity>([...groupPresets, ...monitorPresets]);
+  const presets = layerLibrary(
+    new Map<string, PresetEntity>([...groupPresets, ...monitorPresets]),
+    await loadLibraryList(path.join(libraryDir, "presets.json")),
+  );
 
   // notes templates
   const notesTemplates = await loadMap<NotesTemplate>(getNotesTemplatesDir(dataRoot));
@@ -59,14 +100,14 @@ export async function loadRepository(options?: {
       throw new Error(`Band not found: ${id}`);
     },
     getMusician: (id: string) => must(musicians, id, "Musician"),
+    getContact: (id: string) => must(contacts, id, "Contact"),
     getProject: (id: string) => must(projects, id, "Project"),
     getPreset: (id: string) => must(presets, resolvePresetIdAlias(id), "PresetEntity"),
     getNotesTemplate: (id: string) => must(notesTemplates, id, "NotesTemplate"),
   };
 }
 
-async function loadBandsMap(absDir: string): Promise<Map<string, Band>> {
-  const map = await loadMap<Band>(absDir);
+function normalizeBands(map: Map<string, Band>): Map<string, Band> {
   for (const [id, band] of map.entries()) {
     const defaultLineup = (band.defaultLineup ?? {}) as Record<
       string,
@@ -86,6 +127,73 @@ async function loadBandsMap(absDir: string): Promise<Map<string, Band>> {
   return map;
 }
 
+// Library musicians carry `name` and `defaultRoles`; the pipeline reads
+// `firstName`, `group` and `presets`.
+function normalizeMusicians(map: Map<string, Musician>): Map<string, Musician> {
+  for (const [id, musician] of map.entries()) {
+    const record = musician as Musician & { name?: unknown; defaultRoles?: unknown };
+    const roles = Array.isArray(record.defaultRoles) ? record.defaultRoles : [];
+    const firstRole = typeof roles[0] === "string" ? roles[0].trim().toLowerCase() : undefined;
+    map.set(id, {
+      ...record,
+      firstName: record.firstName ?? (typeof record.name === "string" ? record.name.trim() : ""),
+      lastName: record.lastName ?? "",
+      group: record.group ?? (firstRole as Musician["group"]),
+      presets: record.presets ?? [],
+    });
+  }
+  return map;
+}
+
+// A missing library file is an empty layer; anything but a JSON list is an error.
+async function loadLibraryList(absPath: string): Promise<JsonRecord[]> {
+  const items = await loadJsonFile<unknown>(absPath).catch((error: NodeJS.ErrnoException) => {
+    if (error.code === "ENOENT") return [];
+    if (error instanceof SyntaxError) throw new Error(`Invalid JSON in ${absPath}: ${error.message}`);
+    throw error;
+  });
+  if (!Array.isArray(items)) {
+    throw new Error(`${absPath} is not a JSON list`);
+  }
+  return items
+    .filter((item): item is JsonRecord => typeof item === "object" && item !== null)
+    .map(normalizeLegacyKeys);
+}
+
+function renameKeys(fields: JsonRecord, keys: [string, string][]): void {
+  for (const [legacy, current] of keys) {
+    if (legacy in fields) {
+      if (!(current in fields)) fields[current] = fields[legacy];
+      delete fields[legacy];
+    }
+  }
+}
+
+function normalizeLegacyKeys(record: JsonRecord): JsonRecord {
+  const fields = { ...record };
+  renameKeys(fields, LEGACY_KEYS);
+  if (Array.isArray(fields.members)) {
+    fields.members = fields.members.map((member: unknown) => {
+      if (typeof member !== "object" || member === null) return member;
+      const memberFields = { ...(member as JsonRecord) };
+      renameKeys(memberFields, LEGACY_MEMBER_KEYS);
+      return memberFields;
+    });
+  }
+  return fields;
+}
+
+function layerLibrary<T extends object>(shipped: Map<string, T>, library: JsonRecord[]): Map<string, T> {
+  const map = new Map(shipped);
+  for (const record of library) {
+    const id = typeof record.id === "string" ? record.id.trim() : "";
+    if (!id) continue;
+    const fields = Object.fromEntries(Object.entries(record).filter(([, value]) => value !== null));
+    map.set(id, { ...(map.get(id) ?? {}), ...fields, id } as T);
+  }
+  return map;
+}
+
 async function loadMap<T>(absDir: string): Promise<Map<string, T>> {
   const files = await listJsonFiles(absDir);
   const map = new Map<string, T>();
@@ -0,0 +1,109 @@
+import fs from "node:fs/promises";
+import os from "node:os";
+import path from "node:path";
+
+import { afterEach, describe, expect, it } from "vitest";
+
+import { loadRepository } from "./repo.js";
+
+const tmpDirs: string[] = [];
+
+afterEach(async () => {
+  await Promise.all(
+    tmpDirs.splice(0).map(async (dir) => {
+      await fs.rm(dir, { recursive: true, force: true });
+    }),
+  );
+});
+
+async function makeRoot(): Promise<string> {
+  const root = await fs.mkdtemp(path.join(os.tmpdir(), "stagepilot-library-"));
+  tmpDirs.push(root);
+  return root;
+}
+
+async function writeJson(file: string, value: unknown): Promise<void> {
+  await fs.mkdir(path.dirname(file), { recursive: true });
+  await fs.writeFile(file, JSON.stringify(value));
+}
+
+/** Shipped band `band`, bassist `b1`, preset `di` and contact `b1`, with library records on top. */
+async function makeLayers(): Promise<{ dataRoot: string; userDataRoot: string }> {
+  const root = await makeRoot();
+  const dataRoot = path.join(root, "data");
+  const userDataRoot = path.join(root, "user");
+  await fs.mkdir(path.join(userDataRoot, "projects"), { recursive: true });
+  await writeJson(path.join(dataRoot, "bands", "band.json"), {
+    id: "band",
+    code: "BND",
+    name: "Band",
+    bandLeader: "b1",
+    defaultLineup: { bass: "b1" },
+  });
+  await writeJson(path.join(dataRoot, "musicians", "bass", "b1.json"), {
+    id: "b1",
+    firstName: "Jan",
+    lastName: "Novak",
+    group: "bass",
+    presets: [],
+  });
+  await writeJson(path.join(dataRoot, "assets", "presets", "groups", "bass", "di.json"), {
+    type: "preset",
+    id: "di",
+    label: "DI",
+  });
+  await writeJson(path.join(dataRoot, "contacts", "b1.json"), {
+    id: "b1",
+    firstName: "Jan",
+    lastName: "Novak",
+  });
+  const libraryDir = path.join(userDataRoot, "library");
+  await writeJson(path.join(libraryDir, "bands.json"), [
+    { id: "band", name: "Renamed", band_leader: null },
+    { id: "mine", code: "MINE", name: "Mine", default_lineup: { keys: "u1" } },
+  ]);
+  await writeJson(path.join(libraryDir, "musicians.json"), [
+    { id: "u1", name: "Eva", default_roles: ["Keys"] },
+  ]);
+  await writeJson(path.join(libraryDir, "presets.json"), [{ id: "di", label: "DI box" }]);
+  await writeJson(path.join(libraryDir, "contacts.json"), [
+    { id: "u1", firstName: "Eva", lastName: "Svobodova" },
+  ]);
+  return { dataRoot, userDataRoot };
+}
+
+describe("loadRepository library layering", () => {
+  it("overrides shipped records field by field and keeps shipped values for null", async () => {
+    const repo = await loadRepository(await makeLayers());
+
+    const band = repo.getBand("bnd");
+    expect(band.name).toBe("Renamed");
+    expect(band.bandLeader).toBe("b1");
+    expect(band.defaultLineup).toEqual({ bass: "b1" });
+    expect(repo.getPreset("di")).toMatchObject({ type: "preset", label: "DI box" });
+  });
+
+  it("resolves records that exist only in the library", async () => {
+    const repo = await loadRepository(await makeLayers());
+
+    expect(repo.getBand("MINE").defaultLineup).toEqual({ keys: "u1" });
+    expect(repo.getMusician("u1")).toMatchObject({ firstName: "Eva", group: "keys", presets: [] });
+    expect(repo.getContact("u1").lastName).toBe("Svobodova");
+    expect(repo.getContact("b1").lastName).toBe("Novak");
+  });
+
+  it("reads the library from libraryDir when given", async () => {
+    const layers = await makeLayers();
+    const repo = await loadRepository({ ...layers, libraryDir: path.join(layers.userDataRoot, "elsewhere") });
+
+    expect(repo.getBand("band").name).toBe("Band");
+    expect(() => repo.getMusician("u1")).toThrow("Musician not found: u1");
+  });
+
+  it("rejects a library file that is not a JSON list", async () => {
+    const layers = await makeLayers();
+    await writeJson(path.join(layers.userDataRoot, "library", "presets.json"), { id: "di" });
+
+    await expect(loadRepository(layers)).rejects.toThrow("is not a JSON list");
+  });
+});
@@ -22,6 +22,13 @@ export type ProjectSnapshotMeta = {
     metaJson: string;
     pdf: string;
   };
+  /** Set by the desktop app; sent versions survive retention pruning. */
+  sent?: boolean;
+  tags?: string[];
+  /** Distribution log, oldest first. */
+  sentTo?: Array<{ contact: string; channel: string; sentAt: string }>;
+  /** Recorded by the desktop app after export; see `get_export_status`. */
+  sourceHashes?: { project: string; library: string };
 };
 
 type CreateProjectVersionArgs = {